@@ -0,0 +1,102 @@
+// ==========================================================================
+// MÓDULO: access_control.rs
+// Descrição: Controle de acesso por papéis compartilhado pelos contratos
+// ==========================================================================
+
+multiversx_sc::imports!();
+multiversx_sc::derive_imports!();
+
+// Papéis disponíveis; o owner do contrato sempre possui todos eles
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Role {
+    Admin,       // endereços, tokens, fundos e gestão de papéis
+    RiskManager, // taxas, limites e parâmetros de risco
+    Pauser,      // pausa e retomada do contrato
+    Oracle,      // preços e pontuações externas
+    Keeper,      // manutenção de empréstimos vencidos ou inadimplentes
+    Underwriter, // aprovação de pedidos de empréstimo acima do limite automático
+}
+
+#[multiversx_sc::module]
+pub trait AccessControlModule {
+    // Concede um papel a um endereço (owner ou Admin)
+    #[endpoint(grantRole)]
+    fn grant_role(&self, role: Role, address: ManagedAddress) {
+        self.require_role_manager();
+        require!(!address.is_zero(), "Endereço não pode ser zero");
+        require!(
+            self.role_members(role).insert(address.clone()),
+            "Endereço já possui este papel"
+        );
+
+        self.role_granted_event(role, &address, &self.blockchain().get_caller());
+    }
+
+    // Revoga um papel de um endereço (owner ou Admin)
+    #[endpoint(revokeRole)]
+    fn revoke_role(&self, role: Role, address: ManagedAddress) {
+        self.require_role_manager();
+        require!(
+            self.role_members(role).swap_remove(&address),
+            "Endereço não possui este papel"
+        );
+
+        self.role_revoked_event(role, &address, &self.blockchain().get_caller());
+    }
+
+    // Verifica se um endereço recebeu o papel explicitamente
+    #[view(hasRole)]
+    fn has_role(&self, role: Role, address: ManagedAddress) -> bool {
+        self.role_members(role).contains(&address)
+    }
+
+    // Lista os endereços que possuem um papel
+    #[view(getRoleMembers)]
+    fn get_role_members(&self, role: Role) -> MultiValueEncoded<ManagedAddress> {
+        self.role_members(role).iter().collect()
+    }
+
+    // Exige que o chamador seja o owner ou possua o papel
+    fn require_role(&self, role: Role) {
+        let caller = self.blockchain().get_caller();
+        require!(
+            self.is_owner_or_has_role(role, &caller),
+            "Caller does not have the required role"
+        );
+    }
+
+    fn is_owner_or_has_role(&self, role: Role, address: &ManagedAddress) -> bool {
+        *address == self.blockchain().get_owner_address()
+            || self.role_members(role).contains(address)
+    }
+
+    fn require_role_manager(&self) {
+        let caller = self.blockchain().get_caller();
+        require!(
+            self.is_owner_or_has_role(Role::Admin, &caller),
+            "Only owner or admin can manage roles"
+        );
+    }
+
+    // Eventos
+    #[event("role_granted")]
+    fn role_granted_event(
+        &self,
+        #[indexed] role: Role,
+        #[indexed] address: &ManagedAddress,
+        #[indexed] granted_by: &ManagedAddress,
+    );
+
+    #[event("role_revoked")]
+    fn role_revoked_event(
+        &self,
+        #[indexed] role: Role,
+        #[indexed] address: &ManagedAddress,
+        #[indexed] revoked_by: &ManagedAddress,
+    );
+
+    // Membros de cada papel
+    #[storage_mapper("role_members")]
+    fn role_members(&self, role: Role) -> UnorderedSetMapper<ManagedAddress>;
+}
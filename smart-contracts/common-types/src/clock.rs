@@ -0,0 +1,28 @@
+// ==========================================================================
+// MÓDULO: clock.rs
+// Descrição: Fonte de tempo única dos contratos. O ajuste do relógio pelos
+//            testes só é compilado com a feature `testing`; o wasm de produção
+//            lê sempre o timestamp do bloco
+// ==========================================================================
+
+multiversx_sc::imports!();
+
+#[multiversx_sc::module]
+pub trait ClockModule {
+    // Timestamp atual em segundos; toda regra de negócio lê o tempo por aqui
+    fn current_timestamp(&self) -> u64 {
+        #[cfg(feature = "testing")]
+        {
+            let warped = self.clock_override().get();
+            if warped > 0 {
+                return warped;
+            }
+        }
+        self.blockchain().get_block_timestamp()
+    }
+
+    // Timestamp fixado pelos testes de cenário (zero = tempo do bloco).
+    // Sem a feature `testing` este valor nunca é lido
+    #[storage_mapper("clock_override")]
+    fn clock_override(&self) -> SingleValueMapper<u64>;
+}
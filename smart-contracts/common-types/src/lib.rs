@@ -4,6 +4,11 @@
 multiversx_sc::imports!();
 multiversx_sc::derive_imports!();
 
+pub mod access_control;
+pub mod clock;
+pub mod multisig;
+pub mod storage_version;
+
 // Structs com anotações na ordem correta conforme documentação
 #[multiversx_sc::derive::type_abi]
 #[derive(TopEncode, TopDecode, NestedEncode, NestedDecode)]
@@ -27,13 +32,24 @@ pub struct Loan<M: ManagedTypeApi> {
     pub collateral_amount: BigUint<M>,
 }
 
+// Decisão de crédito do ReputationScore (getCreditDecision), devolvida
+// ao LoanController em uma única chamada
+#[multiversx_sc::derive::type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone, PartialEq, Debug)]
+pub struct CreditDecision<M: ManagedTypeApi> {
+    pub eligible: bool,           // pontuação >= pontuação mínima pedida
+    pub within_limit: bool,       // valor pedido <= valor máximo
+    pub max_amount: BigUint<M>,
+    pub score: u64,
+}
+
 // Interfaces com trait bounds necessários
 pub trait ILiquidityPool: multiversx_sc::contract_base::ContractBase {
     // Fornece fundos para um empréstimo
     fn provide_funds_for_loan(&self, amount: BigUint<Self::Api>, token_id: TokenIdentifier<Self::Api>);
     
-    // Recebe pagamento de empréstimo
-    fn receive_loan_repayment(&self);
+    // Recebe pagamento de empréstimo (principal + juros)
+    fn receive_loan_repayment(&self, principal: BigUint<Self::Api>);
     
     // Obtém liquidez total disponível
     fn get_total_liquidity(&self) -> BigUint<Self::Api>;
@@ -53,6 +69,11 @@ pub trait IReputationScore: multiversx_sc::contract_base::ContractBase {
     fn update_score_after_default(&self, address: &ManagedAddress<Self::Api>, amount: &BigUint<Self::Api>);
 }
 
+pub trait IPriceSource: multiversx_sc::contract_base::ContractBase {
+    // Preço de 1 unidade de base_token em unidades de quote_token (escala 10^18)
+    fn get_price(&self, base_token: EgldOrEsdtTokenIdentifier<Self::Api>, quote_token: EgldOrEsdtTokenIdentifier<Self::Api>) -> BigUint<Self::Api>;
+}
+
 pub trait ILoanController: multiversx_sc::contract_base::ContractBase {
     // Solicita um empréstimo
     fn request_loan(&self, amount: BigUint<Self::Api>, token_id: TokenIdentifier<Self::Api>, duration_days: u64) -> u64;
@@ -0,0 +1,264 @@
+// ==========================================================================
+// MÓDULO: multisig.rs
+// Descrição: Aprovação M-de-N para operações destrutivas compartilhada pelos
+//            contratos (destruição, saque de emergência, reservas, oráculos)
+// ==========================================================================
+
+multiversx_sc::imports!();
+multiversx_sc::derive_imports!();
+
+// Propostas não executadas dentro deste prazo expiram (3 dias)
+pub const MULTISIG_PROPOSAL_LIFETIME: u64 = 3 * 24 * 60 * 60;
+
+// Operações que exigem aprovação do conjunto de signatários.
+// Cada contrato executa as que lhe dizem respeito em performAction
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub enum MultisigAction<M: ManagedTypeApi> {
+    ContractDestruction,
+    EmergencyWithdraw,
+    UseReserves {
+        target: ManagedAddress<M>,
+        amount: BigUint<M>,
+    },
+    SetOracle {
+        address: ManagedAddress<M>,
+    },
+    AddSigner {
+        signer: ManagedAddress<M>,
+    },
+    RemoveSigner {
+        signer: ManagedAddress<M>,
+    },
+    ChangeQuorum {
+        quorum: u32,
+    },
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct MultisigProposal<M: ManagedTypeApi> {
+    pub action: MultisigAction<M>,
+    pub proposer: ManagedAddress<M>,
+    pub created_at: u64,
+    pub expires_at: u64,
+}
+
+#[multiversx_sc::module]
+pub trait MultisigModule: crate::clock::ClockModule {
+    // Configuração inicial do conjunto de signatários (somente owner, uma vez).
+    // Depois disso, signatários e quórum só mudam por proposta aprovada
+    #[endpoint(setupMultisig)]
+    fn setup_multisig(&self, quorum: u32, signers: MultiValueEncoded<ManagedAddress>) {
+        require!(
+            self.blockchain().get_caller() == self.blockchain().get_owner_address(),
+            "Only owner can call this function"
+        );
+        require!(self.multisig_signers().is_empty(), "Multisig já configurado");
+
+        for signer in signers {
+            require!(!signer.is_zero(), "Endereço não pode ser zero");
+            self.multisig_signers().insert(signer);
+        }
+        self.require_valid_quorum(quorum, self.multisig_signers().len());
+        self.multisig_quorum().set(quorum);
+    }
+
+    // Propõe uma operação; o proponente já conta como primeira aprovação
+    #[endpoint(proposeAction)]
+    fn propose_action(&self, action: MultisigAction<Self::Api>) -> u64 {
+        let caller = self.require_multisig_signer();
+        let now = self.current_timestamp();
+
+        let action_id = self.multisig_proposal_count().get() + 1;
+        self.multisig_proposal_count().set(action_id);
+        self.multisig_proposals(action_id).set(MultisigProposal {
+            action,
+            proposer: caller.clone(),
+            created_at: now,
+            expires_at: now + MULTISIG_PROPOSAL_LIFETIME,
+        });
+        self.multisig_approvals(action_id).insert(caller.clone());
+
+        self.action_proposed_event(action_id, &caller);
+        self.action_approved_event(action_id, &caller);
+        action_id
+    }
+
+    #[endpoint(approveAction)]
+    fn approve_action(&self, action_id: u64) {
+        let caller = self.require_multisig_signer();
+        self.require_open_proposal(action_id);
+        require!(
+            self.multisig_approvals(action_id).insert(caller.clone()),
+            "Signatário já aprovou esta proposta"
+        );
+
+        self.action_approved_event(action_id, &caller);
+    }
+
+    #[endpoint(revokeApproval)]
+    fn revoke_approval(&self, action_id: u64) {
+        let caller = self.require_multisig_signer();
+        self.require_open_proposal(action_id);
+        require!(
+            self.multisig_approvals(action_id).swap_remove(&caller),
+            "Signatário não aprovou esta proposta"
+        );
+
+        self.approval_revoked_event(action_id, &caller);
+    }
+
+    // Descarta propostas expiradas ou sem nenhuma aprovação
+    #[endpoint(discardAction)]
+    fn discard_action(&self, action_id: u64) {
+        self.require_multisig_signer();
+        require!(!self.multisig_proposals(action_id).is_empty(), "Proposta não existe");
+        let proposal = self.multisig_proposals(action_id).get();
+        require!(
+            self.current_timestamp() > proposal.expires_at
+                || self.valid_approval_count(action_id) == 0,
+            "Proposta ainda possui aprovações válidas"
+        );
+
+        self.clear_proposal(action_id);
+        self.action_discarded_event(action_id);
+    }
+
+    #[view(getMultisigSigners)]
+    fn get_multisig_signers(&self) -> MultiValueEncoded<ManagedAddress> {
+        self.multisig_signers().iter().collect()
+    }
+
+    #[view(getMultisigQuorum)]
+    fn get_multisig_quorum(&self) -> u32 {
+        self.multisig_quorum().get()
+    }
+
+    #[view(getProposal)]
+    fn get_proposal(&self, action_id: u64) -> OptionalValue<MultisigProposal<Self::Api>> {
+        if self.multisig_proposals(action_id).is_empty() {
+            return OptionalValue::None;
+        }
+        OptionalValue::Some(self.multisig_proposals(action_id).get())
+    }
+
+    #[view(getActionApprovals)]
+    fn get_action_approvals(&self, action_id: u64) -> MultiValueEncoded<ManagedAddress> {
+        self.multisig_approvals(action_id).iter().collect()
+    }
+
+    #[view(isQuorumReached)]
+    fn is_quorum_reached(&self, action_id: u64) -> bool {
+        !self.multisig_proposals(action_id).is_empty()
+            && self.valid_approval_count(action_id) >= self.multisig_quorum().get() as usize
+    }
+
+    // Consome uma proposta aprovada e devolve a ação para o contrato executar
+    fn take_approved_action(&self, action_id: u64) -> MultisigAction<Self::Api> {
+        self.require_multisig_signer();
+        self.require_open_proposal(action_id);
+        require!(self.is_quorum_reached(action_id), "Quórum de aprovações não atingido");
+
+        let proposal = self.multisig_proposals(action_id).get();
+        self.clear_proposal(action_id);
+        self.action_performed_event(action_id);
+        proposal.action
+    }
+
+    // Aplica ações sobre o próprio conjunto de signatários; retorna false
+    // para as ações que cabem ao contrato
+    fn perform_signer_action(&self, action: &MultisigAction<Self::Api>) -> bool {
+        match action {
+            MultisigAction::AddSigner { signer } => {
+                require!(!signer.is_zero(), "Endereço não pode ser zero");
+                require!(self.multisig_signers().insert(signer.clone()), "Endereço já é signatário");
+            },
+            MultisigAction::RemoveSigner { signer } => {
+                require!(self.multisig_signers().swap_remove(signer), "Endereço não é signatário");
+                self.require_valid_quorum(self.multisig_quorum().get(), self.multisig_signers().len());
+            },
+            MultisigAction::ChangeQuorum { quorum } => {
+                self.require_valid_quorum(*quorum, self.multisig_signers().len());
+                self.multisig_quorum().set(*quorum);
+            },
+            _ => return false,
+        }
+        true
+    }
+
+    // Setters diretos de operações sensíveis só valem antes do multisig existir
+    fn require_multisig_not_configured(&self) {
+        require!(
+            self.multisig_signers().is_empty(),
+            "Operação exige aprovação multisig: use proposeAction"
+        );
+    }
+
+    fn require_multisig_signer(&self) -> ManagedAddress {
+        let caller = self.blockchain().get_caller();
+        require!(self.multisig_signers().contains(&caller), "Apenas signatários do multisig");
+        caller
+    }
+
+    fn require_open_proposal(&self, action_id: u64) {
+        require!(!self.multisig_proposals(action_id).is_empty(), "Proposta não existe");
+        require!(
+            self.current_timestamp() <= self.multisig_proposals(action_id).get().expires_at,
+            "Proposta expirou"
+        );
+    }
+
+    fn require_valid_quorum(&self, quorum: u32, signer_count: usize) {
+        require!(
+            quorum > 0 && quorum as usize <= signer_count,
+            "Quórum deve estar entre 1 e o número de signatários"
+        );
+    }
+
+    // Aprovações de quem deixou de ser signatário não contam
+    fn valid_approval_count(&self, action_id: u64) -> usize {
+        self.multisig_approvals(action_id)
+            .iter()
+            .filter(|signer| self.multisig_signers().contains(signer))
+            .count()
+    }
+
+    fn clear_proposal(&self, action_id: u64) {
+        self.multisig_proposals(action_id).clear();
+        self.multisig_approvals(action_id).clear();
+    }
+
+    // Eventos
+    #[event("action_proposed")]
+    fn action_proposed_event(&self, #[indexed] action_id: u64, #[indexed] proposer: &ManagedAddress);
+
+    #[event("action_approved")]
+    fn action_approved_event(&self, #[indexed] action_id: u64, #[indexed] signer: &ManagedAddress);
+
+    #[event("approval_revoked")]
+    fn approval_revoked_event(&self, #[indexed] action_id: u64, #[indexed] signer: &ManagedAddress);
+
+    #[event("action_performed")]
+    fn action_performed_event(&self, #[indexed] action_id: u64);
+
+    #[event("action_discarded")]
+    fn action_discarded_event(&self, #[indexed] action_id: u64);
+
+    // Signatários e quórum
+    #[storage_mapper("multisig_signers")]
+    fn multisig_signers(&self) -> UnorderedSetMapper<ManagedAddress>;
+
+    #[storage_mapper("multisig_quorum")]
+    fn multisig_quorum(&self) -> SingleValueMapper<u32>;
+
+    // Propostas (id sequencial) e aprovações por signatário
+    #[storage_mapper("multisig_proposal_count")]
+    fn multisig_proposal_count(&self) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("multisig_proposals")]
+    fn multisig_proposals(&self, action_id: u64) -> SingleValueMapper<MultisigProposal<Self::Api>>;
+
+    #[storage_mapper("multisig_approvals")]
+    fn multisig_approvals(&self, action_id: u64) -> UnorderedSetMapper<ManagedAddress>;
+}
@@ -0,0 +1,52 @@
+// ==========================================================================
+// MÓDULO: storage_version.rs
+// Descrição: Versão do layout de armazenamento e migrações executadas no
+//            #[upgrade] de cada contrato, em ordem e uma única vez
+// ==========================================================================
+
+multiversx_sc::imports!();
+
+#[multiversx_sc::module]
+pub trait StorageVersionModule {
+    // Versão do layout gravado (zero = contrato implantado antes do versionamento)
+    #[view(getStorageVersion)]
+    fn get_storage_version(&self) -> u32 {
+        self.storage_version().get()
+    }
+
+    // Contratos novos já nascem no layout atual
+    fn init_storage_version(&self, current_version: u32) {
+        self.storage_version().set(current_version);
+    }
+
+    // Recusa o upgrade para um código mais antigo que o armazenamento gravado
+    fn require_supported_storage_version(&self, current_version: u32) {
+        require!(
+            self.storage_version().get() <= current_version,
+            "Versão de armazenamento mais nova que o código"
+        );
+    }
+
+    // Corpo comum do #[upgrade]: recusa código mais antigo que o armazenamento e
+    // roda, em ordem e uma única vez, as migrações até `current_version`.
+    // `migrate` recebe a versão de destino de cada passo
+    fn run_storage_migrations<F: Fn(u32)>(&self, current_version: u32, migrate: F) {
+        self.require_supported_storage_version(current_version);
+        for version in (self.storage_version().get() + 1)..=current_version {
+            migrate(version);
+            self.complete_migration(version);
+        }
+    }
+
+    fn complete_migration(&self, version: u32) {
+        let from_version = self.storage_version().replace(version);
+        self.storage_migrated_event(from_version, version);
+    }
+
+    // Eventos
+    #[event("storage_migrated")]
+    fn storage_migrated_event(&self, #[indexed] from_version: u32, #[indexed] to_version: u32);
+
+    #[storage_mapper("storage_version")]
+    fn storage_version(&self) -> SingleValueMapper<u32>;
+}
@@ -22,6 +22,11 @@ multiversx_sc::imports!();
 
 pub mod debt_token_proxy;
 
+use common_types::access_control::Role;
+
+// Versão atual do layout de armazenamento (ver StorageVersionModule)
+const STORAGE_VERSION: u32 = 1;
+
 #[type_abi]
 #[derive(TopEncode, TopDecode, PartialEq, Clone, Copy)]
 pub enum Status {
@@ -31,7 +36,11 @@ pub enum Status {
 }
 
 #[multiversx_sc::contract]
-pub trait DebtToken {
+pub trait DebtToken:
+    common_types::access_control::AccessControlModule
+    + common_types::clock::ClockModule
+    + common_types::storage_version::StorageVersionModule
+{
     // ============================
     // Inicialização e configuração
     // ============================
@@ -45,15 +54,23 @@ pub trait DebtToken {
         // Inicializa o ID do token como vazio
         self.debt_token_id().set_if_empty(TokenIdentifier::from_esdt_bytes(&[]));
         // Inicializa a oferta total como zero
-        self.total_supply().set_if_empty(&BigUint::zero());
+        self.total_supply().set_if_empty(BigUint::zero());
+        self.init_storage_version(STORAGE_VERSION);
+    }
+
+    // Executa as migrações pendentes em ordem; cada uma roda uma única vez
+    #[upgrade]
+    fn upgrade(&self) {
+        // v1: layout inicial, nada a converter
+        self.run_storage_migrations(STORAGE_VERSION, |_| {});
     }
 
     /// Emite o token de dívida como um NFT/SFT
-    /// Somente o proprietário do contrato pode chamar esta função
+    /// Somente o proprietário do contrato ou um Admin pode chamar esta função
     #[payable("EGLD")]
-    #[only_owner]
     #[endpoint(issueDebtToken)]
     fn issue_debt_token(&self) {
+        self.require_role(Role::Admin);
         require!(self.debt_token_id().is_empty(), "Token already issued");
         let token_name = ManagedBuffer::from("DebtToken");
         let token_ticker = ManagedBuffer::from("DEBT");
@@ -118,7 +135,7 @@ pub trait DebtToken {
         require!(!self.debt_token_id().is_empty(), "Debt token not issued yet");
         require!(!borrower.is_zero(), "Borrower cannot be zero address");
         require!(amount > BigUint::zero(), "Amount must be greater than zero");
-        require!(due_timestamp > self.blockchain().get_block_timestamp(), "Due date must be in the future");
+        require!(due_timestamp > self.current_timestamp(), "Due date must be in the future");
 
         // Verificar se já existe um NFT para este empréstimo
         require!(self.loan_to_debt_nft(loan_id).is_empty(), "NFT already exists for this loan");
@@ -153,7 +170,9 @@ pub trait DebtToken {
         nft_nonce
     }
 
-    /// Queima o NFT quando o empréstimo é pago ou inadimplente
+    /// Queima o NFT quando o empréstimo é pago ou liquidado
+    /// O controlador devolve o NFT junto com a chamada
+    #[payable("*")]
     #[endpoint(burnDebtNft)]
     fn burn_debt_nft(&self, loan_id: u64) {
         // Verificações de segurança
@@ -178,18 +197,34 @@ pub trait DebtToken {
         // Limpar mapeamentos
         self.debt_nft_to_loan(nft_nonce).clear();
         self.loan_to_debt_nft(loan_id).clear();
+        self.debt_nft_defaulted(nft_nonce).clear();
         
         // Emitir evento para auditoria
         self.debt_nft_burned_event(loan_id, nft_nonce);
     }
 
-    //=====================================
+    /// Marca o NFT de um empréstimo inadimplente, mantendo-o em circulação
+    #[endpoint(markDebtNftDefaulted)]
+    fn mark_debt_nft_defaulted(&self, loan_id: u64) {
+        // Verificações de segurança
+        let caller = self.blockchain().get_caller();
+        require!(
+            caller == self.loan_controller_address().get(),
+            "Only loan controller can mark debt NFTs"
+        );
+        require!(
+            !self.loan_to_debt_nft(loan_id).is_empty(),
+            "No NFT exists for this loan"
+        );
 
-    //==========private===================
-    fn get_current_time(&self) -> u64 {
-        self.blockchain().get_block_timestamp()
+        let nft_nonce = self.loan_to_debt_nft(loan_id).get();
+        self.debt_nft_defaulted(nft_nonce).set(true);
+
+        // Emitir evento para auditoria
+        self.debt_nft_defaulted_event(loan_id, nft_nonce);
     }
-    //==========private===================
+
+    //=====================================
 
     // No seu contrato, adicione temporariamente:
     #[view]
@@ -209,7 +244,7 @@ pub trait DebtToken {
 
     #[view]
     fn status(&self) -> Status {
-        if self.get_current_time() <= self.deadline().get() {
+        if self.current_timestamp() <= self.deadline().get() {
             Status::FundingPeriod
         } else if self.get_current_funds() >= self.target().get() {
             Status::Successful
@@ -452,6 +487,12 @@ pub trait DebtToken {
         self.loan_to_debt_nft(loan_id).get()
     }
     
+    /// Indica se o NFT pertence a um empréstimo inadimplente
+    #[view(isDebtNftDefaulted)]
+    fn is_debt_nft_defaulted(&self, nft_nonce: u64) -> bool {
+        self.debt_nft_defaulted(nft_nonce).get()
+    }
+
     /// Retorna o ID do empréstimo associado a um NFT, ou zero se não existir
     #[view(getNftLoanId)]
     fn get_nft_loan_id(&self, nft_nonce: u64) -> u64 {
@@ -487,7 +528,7 @@ pub trait DebtToken {
         attributes.append(&ManagedBuffer::from("|due_timestamp:"));
         attributes.append(&self.number_to_managed_buffer(due_timestamp));
         attributes.append(&ManagedBuffer::from("|created_timestamp:"));
-        attributes.append(&self.number_to_managed_buffer(self.blockchain().get_block_timestamp()));
+        attributes.append(&self.number_to_managed_buffer(self.current_timestamp()));
         attributes
     }
 
@@ -551,6 +592,13 @@ pub trait DebtToken {
         #[indexed] nft_nonce: u64
     );
     
+    #[event("debt_nft_defaulted")]
+    fn debt_nft_defaulted_event(
+        &self,
+        #[indexed] loan_id: u64,
+        #[indexed] nft_nonce: u64
+    );
+
     #[event("transfer")]
     fn transfer_event(
         &self,
@@ -601,6 +649,10 @@ pub trait DebtToken {
     #[storage_mapper("loan_to_debt_nft")]
     fn loan_to_debt_nft(&self, loan_id: u64) -> SingleValueMapper<u64>;
     
+    /// NFTs de empréstimos inadimplentes
+    #[storage_mapper("debt_nft_defaulted")]
+    fn debt_nft_defaulted(&self, nft_nonce: u64) -> SingleValueMapper<bool>;
+
     /// Mapeamento de allowances para ERC20
     #[storage_mapper("allowances")]
     fn allowances(&self, owner: &ManagedAddress, spender: &ManagedAddress) -> SingleValueMapper<BigUint>;
@@ -105,16 +105,43 @@ where
             .original_result()
     }
 
-    /// Queima o NFT quando o empréstimo é pago ou inadimplente 
+    /// Queima o NFT quando o empréstimo é pago ou liquidado 
+    /// O controlador devolve o NFT junto com a chamada 
     pub fn burn_debt_nft<
         Arg0: ProxyArg<u64>,
     >(
         self,
         loan_id: Arg0,
+    ) -> TxTypedCall<Env, From, To, (), Gas, ()> {
+        self.wrapped_tx
+            .raw_call("burnDebtNft")
+            .argument(&loan_id)
+            .original_result()
+    }
+
+    /// Marca o NFT de um empréstimo inadimplente, mantendo-o em circulação 
+    pub fn mark_debt_nft_defaulted<
+        Arg0: ProxyArg<u64>,
+    >(
+        self,
+        loan_id: Arg0,
     ) -> TxTypedCall<Env, From, To, NotPayable, Gas, ()> {
         self.wrapped_tx
             .payment(NotPayable)
-            .raw_call("burnDebtNft")
+            .raw_call("markDebtNftDefaulted")
+            .argument(&loan_id)
+            .original_result()
+    }
+
+    pub fn get_loan_nft_id_for_test<
+        Arg0: ProxyArg<u64>,
+    >(
+        self,
+        loan_id: Arg0,
+    ) -> TxTypedCall<Env, From, To, NotPayable, Gas, u64> {
+        self.wrapped_tx
+            .payment(NotPayable)
+            .raw_call("get_loan_nft_id_for_test")
             .argument(&loan_id)
             .original_result()
     }
@@ -357,6 +384,20 @@ where
             .original_result()
     }
 
+    /// Indica se o NFT pertence a um empréstimo inadimplente 
+    pub fn is_debt_nft_defaulted<
+        Arg0: ProxyArg<u64>,
+    >(
+        self,
+        nft_nonce: Arg0,
+    ) -> TxTypedCall<Env, From, To, NotPayable, Gas, bool> {
+        self.wrapped_tx
+            .payment(NotPayable)
+            .raw_call("isDebtNftDefaulted")
+            .argument(&nft_nonce)
+            .original_result()
+    }
+
     /// Retorna o ID do empréstimo associado a um NFT, ou zero se não existir 
     pub fn get_nft_loan_id<
         Arg0: ProxyArg<u64>,
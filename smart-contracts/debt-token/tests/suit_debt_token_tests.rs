@@ -922,4 +922,25 @@ fn d_t_burn_insufficient_balance() {
         .execute_tx(&setup.loan_controller_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
             sc.burn(managed_address!(&setup.user_address), managed_biguint!(2000));
         });
-}
\ No newline at end of file
+}
+// Teste de segurança: Apenas o controlador de empréstimos pode marcar NFTs como inadimplentes
+#[test]
+fn d_t_mark_debt_nft_defaulted_unauthorized() {
+    let mut setup = d_t_setup_contract(debt_token::contract_obj);
+
+    // Emitir o token de dívida primeiro
+    d_t_issue_debt_token(&mut setup);
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.user_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.mark_debt_nft_defaulted(1u64);
+        })
+        .assert_user_error("Only loan controller can mark debt NFTs");
+
+    // Sem NFT para o empréstimo, nem o controlador pode marcá-lo
+    setup.blockchain_wrapper
+        .execute_tx(&setup.loan_controller_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.mark_debt_nft_defaulted(1u64);
+        })
+        .assert_user_error("No NFT exists for this loan");
+}
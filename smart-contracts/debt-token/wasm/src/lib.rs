@@ -5,9 +5,10 @@
 ////////////////////////////////////////////////////
 
 // Init:                                 1
-// Endpoints:                           22
+// Upgrade:                              1
+// Endpoints:                           29
 // Async Callback:                       1
-// Total number of exported functions:  24
+// Total number of exported functions:  32
 
 #![no_std]
 
@@ -18,9 +19,11 @@ multiversx_sc_wasm_adapter::endpoints! {
     debt_token
     (
         init => init
+        upgrade => upgrade
         issueDebtToken => issue_debt_token
         createDebtNft => create_debt_nft
         burnDebtNft => burn_debt_nft
+        markDebtNftDefaulted => mark_debt_nft_defaulted
         get_loan_nft_id_for_test => get_loan_nft_id_for_test
         getCurrentFunds => get_current_funds
         status => status
@@ -39,7 +42,13 @@ multiversx_sc_wasm_adapter::endpoints! {
         balanceOf => balance_of
         totalTokenSupply => total_token_supply
         getLoanNftId => get_loan_nft_id
+        isDebtNftDefaulted => is_debt_nft_defaulted
         getNftLoanId => get_nft_loan_id
+        grantRole => grant_role
+        revokeRole => revoke_role
+        hasRole => has_role
+        getRoleMembers => get_role_members
+        getStorageVersion => get_storage_version
     )
 }
 
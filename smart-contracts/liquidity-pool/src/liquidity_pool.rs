@@ -7,10 +7,16 @@
 
 multiversx_sc::imports!();
 multiversx_sc::derive_imports!();
+
+use common_types::access_control::Role;
+use common_types::multisig::MultisigAction;
 use core::cmp;
 
 use multiversx_sc::api::ManagedTypeApi;
 
+// Versão atual do layout de armazenamento (ver StorageVersionModule)
+const STORAGE_VERSION: u32 = 1;
+
 mod reputation_score_proxy {
     multiversx_sc::imports!();
     
@@ -28,7 +34,12 @@ mod reputation_score_proxy {
 }
 
 #[multiversx_sc::contract]
-pub trait LiquidityPool {
+pub trait LiquidityPool:
+    common_types::access_control::AccessControlModule
+    + common_types::multisig::MultisigModule
+    + common_types::clock::ClockModule
+    + common_types::storage_version::StorageVersionModule
+{
     // Inicializa o contrato com os parâmetros básicos
     // Este método é chamado apenas uma vez, durante a implantação do contrato
     #[init]
@@ -62,20 +73,29 @@ pub trait LiquidityPool {
 
         // Inicializa como não pausado
         self.paused().set(false);
+        self.init_storage_version(STORAGE_VERSION);
     }
-    
+
+    // Executa as migrações pendentes em ordem; cada uma roda uma única vez
+    #[upgrade]
+    fn upgrade(&self) {
+        // v1: layout inicial, nada a converter
+        self.run_storage_migrations(STORAGE_VERSION, |_| {});
+    }
+
     // Função auxiliar para verificar se o contrato está pausado
     fn require_not_paused(&self) {
         require!(!self.paused().get(), "Contrato está pausado");
     }
-    
-    // Função auxiliar para verificar se o chamador é o proprietário
-    fn require_caller_is_owner(&self) {
-        let caller = self.blockchain().get_caller();
-        let owner = self.blockchain().get_owner_address();
-        require!(caller == owner, "Apenas o proprietário pode chamar esta função");
-    }
 
+    // Fundos emprestados só podem ser retirados depois de devolvidos ao pool
+    fn require_available_liquidity(&self, amount: &BigUint) {
+        require!(
+            &self.total_liquidity().get() >= amount,
+            "Insufficient available liquidity: funds are lent out"
+        );
+    }
+    
     // Deposita fundos no pool de liquidez
     // Esta função permite que usuários depositem tokens para fornecer liquidez
     #[payable("*")]
@@ -99,7 +119,7 @@ pub trait LiquidityPool {
             "Deposit amount below minimum"
         );
         
-        let current_timestamp = self.blockchain().get_block_timestamp();
+        let current_timestamp = self.current_timestamp();
         
         // Se for a primeira vez depositando, cria registro do provedor
         if self.provider_funds(caller.clone()).is_empty() {
@@ -164,11 +184,12 @@ pub trait LiquidityPool {
             amount <= provider_funds.amount,
             "Insufficient funds to withdraw"
         );
+        self.require_available_liquidity(&amount);
         
         // Atualiza fundos do provedor
         provider_funds.amount -= &amount;
         let token_id = provider_funds.token_id.clone();
-        let current_timestamp = self.blockchain().get_block_timestamp();
+        let current_timestamp = self.current_timestamp();
         provider_funds.last_yield_timestamp = current_timestamp;
         
         self.provider_funds(caller.clone()).set(provider_funds);
@@ -221,6 +242,7 @@ pub trait LiquidityPool {
             provider_funds.amount >= amount,
             "Saldo insuficiente"
         );
+        self.require_available_liquidity(&amount);
         
         // Atualiza fundos do provedor
         provider_funds.amount -= &amount;
@@ -251,7 +273,7 @@ pub trait LiquidityPool {
     // Esta função interna calcula e adiciona rendimentos com base no tempo decorrido
     fn process_pending_yield(&self, provider: &ManagedAddress) {
         let mut provider_funds = self.provider_funds(provider.clone()).get();
-        let current_timestamp = self.blockchain().get_block_timestamp();
+        let current_timestamp = self.current_timestamp();
         let time_diff_seconds = current_timestamp - provider_funds.last_yield_timestamp;
         
         // Pula se nenhum tempo passou
@@ -305,18 +327,22 @@ pub trait LiquidityPool {
         // Envia tokens para o controlador de empréstimos
         self.send().direct(&caller, &esdt_token, 0, &amount);
         
-        // Atualiza a liquidez total
+        // Atualiza a liquidez total e o volume emprestado
         self.total_liquidity().update(|liquidity| *liquidity -= &amount);
+        self.total_borrows().update(|borrows| *borrows += &amount);
+        self.update_utilization_rate();
         
         // Emite evento para auditoria
         self.funds_provided_for_loan_event(&amount);
     }
     
     // Recebe pagamento de empréstimo
-    // Esta função permite que o controlador de empréstimos devolva fundos ao pool
+    // Esta função permite que o controlador de empréstimos devolva fundos ao pool.
+    // `principal` indica quanto do pagamento é principal; o restante são juros
+    // pagos pelo tomador, acumulados para distribuição aos provedores
     #[payable("*")]
     #[endpoint(receiveLoanRepayment)]
-    fn receive_loan_repayment(&self) {
+    fn receive_loan_repayment(&self, principal: BigUint) {
         self.require_not_paused();
         
         let caller = self.blockchain().get_caller();
@@ -328,12 +354,27 @@ pub trait LiquidityPool {
         
         let payment = self.call_value().egld_or_single_esdt();
         let amount = payment.amount.clone(); // Clone para evitar uso após movimento
+        require!(amount >= principal, "Principal exceeds repayment amount");
         
         // Emite evento para auditoria
         self.loan_repayment_received_event(&amount);
         
-        // Atualiza a liquidez total
-        self.total_liquidity().update(|liquidity| *liquidity += amount);
+        // O principal volta à liquidez disponível e deixa de contar como emprestado
+        self.total_liquidity().update(|liquidity| *liquidity += &principal);
+        self.total_borrows().update(|borrows| {
+            if *borrows >= principal {
+                *borrows -= &principal;
+            } else {
+                *borrows = BigUint::zero();
+            }
+        });
+        self.update_utilization_rate();
+        
+        // Os juros compõem o rendimento dos provedores
+        let interest = amount - principal;
+        if interest > BigUint::zero() {
+            self.total_interest_accumulated().update(|v| *v += &interest);
+        }
     }
     
     // Endpoint borrow para empréstimos
@@ -604,14 +645,14 @@ pub trait LiquidityPool {
     // Função para pausar o contrato
     #[endpoint]
     fn pause(&self) {
-        self.require_caller_is_owner();
+        self.require_role(Role::Pauser);
         self.paused().set(true);
     }
     
     // Função para despausar o contrato
     #[endpoint]
     fn unpause(&self) {
-        self.require_caller_is_owner();
+        self.require_role(Role::Pauser);
         self.paused().set(false);
     }
     
@@ -619,7 +660,7 @@ pub trait LiquidityPool {
     #[endpoint]
     fn add_accumulated_interest_endpoint(&self, amount: BigUint) {
         self.require_not_paused();
-        self.require_caller_is_owner();
+        self.require_role(Role::Admin);
         
         // Adiciona juros acumulados
         self.total_interest_accumulated().update(|v| *v += &amount);
@@ -630,6 +671,7 @@ pub trait LiquidityPool {
     #[endpoint(distributeInterest)]
     fn distribute_interest_endpoint(&self) {
         self.require_not_paused();
+        self.require_role(Role::Keeper);
         
         // Obtém o total de juros acumulados
         let total_interest = self.total_interest_accumulated().get();
@@ -675,13 +717,35 @@ pub trait LiquidityPool {
     #[endpoint(useReserves)]
     fn use_reserves_endpoint(&self, target: ManagedAddress, amount: BigUint) {
         self.require_not_paused();
-        self.require_caller_is_owner();
-        
+        self.require_role(Role::Admin);
+        self.require_multisig_not_configured();
+
+        self.use_reserves(&target, &amount);
+    }
+
+    // Executa uma proposta do multisig que atingiu o quórum
+    #[endpoint(performAction)]
+    fn perform_action(&self, action_id: u64) {
+        let action = self.take_approved_action(action_id);
+        if self.perform_signer_action(&action) {
+            return;
+        }
+
+        match action {
+            MultisigAction::UseReserves { target, amount } => {
+                self.require_not_paused();
+                self.use_reserves(&target, &amount);
+            },
+            _ => sc_panic!("Ação não suportada por este contrato"),
+        }
+    }
+
+    fn use_reserves(&self, target: &ManagedAddress, amount: &BigUint) {
         let reserves = self.total_reserves().get();
-        require!(reserves >= amount, "Reservas insuficientes");
+        require!(&reserves >= amount, "Reservas insuficientes");
         
         // Atualiza as reservas
-        self.total_reserves().update(|v| *v -= &amount);
+        self.total_reserves().update(|v| *v -= amount);
         
         // Recupera o token ID da reserva
         let provider_count = self.providers().len();
@@ -695,19 +759,25 @@ pub trait LiquidityPool {
         let esdt_token = EgldOrEsdtTokenIdentifier::esdt(token_id);
         
         // Envia os tokens para o endereço alvo
-        self.send().direct(&target, &esdt_token, 0, &amount);
+        self.send().direct(target, &esdt_token, 0, amount);
+    }
+
+    // Os registros de emissão e queima vêm do contrato de token; Admin também
+    // pode registrá-los (ex.: conciliação)
+    fn require_token_contract_or_admin(&self, token_contract: &ManagedAddress, error: &str) {
+        let caller = self.blockchain().get_caller();
+        require!(
+            &caller == token_contract || self.is_owner_or_has_role(Role::Admin, &caller),
+            error
+        );
     }
 
     // Endpoint para registrar emissão de tokens LP
     #[endpoint(lpTokensMinted)]
     fn lp_tokens_minted_endpoint(&self, _provider: ManagedAddress, amount: BigUint) {
-        let caller = self.blockchain().get_caller();
-        
-        // Verificar se o chamador é o contrato de token LP ou o proprietário (para testes)
-        let owner = self.blockchain().get_owner_address();
-        require!(
-            caller == self.lp_token_address().get() || caller == owner,
-            "Apenas o contrato de token LP pode chamar esta função"
+        self.require_token_contract_or_admin(
+            &self.lp_token_address().get(),
+            "Apenas o contrato de token LP pode chamar esta função",
         );
         
         // Registra a emissão de tokens LP
@@ -717,13 +787,9 @@ pub trait LiquidityPool {
     // Endpoint para registrar queima de tokens LP
     #[endpoint(lpTokensBurned)]
     fn lp_tokens_burned_endpoint(&self, _provider: ManagedAddress, amount: BigUint) {
-        let caller = self.blockchain().get_caller();
-        
-        // Verificar se o chamador é o contrato de token LP ou o proprietário (para testes)
-        let owner = self.blockchain().get_owner_address();
-        require!(
-            caller == self.lp_token_address().get() || caller == owner,
-            "Apenas o contrato de token LP pode chamar esta função"
+        self.require_token_contract_or_admin(
+            &self.lp_token_address().get(),
+            "Apenas o contrato de token LP pode chamar esta função",
         );
         
         // Registra a queima de tokens LP
@@ -733,13 +799,9 @@ pub trait LiquidityPool {
     // Endpoint para registrar emissão de tokens de dívida
     #[endpoint(debtTokensMinted)]
     fn debt_tokens_minted_endpoint(&self, _borrower: ManagedAddress, amount: BigUint) {
-        let caller = self.blockchain().get_caller();
-        
-        // Verificar se o chamador é o contrato de token de dívida ou o proprietário (para testes)
-        let owner = self.blockchain().get_owner_address();
-        require!(
-            caller == self.debt_token_address().get() || caller == owner,
-            "Apenas o contrato de token de dívida pode chamar esta função"
+        self.require_token_contract_or_admin(
+            &self.debt_token_address().get(),
+            "Apenas o contrato de token de dívida pode chamar esta função",
         );
         
         // Registra a emissão de tokens de dívida
@@ -749,13 +811,9 @@ pub trait LiquidityPool {
     // Endpoint para registrar queima de tokens de dívida
     #[endpoint(debtTokensBurned)]
     fn debt_tokens_burned_endpoint(&self, _borrower: ManagedAddress, amount: BigUint) {
-        let caller = self.blockchain().get_caller();
-        
-        // Verificar se o chamador é o contrato de token de dívida ou o proprietário (para testes)
-        let owner = self.blockchain().get_owner_address();
-        require!(
-            caller == self.debt_token_address().get() || caller == owner,
-            "Apenas o contrato de token de dívida pode chamar esta função"
+        self.require_token_contract_or_admin(
+            &self.debt_token_address().get(),
+            "Apenas o contrato de token de dívida pode chamar esta função",
         );
         
         // Registra a queima de tokens de dívida
@@ -809,27 +867,27 @@ pub trait LiquidityPool {
     // Funções para atualização de parâmetros
     #[endpoint]
     fn set_interest_rate_base(&self, new_rate: u64) {
-        self.require_caller_is_owner();
+        self.require_role(Role::RiskManager);
         require!(new_rate <= 10000, "Taxa base muito alta");
         self.interest_rate_base().set(new_rate);
     }
     
     #[endpoint]
     fn set_target_utilization_rate(&self, new_rate: u64) {
-        self.require_caller_is_owner();
+        self.require_role(Role::RiskManager);
         require!(new_rate <= 10000, "Taxa de utilização alvo muito alta");
         self.target_utilization_rate().set(new_rate);
     }
     
     #[endpoint]
     fn set_max_utilization_rate(&self, new_rate: u64) {
-        self.require_caller_is_owner();
+        self.require_role(Role::RiskManager);
         self.max_utilization_rate().set(new_rate);
     }
     
     #[endpoint]
     fn set_reserve_percent(&self, new_percent: u64) {
-        self.require_caller_is_owner();
+        self.require_role(Role::RiskManager);
         require!(new_percent <= 10000, "Percentual de reserva muito alto");
         self.reserve_percent().set(new_percent);
     }
@@ -837,19 +895,19 @@ pub trait LiquidityPool {
     // Funções para atualizar endereços de contratos relacionados
     #[endpoint(setLoanControllerAddress)]
     fn set_loan_controller_address(&self, address: ManagedAddress) {
-        self.require_caller_is_owner();
+        self.require_role(Role::Admin);
         self.loan_controller_address().set(address);
     }
     
     #[endpoint(setDebtTokenAddress)]
     fn set_debt_token_address(&self, address: ManagedAddress) {
-        self.require_caller_is_owner();
+        self.require_role(Role::Admin);
         self.debt_token_address().set(address);
     }
     
     #[endpoint(setLpTokenAddress)]
     fn set_lp_token_address(&self, address: ManagedAddress) {
-        self.require_caller_is_owner();
+        self.require_role(Role::Admin);
         self.lp_token_address().set(address);
     }
     
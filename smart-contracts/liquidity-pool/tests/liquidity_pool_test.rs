@@ -250,6 +250,44 @@ fn l_t_deposit_liquidity() {
         .assert_ok();
 }
 
+// Registros de emissão/queima: só o contrato de token ou quem tem o papel de Admin
+#[test]
+fn l_t_token_notifications_require_token_contract_or_admin() {
+    use common_types::access_control::{AccessControlModule, Role};
+
+    let mut setup = setup_contract(liquidity_pool::contract_obj);
+    let admin = setup.blockchain_wrapper.create_user_account(&rust_biguint!(0));
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.debt_tokens_minted_endpoint(managed_address!(&setup.borrower_address), managed_biguint!(100));
+        })
+        .assert_user_error("Apenas o contrato de token de dívida pode chamar esta função");
+    setup.blockchain_wrapper
+        .execute_tx(&admin, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.lp_tokens_minted_endpoint(managed_address!(&setup.provider_address), managed_biguint!(100));
+        })
+        .assert_user_error("Apenas o contrato de token LP pode chamar esta função");
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.grant_role(Role::Admin, managed_address!(&admin));
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&admin, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.lp_tokens_minted_endpoint(managed_address!(&setup.provider_address), managed_biguint!(100));
+            assert_eq!(sc.lp_tokens_minted_storage().get(), managed_biguint!(100));
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&setup.debt_token_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.debt_tokens_minted_endpoint(managed_address!(&setup.borrower_address), managed_biguint!(100));
+            assert_eq!(sc.debt_tokens_minted_storage().get(), managed_biguint!(100));
+        })
+        .assert_ok();
+}
+
 #[test]
 fn l_t_withdraw_liquidity() {
     let mut setup = setup_contract(liquidity_pool::contract_obj);
@@ -1080,4 +1118,115 @@ fn l_t_update_parameters() {
             assert_eq!(rate, 1200u64);
         })
         .assert_ok();
-}
\ No newline at end of file
+}
+// Teste de recebimento de pagamento com separação entre principal e juros
+#[test]
+fn l_t_receive_loan_repayment_with_interest() {
+    let mut setup = setup_contract(liquidity_pool::contract_obj);
+    let provider_addr = setup.provider_address.clone();
+    let controller_addr = setup.loan_controller_address.clone();
+
+    // Depósito inicial do provedor
+    setup.blockchain_wrapper.set_esdt_balance(&provider_addr, TOKEN_ID_BYTES, &rust_biguint!(10000));
+    setup.blockchain_wrapper.execute_esdt_transfer(
+        &provider_addr,
+        &setup.contract_wrapper,
+        TOKEN_ID_BYTES,
+        0,
+        &rust_biguint!(10000),
+        |sc| {
+            sc.deposit_funds();
+        }
+    ).assert_ok();
+
+    // Controlador retira o principal de um empréstimo
+    setup.blockchain_wrapper
+        .execute_tx(&controller_addr, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.provide_funds_for_loan(managed_biguint!(4000), TokenIdentifier::from_esdt_bytes(TOKEN_ID_BYTES));
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper
+        .execute_query(&setup.contract_wrapper, |sc| {
+            assert_eq!(sc.total_liquidity().get(), managed_biguint!(6000));
+            assert_eq!(sc.total_borrows().get(), managed_biguint!(4000));
+            assert_eq!(sc.utilization_rate().get(), 4000u64);
+        })
+        .assert_ok();
+
+    // Controlador devolve principal + juros
+    setup.blockchain_wrapper.set_esdt_balance(&controller_addr, TOKEN_ID_BYTES, &rust_biguint!(4500));
+    setup.blockchain_wrapper.execute_esdt_transfer(
+        &controller_addr,
+        &setup.contract_wrapper,
+        TOKEN_ID_BYTES,
+        0,
+        &rust_biguint!(4400),
+        |sc| {
+            sc.receive_loan_repayment(managed_biguint!(4000));
+        }
+    ).assert_ok();
+
+    setup.blockchain_wrapper
+        .execute_query(&setup.contract_wrapper, |sc| {
+            assert_eq!(sc.total_liquidity().get(), managed_biguint!(10000), "Principal deve voltar à liquidez");
+            assert_eq!(sc.total_borrows().get(), managed_biguint!(0));
+            assert_eq!(sc.total_interest_accumulated().get(), managed_biguint!(400), "Juros devem ser acumulados para os provedores");
+        })
+        .assert_ok();
+
+    // Principal maior que o valor enviado é rejeitado
+    setup.blockchain_wrapper.execute_esdt_transfer(
+        &controller_addr,
+        &setup.contract_wrapper,
+        TOKEN_ID_BYTES,
+        0,
+        &rust_biguint!(100),
+        |sc| {
+            sc.receive_loan_repayment(managed_biguint!(200));
+        }
+    ).assert_user_error("Principal exceeds repayment amount");
+}
+
+// Provedores só retiram a liquidez disponível; o que está emprestado volta com os pagamentos
+#[test]
+fn l_t_withdraw_limited_to_available_liquidity() {
+    let mut setup = setup_contract(liquidity_pool::contract_obj);
+    let provider_addr = setup.provider_address.clone();
+    let controller_addr = setup.loan_controller_address.clone();
+
+    setup.blockchain_wrapper.set_esdt_balance(&provider_addr, TOKEN_ID_BYTES, &rust_biguint!(10000));
+    setup.blockchain_wrapper.execute_esdt_transfer(
+        &provider_addr,
+        &setup.contract_wrapper,
+        TOKEN_ID_BYTES,
+        0,
+        &rust_biguint!(10000),
+        |sc| {
+            sc.deposit_funds();
+        }
+    ).assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&controller_addr, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.provide_funds_for_loan(managed_biguint!(4000), TokenIdentifier::from_esdt_bytes(TOKEN_ID_BYTES));
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper
+        .execute_tx(&provider_addr, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.withdraw_funds(managed_biguint!(8000));
+        })
+        .assert_user_error("Insufficient available liquidity: funds are lent out");
+    setup.blockchain_wrapper
+        .execute_tx(&provider_addr, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.withdraw(managed_biguint!(8000));
+        })
+        .assert_user_error("Insufficient available liquidity: funds are lent out");
+
+    setup.blockchain_wrapper
+        .execute_tx(&provider_addr, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.withdraw_funds(managed_biguint!(6000));
+            assert_eq!(sc.total_liquidity().get(), managed_biguint!(0));
+        })
+        .assert_ok();
+}
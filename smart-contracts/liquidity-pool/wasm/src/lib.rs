@@ -5,9 +5,10 @@
 ////////////////////////////////////////////////////
 
 // Init:                                 1
-// Endpoints:                           30
+// Upgrade:                              1
+// Endpoints:                           46
 // Async Callback (empty):               1
-// Total number of exported functions:  32
+// Total number of exported functions:  49
 
 #![no_std]
 
@@ -18,6 +19,7 @@ multiversx_sc_wasm_adapter::endpoints! {
     liquidity_pool
     (
         init => init
+        upgrade => upgrade
         depositFunds => deposit_funds
         withdrawFunds => withdraw_funds
         withdraw => withdraw
@@ -30,6 +32,7 @@ multiversx_sc_wasm_adapter::endpoints! {
         add_accumulated_interest_endpoint => add_accumulated_interest_endpoint
         distributeInterest => distribute_interest_endpoint
         useReserves => use_reserves_endpoint
+        performAction => perform_action
         lpTokensMinted => lp_tokens_minted_endpoint
         lpTokensBurned => lp_tokens_burned_endpoint
         debtTokensMinted => debt_tokens_minted_endpoint
@@ -48,6 +51,21 @@ multiversx_sc_wasm_adapter::endpoints! {
         getAnnualYieldPercentage => get_annual_yield_percentage
         getTotalLiquidity => get_total_liquidity
         getProviderFunds => get_provider_funds
+        grantRole => grant_role
+        revokeRole => revoke_role
+        hasRole => has_role
+        getRoleMembers => get_role_members
+        setupMultisig => setup_multisig
+        proposeAction => propose_action
+        approveAction => approve_action
+        revokeApproval => revoke_approval
+        discardAction => discard_action
+        getMultisigSigners => get_multisig_signers
+        getMultisigQuorum => get_multisig_quorum
+        getProposal => get_proposal
+        getActionApprovals => get_action_approvals
+        isQuorumReached => is_quorum_reached
+        getStorageVersion => get_storage_version
     )
 }
 
@@ -13,20 +13,95 @@ use multiversx_sc::{
     api::ManagedTypeApi,
     derive::ManagedVecItem,
 };
+use common_types::access_control::Role;
+use common_types::multisig::MultisigAction;
+use common_types::CreditDecision;
 
 mod reputation_score_proxy {
     multiversx_sc::imports!();
+
+    use common_types::CreditDecision;
     
     #[multiversx_sc::proxy]
     pub trait ReputationScore {
-        #[endpoint(isEligibleForLoan)]
-        fn is_eligible_for_loan(&self, user: ManagedAddress, min_score: u64) -> bool;
+        #[endpoint(getCreditDecision)]
+        fn get_credit_decision(
+            &self,
+            user: ManagedAddress,
+            requested_amount: BigUint,
+            min_score: u64,
+            base_amount: BigUint,
+        ) -> CreditDecision<Self::Api>;
         
         #[endpoint(calculateMaxLoanAmount)]
         fn calculate_max_loan_amount(&self, user: ManagedAddress, base_amount: BigUint) -> BigUint;
         
         #[endpoint(getUserScore)]
         fn get_user_score(&self, user: ManagedAddress) -> u64;
+
+        #[endpoint(updateScoreAfterRepayment)]
+        fn update_score_after_repayment(&self, user: ManagedAddress, amount: BigUint);
+
+        #[endpoint(updateScoreAfterLatePayment)]
+        fn update_score_after_late_payment(&self, user: ManagedAddress, amount: BigUint, delay_days: u64);
+
+        #[endpoint(updateScoreAfterDefault)]
+        fn update_score_after_default(&self, user: ManagedAddress, amount: BigUint);
+
+        #[endpoint(updateScoreAfterGuarantee)]
+        fn update_score_after_guarantee(&self, user: ManagedAddress, amount: BigUint);
+    }
+}
+
+mod liquidity_pool_proxy {
+    multiversx_sc::imports!();
+
+    #[multiversx_sc::proxy]
+    pub trait LiquidityPool {
+        #[endpoint(provideFundsForLoan)]
+        fn provide_funds_for_loan(&self, amount: BigUint, token_id: TokenIdentifier);
+
+        #[payable("*")]
+        #[endpoint(receiveLoanRepayment)]
+        fn receive_loan_repayment(&self, principal: BigUint);
+    }
+}
+
+mod debt_token_proxy {
+    multiversx_sc::imports!();
+
+    #[multiversx_sc::proxy]
+    pub trait DebtToken {
+        #[endpoint(createDebtNft)]
+        fn create_debt_nft(
+            &self,
+            loan_id: u64,
+            borrower: ManagedAddress,
+            amount: BigUint,
+            interest_rate: u64,
+            due_timestamp: u64,
+        ) -> u64;
+
+        #[payable("*")]
+        #[endpoint(burnDebtNft)]
+        fn burn_debt_nft(&self, loan_id: u64);
+
+        #[endpoint(markDebtNftDefaulted)]
+        fn mark_debt_nft_defaulted(&self, loan_id: u64);
+    }
+}
+
+mod price_source_proxy {
+    multiversx_sc::imports!();
+
+    #[multiversx_sc::proxy]
+    pub trait PriceSource {
+        #[view(getPrice)]
+        fn get_price(
+            &self,
+            base_token: EgldOrEsdtTokenIdentifier,
+            quote_token: EgldOrEsdtTokenIdentifier,
+        ) -> BigUint;
     }
 }
 
@@ -39,13 +114,36 @@ pub enum LoanStatus {
     Repaid,
     Defaulted,
     Liquidated,
+    Refinanced,
 }
 
+// Parâmetros de risco alterados via proposta com timelock
+#[type_abi]
 #[derive(NestedEncode, NestedDecode, TopEncode, TopDecode, Debug, PartialEq, Eq, Clone, Copy)]
 pub enum ParamType {
     MinScore,
     MaxScore,
     InterestRate,
+    MinInterestRate,
+    MaxInterestRate,
+    ExtendedTermRateMultiplier,
+    MaxTermRateMultiplier,
+    CollateralRatio,
+    LiquidationThreshold,
+    LiquidationPenalty,
+    LiquidationDiscount,
+    LateFeeDailyRate,
+    ProgressiveLateFeeThresholdDays,
+    ProgressiveLateFeeDailyRate,
+    ExtensionFeePercent,
+    MaxActiveLoans,
+    MaxLoansPerUser,
+    AuctionDuration,
+    OperationTimelock,
+    GuarantorStakeRatio,
+    MinInterestFloor,
+    ApplicationLifetime,
+    MaxLoanTermDays,
 }
 
 #[type_abi]
@@ -73,6 +171,7 @@ impl LoanTerm {
 pub struct ParameterChange {
     pub value: u64,       // The new value for the parameter
     pub timestamp: u64,   // The timestamp when the change was requested
+    pub executable_at: u64, // Fixado na proposta com o timelock vigente
 }
 // Dados do empréstimo
 #[type_abi]
@@ -87,9 +186,278 @@ pub struct Loan<M: ManagedTypeApi> {
     pub status: LoanStatus,
 }
 
+// Empréstimo com os valores calculados no momento da consulta (listagens paginadas)
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone)]
+pub struct LoanView<M: ManagedTypeApi> {
+    pub loan_id: u64,
+    pub loan: Loan<M>,
+    pub token_id: EgldOrEsdtTokenIdentifier<M>,
+    pub accrued_interest: BigUint<M>,
+    pub late_fee_due: BigUint<M>,
+    pub amount_due: BigUint<M>,
+    pub collateral: BigUint<M>,
+}
+
+// Modalidade de leilão da garantia de um empréstimo inadimplente
+#[type_abi]
+#[derive(NestedEncode, NestedDecode, TopEncode, TopDecode, PartialEq, Debug, Clone, Copy)]
+pub enum AuctionType {
+    Dutch,      // preço cai do valor da dívida até o piso com desconto
+    English,    // lances crescentes a partir do piso, com reembolso do lance superado
+}
+
+// Leilão em andamento da garantia de um empréstimo
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone, PartialEq, Debug)]
+pub struct CollateralAuction<M: ManagedTypeApi> {
+    pub auction_type: AuctionType,
+    pub start_timestamp: u64,
+    pub end_timestamp: u64,
+    pub start_price: BigUint<M>,
+    pub floor_price: BigUint<M>,
+    pub highest_bidder: ManagedAddress<M>,
+    pub highest_bid: BigUint<M>,
+}
+
+// Periodicidade das parcelas de um empréstimo parcelado
+#[type_abi]
+#[derive(NestedEncode, NestedDecode, TopEncode, TopDecode, PartialEq, Debug, Clone, Copy)]
+pub enum InstallmentPeriod {
+    Weekly,     //  7 dias
+    Monthly,    // 30 dias
+}
+
+impl InstallmentPeriod {
+    fn get_days(&self) -> u64 {
+        match self {
+            InstallmentPeriod::Weekly => 7u64,
+            InstallmentPeriod::Monthly => 30u64,
+        }
+    }
+}
+
+// Parcela do cronograma de pagamento (principal e juros separados)
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, ManagedVecItem, Clone, PartialEq, Debug)]
+pub struct Installment<M: ManagedTypeApi> {
+    pub due_timestamp: u64,
+    pub principal: BigUint<M>,
+    pub interest: BigUint<M>,
+}
+
+// Configuração de um token aceito para empréstimos
+// (liquidity_pool zero = financiado pelo saldo do próprio contrato;
+// max_loan_amount zero = sem limite)
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone, PartialEq, Debug)]
+pub struct LoanTokenConfig<M: ManagedTypeApi> {
+    pub interest_rate_base: u64,
+    pub max_loan_amount: BigUint<M>,
+    pub liquidity_pool: ManagedAddress<M>,
+}
+
+// Linha de crédito rotativo: limite pela reputação, juros por segundo sobre o saldo
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone, PartialEq, Debug)]
+pub struct CreditLine<M: ManagedTypeApi> {
+    pub token_id: EgldOrEsdtTokenIdentifier<M>,
+    pub limit: BigUint<M>,
+    pub principal: BigUint<M>,
+    pub accrued_interest: BigUint<M>,
+    pub interest_rate: u64, // anual, em pontos base
+    pub last_accrual_timestamp: u64,
+}
+
+// Pedido de empréstimo com fiadores: o empréstimo só abre quando as garantias
+// pessoais somam required_stake (loan_id preenchido quando o empréstimo abre)
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone, PartialEq, Debug)]
+pub struct GuaranteedLoanRequest<M: ManagedTypeApi> {
+    pub borrower: ManagedAddress<M>,
+    pub amount: BigUint<M>,
+    pub token_id: EgldOrEsdtTokenIdentifier<M>,
+    pub term: LoanTerm,
+    pub required_stake: BigUint<M>,
+    pub pledged_stake: BigUint<M>,
+    pub loan_id: Option<u64>,
+}
+
+// Situação de um pedido na fila de aprovação
+#[type_abi]
+#[derive(NestedEncode, NestedDecode, TopEncode, TopDecode, PartialEq, Debug, Clone, Copy)]
+pub enum ApplicationStatus {
+    Pending,
+    Approved,
+    Rejected,
+    Expired,
+}
+
+// Pedido acima do limite de aprovação automática, à espera de um underwriter.
+// A garantia pendente do tomador fica retida no pedido até a decisão, assim como
+// as garantias dos fiadores de guarantee_request_id (zero = sem fiadores)
+// (loan_id preenchido quando o empréstimo é registrado)
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone, PartialEq, Debug)]
+pub struct LoanApplication<M: ManagedTypeApi> {
+    pub borrower: ManagedAddress<M>,
+    pub amount: BigUint<M>,
+    pub token_id: EgldOrEsdtTokenIdentifier<M>,
+    pub term: LoanTerm,
+    pub interest_rate: u64,
+    pub user_score: u64,
+    pub installment_period: Option<InstallmentPeriod>,
+    pub guarantee_request_id: u64,
+    pub collateral_token: EgldOrEsdtTokenIdentifier<M>,
+    pub collateral: BigUint<M>,
+    pub created_at: u64,
+    pub expires_at: u64,
+    pub status: ApplicationStatus,
+    pub reason_code: u32,
+    pub loan_id: Option<u64>,
+}
+
+// Limites de exposição de um tomador numa faixa de pontuação (zero = sem limite)
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone, PartialEq, Debug)]
+pub struct ExposureLimit<M: ManagedTypeApi> {
+    pub max_active_loans: u64,
+    pub max_outstanding_principal: BigUint<M>,
+}
+
+// Alterações de configuração de risco que não cabem num parâmetro u64,
+// sujeitas ao mesmo timelock de proposeParameterChange
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone, PartialEq, Debug)]
+pub enum ConfigChange<M: ManagedTypeApi> {
+    LoanToken {
+        token_id: EgldOrEsdtTokenIdentifier<M>,
+        interest_rate_base: u64,
+        max_loan_amount: BigUint<M>,
+        liquidity_pool: ManagedAddress<M>,
+    },
+    KeeperBounty {
+        bounty: BigUint<M>,
+    },
+    ExposureTier {
+        min_score: u64,
+        max_active_loans: u64,
+        max_outstanding_principal: BigUint<M>,
+    },
+    RemoveExposureTier {
+        min_score: u64,
+    },
+    UnderwritingThreshold {
+        threshold: BigUint<M>,
+    },
+    LoanTerms {
+        standard: u64,
+        extended: u64,
+        short: u64,
+    },
+    MinCollateralAmount {
+        amount: BigUint<M>,
+    },
+}
+
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone, PartialEq, Debug)]
+pub struct PendingConfigChange<M: ManagedTypeApi> {
+    pub change: ConfigChange<M>,
+    pub proposed_at: u64,
+    pub executable_at: u64,
+}
+
+// Prévia de um pedido de empréstimo (quoteLoan), calculada com a mesma matemática
+// da originação. reason_code segue as constantes ELIGIBILITY_* (zero = elegível).
+// binding indica que a decisão de crédito foi consultada no ReputationScore;
+// sem ela a prévia usa a última pontuação registrada e o limite fica indisponível
+#[type_abi]
+#[derive(TopEncode, TopDecode, NestedEncode, NestedDecode, Clone, PartialEq, Debug)]
+pub struct LoanQuote<M: ManagedTypeApi> {
+    pub user_score: u64,
+    pub interest_rate: u64,            // já ajustada ao prazo, em pontos base
+    pub repayment_amount: BigUint<M>,
+    pub due_timestamp: u64,
+    pub max_amount: BigUint<M>,
+    pub required_collateral: BigUint<M>, // em EGLD, para requestLoanWithCollateral
+    pub requires_underwriting: bool,
+    pub eligible: bool,
+    pub reason_code: u32,
+    pub binding: bool,
+    pub installment_count: u64,        // 1 para empréstimos sem parcelamento
+}
+
+// Motivos de recusa de um pedido, na ordem em que a originação os verifica
+pub const ELIGIBILITY_OK: u32 = 0;
+pub const ELIGIBILITY_PAUSED: u32 = 1;
+pub const ELIGIBILITY_BLACKLISTED: u32 = 2;
+pub const ELIGIBILITY_GLOBAL_LOAN_LIMIT: u32 = 3;
+pub const ELIGIBILITY_MAX_ACTIVE_LOANS: u32 = 4;
+pub const ELIGIBILITY_MAX_OUTSTANDING_PRINCIPAL: u32 = 5;
+pub const ELIGIBILITY_SCORE_TOO_LOW: u32 = 6;
+pub const ELIGIBILITY_AMOUNT_ABOVE_LIMIT: u32 = 7;
+// Pedido inválido (token ou periodicidade) e limite sem decisão de crédito
+pub const ELIGIBILITY_TOKEN_NOT_ACCEPTED: u32 = 8;
+pub const ELIGIBILITY_INVALID_INSTALLMENT_PERIOD: u32 = 9;
+pub const ELIGIBILITY_LIMIT_UNAVAILABLE: u32 = 10;
+
+// Duração padrão dos leilões de garantia (1 dia)
+const DEFAULT_AUCTION_DURATION: u64 = 24 * 60 * 60;
+
+// Precisão dos preços de garantia (preço de 1 unidade do token em unidades do token do empréstimo)
+const PRICE_PRECISION: u64 = 1_000_000_000_000_000_000;
+
+// Prazo para executar uma alteração de parâmetro depois que o timelock termina
+const PARAMETER_CHANGE_GRACE_PERIOD: u64 = 7 * 24 * 60 * 60;
+
+// Escala de pontuação usada no cálculo de juros quando MaxScore não foi configurado
+const DEFAULT_MAX_SCORE: u64 = 1000;
+
+// Precisão do acumulador de lucro por participação dos investidores
+const PROFIT_PER_SHARE_PRECISION: u64 = 1_000_000_000_000_000_000;
+
+// Soma máxima das participações dos investidores (100%)
+const MAX_TOTAL_INVESTOR_SHARES: u64 = 10_000;
+
+// Base dos juros por segundo das linhas de crédito
+const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+
+// Linhas de crédito com saldo precisam quitar os juros acumulados a cada período;
+// sem isso ficam em atraso (sem novos saques) e, passado o prazo de default,
+// podem ser declaradas inadimplentes por qualquer um
+const CREDIT_LINE_PAYMENT_PERIOD: u64 = 30 * 24 * 60 * 60;
+const CREDIT_LINE_DEFAULT_PERIOD: u64 = 60 * 24 * 60 * 60;
+
+// Tamanho máximo de página das listagens de empréstimos
+const MAX_LOANS_PAGE_SIZE: u64 = 100;
+
+// Ids examinados por chamada de getLoans, para o filtro por status não varrer
+// todo o histórico quando poucos empréstimos o satisfazem
+const MAX_LOANS_SCAN_PER_PAGE: u64 = 1_000;
+
+// Garantia exigida dos fiadores (sobre o principal) quando GuarantorStakeRatio não foi configurado
+const DEFAULT_GUARANTOR_STAKE_RATIO: u64 = 5_000;
+
+// Número máximo de faixas de exposição por pontuação
+const MAX_EXPOSURE_TIERS: usize = 10;
+
+// Prazo padrão para um underwriter decidir um pedido (7 dias)
+const DEFAULT_APPLICATION_LIFETIME: u64 = 7 * 24 * 60 * 60;
+
+// Versão atual do layout de armazenamento (ver StorageVersionModule)
+const STORAGE_VERSION: u32 = 2;
+
+// Número máximo de fiadores por empréstimo
+const MAX_GUARANTORS: usize = 10;
 
 #[multiversx_sc::contract]
-pub trait LoanController {
+pub trait LoanController:
+    common_types::access_control::AccessControlModule
+    + common_types::multisig::MultisigModule
+    + common_types::clock::ClockModule
+    + common_types::storage_version::StorageVersionModule
+{
     // Inicializa o contrato com os parâmetros básicos
     #[init]
     fn init(
@@ -107,26 +475,78 @@ pub trait LoanController {
         self.standard_term_days().set(30u64);
         self.extended_term_days().set(90u64);
         self.short_term_days().set(15u64);
+        self.init_storage_version(STORAGE_VERSION);
+    }
+
+    // Executa as migrações pendentes em ordem; cada uma roda uma única vez
+    #[upgrade]
+    fn upgrade(&self) {
+        self.run_storage_migrations(STORAGE_VERSION, |version| match version {
+            // v1: prazos gravados em standard_loan_term_days / extended_loan_term_days
+            // passam para as chaves usadas pelo contrato
+            1 => self.consolidate_term_keys(),
+            // v2: alterações pendentes passam a gravar executable_at na proposta
+            2 => self.fix_pending_parameter_deadlines(),
+            _ => {},
+        });
+    }
+
+    // O valor já presente na chave canônica prevalece; a chave legada é apagada.
+    // Prazos ausentes em ambas recebem os padrões do init
+    fn consolidate_term_keys(&self) {
+        let standard = self.standard_loan_term_days().take();
+        if standard > 0 {
+            self.standard_term_days().set_if_empty(standard);
+        }
+        let extended = self.extended_loan_term_days().take();
+        if extended > 0 {
+            self.extended_term_days().set_if_empty(extended);
+        }
+        self.standard_term_days().set_if_empty(30u64);
+        self.extended_term_days().set_if_empty(90u64);
+        self.short_term_days().set_if_empty(15u64);
     }
 
-    #[only_owner]
-    #[payable("*")]                // se quiser que o contrato possa receber taxas antes de liberar fundos
+    // Propostas antigas só tinham o instante da proposta; o prazo passa a
+    // ser fixado com o timelock vigente no upgrade
+    fn fix_pending_parameter_deadlines(&self) {
+        let timelock = self.operation_timelock().get();
+        for param in self.pending_parameter_types().iter() {
+            let (value, timestamp) = self.legacy_pending_parameter_changes(param).get();
+            self.pending_parameter_changes(param).set(ParameterChange {
+                value,
+                timestamp,
+                executable_at: timestamp + timelock,
+            });
+        }
+    }
+
+    // Saca taxas do protocolo já apuradas (extensão, atraso, penalidades de
+    // liquidação) e garantias apreendidas em forfeitCollateral, em EGLD por
+    // padrão. Demais garantias, fundos emprestáveis e lucros dos investidores
+    // não entram nesse saldo
     #[endpoint(withdrawFunds)]
-    fn withdraw_funds(&self, amount: BigUint) {
-        // manda `amount` EGLD para o dono (caller) do endpoint
+    fn withdraw_funds(&self, amount: BigUint, token_id: OptionalValue<EgldOrEsdtTokenIdentifier>) {
+        self.require_role(Role::Admin);
+        self.require_multisig_not_configured();
+        let token_id = token_id.into_option().unwrap_or_else(EgldOrEsdtTokenIdentifier::egld);
+        let available = self.protocol_fees(&token_id).get();
+        require!(amount <= available, "Saldo de taxas insuficiente");
+        self.protocol_fees(&token_id).set(available - &amount);
+
+        // manda `amount` para o Admin (caller) do endpoint
         let caller = self.blockchain().get_caller();
-        self.send().direct_egld(&caller, &amount);
+        self.send().direct(&caller, &token_id, 0, &amount);
+    }
+
+    #[view(getProtocolFees)]
+    fn get_protocol_fees(&self, token_id: EgldOrEsdtTokenIdentifier) -> BigUint {
+        self.protocol_fees(&token_id).get()
     }
 
-    #[only_owner]
     #[endpoint(setLoanTerms)]
     fn set_loan_terms(&self, standard: u64, extended: u64, short: u64) {
-        require!(standard > 0 && extended > 0 && short > 0, "Termos devem ser maiores que zero");
-        require!(extended > standard && standard > short, "Termos devem seguir a hierarquia");
-        
-        self.standard_term_days().set(standard);
-        self.extended_term_days().set(extended);
-        self.short_term_days().set(short);
+        self.set_config_directly(ConfigChange::LoanTerms { standard, extended, short });
     }
 
     // Solicita um empréstimo
@@ -138,23 +558,47 @@ pub trait LoanController {
         // Converter o termo para dias
         // let duration_days = term.get_days();
         
-        // Verificar se o usuário tem pontuação suficiente
-        let rs_address = self.reputation_score_address().get();
-        let min_score = self.min_required_score().get();
-        
         require!(!self.paused().get(), "Contract is paused");
+        self.require_within_unscored_exposure(&caller);
 
-        self.reputation_score_proxy(rs_address.clone())
-            .is_eligible_for_loan(caller.clone(), min_score)
-            .with_callback(self.callbacks().check_eligibility_callback(
-                caller.clone(),
-                amount.clone(),
-                term
-            ))
-            .call_and_exit();
+        // Sem token explícito, o empréstimo é no token emprestado pelo pool
+        let token_id = self.default_loan_token();
+
+        // Verificar se o usuário tem pontuação e limite suficientes
+        self.request_credit_decision(caller, amount, token_id, term, None, 0);
+    }
+
+    // Solicita um empréstimo denominado em um token da lista de tokens aceitos
+    #[endpoint(requestLoanInToken)]
+    fn request_loan_in_token(&self, token_id: EgldOrEsdtTokenIdentifier, amount: BigUint, term: LoanTerm) {
+        let caller = self.blockchain().get_caller();
+        require!(!self.paused().get(), "Contract is paused");
+        self.require_loan_token_limit(&token_id, &amount);
+        self.require_within_unscored_exposure(&caller);
+
+        self.request_credit_decision(caller, amount, token_id, term, None, 0);
     }
 
 
+    // Solicita um empréstimo parcelado: o prazo é dividido em parcelas
+    // semanais ou mensais, cada uma com sua parte de principal e juros
+    #[payable("*")]
+    #[endpoint(requestInstallmentLoan)]
+    fn request_installment_loan(&self, amount: BigUint, term: LoanTerm, period: InstallmentPeriod) {
+        let caller = self.blockchain().get_caller();
+        require!(!self.paused().get(), "Contract is paused");
+        require!(
+            period.get_days() <= term.get_days(),
+            "Período da parcela maior que o prazo do empréstimo"
+        );
+        self.require_within_unscored_exposure(&caller);
+
+        let token_id = self.default_loan_token();
+
+        // O plano segue junto com o pedido até o registro do empréstimo
+        self.request_credit_decision(caller, amount, token_id, term, Some(period), 0);
+    }
+
     // Paga um empréstimo
     #[payable("*")]
     #[endpoint(repayLoan)]
@@ -168,162 +612,1490 @@ pub trait LoanController {
         require!(loan.status == LoanStatus::Active, "Empréstimo não está ativo");
 
         // 2) Captura e valida o valor enviado
+        let payment_token = self.call_value().egld_or_single_esdt().token_identifier.clone();
         let payment = self.call_value().egld_or_single_esdt().amount.clone();
-        require!(payment == loan.repayment_amount, "Incorrect repayment amount");
+        self.require_loan_payment_token(loan_id, &payment_token);
+
+        //    O valor devido inclui as taxas de atraso acumuladas até agora e
+        //    desconta os juros ainda não decorridos (pagamento antecipado).
+        //    Como o valor muda a cada segundo, o excedente é devolvido
+        let now = self.current_timestamp();
+        self.accrue_late_fees(loan_id);
+        let late_fee = self.loan_late_fee_balance(loan_id).get();
+        let amount_due = &loan.repayment_amount - &self.early_repayment_rebate(loan_id, &loan, now) + &late_fee;
+        require!(payment >= amount_due, "Incorrect repayment amount");
+        let change = &payment - &amount_due;
+        if change > 0u64 {
+            self.send().direct(&caller, &payment_token, 0, &change);
+        }
+        let payment = amount_due;
+        self.collect_late_fee(loan_id, &late_fee);
 
         // 3) Marca como pago e atualiza contadores
         let due_ts = loan.due_timestamp;
         loan.status = LoanStatus::Repaid;
         self.loans(loan_id).set(loan.clone());
         self.repaid_loans_count().update(|cnt| *cnt += 1u64);
+        self.on_loan_closed(loan_id, &LoanStatus::Active, &LoanStatus::Repaid);
 
-        // 4) Contabiliza pagamento em dia
-        let now = self.blockchain().get_block_timestamp();
+        // 4) Contabiliza pagamento em dia e reporta o resultado ao ReputationScore
         if now <= due_ts {
             self.on_time_payments(caller.clone()).update(|cnt| *cnt += 1u64);
         }
+        self.report_repayment(&loan, now);
+        self.loan_repaid_event(loan_id, &caller, &payment, &late_fee, now);
 
-        // 5) Calcula e acumula juros
-        let principal = loan.amount.clone();
-        let interest = payment - principal;
-        self.total_interest_earned().update(|tot| *tot += interest);
+        // 5) Devolve o principal à origem dos fundos e contabiliza os juros
+        self.book_loan_payment(loan_id, &loan, &payment, &late_fee);
+    }
 
-        // (Não é necessário enviar nada: o EGLD já ficou no contrato via `payable`)
+    // Configura o pool de liquidez que financia os empréstimos e o token emprestado
+    #[endpoint(setLiquidityPool)]
+    fn set_liquidity_pool(&self, pool_address: ManagedAddress, token_id: TokenIdentifier) {
+        self.require_role(Role::Admin);
+        require!(!pool_address.is_zero(), "Endereço do pool inválido");
+        require!(token_id.is_valid_esdt_identifier(), "Token inválido");
+
+        self.liquidity_pool_address().set(pool_address.clone());
+        self.lending_token_id().set(token_id.clone());
+
+        // O token do pool passa a ser aceito para empréstimos
+        let token = EgldOrEsdtTokenIdentifier::esdt(token_id);
+        if self.loan_token_config(&token).is_empty() {
+            self.loan_token_config(&token).set(LoanTokenConfig {
+                interest_rate_base: self.interest_rate_base().get(),
+                max_loan_amount: BigUint::zero(),
+                liquidity_pool: pool_address,
+            });
+        } else {
+            self.loan_token_config(&token).update(|config| config.liquidity_pool = pool_address);
+        }
+        self.loan_tokens().insert(token);
     }
 
+    // Aceita (ou reconfigura) um token para empréstimos, com taxa base e
+    // limite próprios. Sem pool (só EGLD), os empréstimos saem do saldo emprestável
+    #[endpoint(setLoanToken)]
+    fn set_loan_token(
+        &self,
+        token_id: EgldOrEsdtTokenIdentifier,
+        interest_rate_base: u64,
+        max_loan_amount: BigUint,
+        liquidity_pool: OptionalValue<ManagedAddress>,
+    ) {
+        self.set_config_directly(ConfigChange::LoanToken {
+            token_id,
+            interest_rate_base,
+            max_loan_amount,
+            liquidity_pool: liquidity_pool.into_option().unwrap_or_else(ManagedAddress::zero),
+        });
+    }
 
+    // Deixa de aceitar novos empréstimos no token; os existentes seguem
+    // com a configuração gravada
+    #[endpoint(removeLoanToken)]
+    fn remove_loan_token(&self, token_id: EgldOrEsdtTokenIdentifier) {
+        self.require_role(Role::Admin);
+        require!(self.loan_tokens().swap_remove(&token_id), "Token não aceito para empréstimos");
+    }
 
+    #[view(getLoanTokens)]
+    fn get_loan_tokens(&self) -> MultiValueEncoded<EgldOrEsdtTokenIdentifier> {
+        self.loan_tokens().iter().collect()
+    }
 
-    //-----------------------------------------------
+    #[view(getLoanTokenConfig)]
+    fn get_loan_token_config(&self, token_id: EgldOrEsdtTokenIdentifier) -> LoanTokenConfig<Self::Api> {
+        require!(!self.loan_token_config(&token_id).is_empty(), "Token não aceito para empréstimos");
+        self.loan_token_config(&token_id).get()
+    }
 
-    // Adia o vencimento de um empréstimo (pagando a taxa de extensão)
+    #[view(getLoanToken)]
+    fn get_loan_token(&self, loan_id: u64) -> EgldOrEsdtTokenIdentifier {
+        require!(!self.loans(loan_id).is_empty(), "Empréstimo não existe");
+        self.loan_payment_token(loan_id)
+    }
+
+    // Deposita fundos para os empréstimos de um token sem pool
     #[payable("*")]
-    #[endpoint(extendLoanDeadline)]
-    fn extend_loan_deadline(&self, loan_id: u64, extra_days: u64) {
-        let caller = self.blockchain().get_caller();
+    #[endpoint(depositLendingFunds)]
+    fn deposit_lending_funds(&self) {
+        self.require_role(Role::Admin);
+        let (token_id, amount) = self.call_value().egld_or_single_fungible_esdt();
+        require!(amount > 0u64, "Valor deve ser maior que zero");
+        require!(self.is_contract_funded_token(&token_id), "Token financiado pelo pool de liquidez");
+        self.lendable_balance(&token_id).update(|balance| *balance += &amount);
+    }
 
-        // 1) Verifica existência e estado do empréstimo
-        require!(!self.loans(loan_id).is_empty(), "Empréstimo não existe");
-        let mut loan = self.loans(loan_id).get();
-        require!(loan.borrower == caller, "Only the borrower can extend their loan");
-        require!(loan.status == LoanStatus::Active, "Cannot extend a non-active loan");
+    // Retira fundos ainda não emprestados de um token sem pool
+    #[endpoint(withdrawLendingFunds)]
+    fn withdraw_lending_funds(&self, token_id: EgldOrEsdtTokenIdentifier, amount: BigUint) {
+        self.require_role(Role::Admin);
+        self.require_multisig_not_configured();
+        self.take_lendable_funds(&token_id, &amount);
+        self.send().direct(&self.blockchain().get_caller(), &token_id, 0, &amount);
+    }
 
-        // 2) Não pode estender se já venceu
-        let now = self.blockchain().get_block_timestamp();
-        require!(now < loan.due_timestamp, "Cannot extend an expired loan");
+    /// Saldo disponível para novos empréstimos de um token sem pool
+    #[view(getLendableBalance)]
+    fn get_lendable_balance(&self, token_id: EgldOrEsdtTokenIdentifier) -> BigUint {
+        self.lendable_balance(&token_id).get()
+    }
 
-        // 3) Calcula e exige a fee de extensão (percentual sobre repayment_amount)
-        let fee_bp = self.extension_fee_percent().get(); // em basis points (1000 = 10%)
-        let expected_fee = &loan.repayment_amount * &BigUint::from(fee_bp) / &BigUint::from(10_000u64);
-        let paid = self.call_value().egld_or_single_esdt().amount.clone();
-        require!(paid == expected_fee, "Incorrect extension fee amount");
+    // Tokens sem pool configurado (EGLD por padrão) são emprestados do saldo do contrato
+    fn is_contract_funded_token(&self, token_id: &EgldOrEsdtTokenIdentifier) -> bool {
+        if self.loan_token_config(token_id).is_empty() {
+            return token_id.is_egld();
+        }
+        self.loan_token_config(token_id).get().liquidity_pool.is_zero()
+    }
 
-        // 4) Atualiza due_timestamp e repayment_amount
-        loan.due_timestamp = loan.due_timestamp + extra_days * 86_400u64;
-        loan.repayment_amount = &loan.repayment_amount + &expected_fee;
+    // Debita do saldo emprestável os fundos de um desembolso
+    fn take_lendable_funds(&self, token_id: &EgldOrEsdtTokenIdentifier, amount: &BigUint) {
+        let available = self.lendable_balance(token_id).get();
+        require!(&available >= amount, "Saldo insuficiente para o empréstimo");
+        self.lendable_balance(token_id).set(available - amount);
+    }
 
-        // 5) Grava as alterações
-        self.loans(loan_id).set(&loan);
+    // Token padrão dos pedidos sem token explícito: o token emprestado pelo
+    // pool (EGLD enquanto o pool não estiver configurado)
+    fn default_loan_token(&self) -> EgldOrEsdtTokenIdentifier {
+        if self.lending_token_id().is_empty() {
+            return EgldOrEsdtTokenIdentifier::egld();
+        }
+        EgldOrEsdtTokenIdentifier::esdt(self.lending_token_id().get())
     }
 
+    // Mesma regra de require_loan_token_limit, sem interromper (usada em quoteLoan)
+    fn is_within_loan_token_limit(&self, token_id: &EgldOrEsdtTokenIdentifier, amount: &BigUint) -> bool {
+        if !self.loan_tokens().contains(token_id) || amount == &0u64 {
+            return false;
+        }
+        let max_amount = self.loan_token_config(token_id).get().max_loan_amount;
+        max_amount == 0u64 || amount <= &max_amount
+    }
 
-    // Deposita EGLD como garantia para um empréstimo existente
-    #[payable("*")]
-    #[endpoint(provideCollateral)]
-    fn provide_collateral(&self, loan_id: u64) {
-        let caller = self.blockchain().get_caller();
+    // Exige um token aceito e um valor dentro do limite configurado para ele
+    fn require_loan_token_limit(&self, token_id: &EgldOrEsdtTokenIdentifier, amount: &BigUint) {
+        require!(self.loan_tokens().contains(token_id), "Token não aceito para empréstimos");
+        require!(amount > &0u64, "Valor do empréstimo deve ser maior que zero");
+        let max_amount = self.loan_token_config(token_id).get().max_loan_amount;
+        require!(
+            max_amount == 0u64 || amount <= &max_amount,
+            "Valor excede o limite do token"
+        );
+    }
 
-        // 1) Verifica existência e dono do empréstimo
-        require!(!self.loans(loan_id).is_empty(), "Empréstimo não existe");
-        let loan = self.loans(loan_id).get();
-        require!(loan.borrower == caller, "Only the borrower can provide collateral");
-        require!(loan.status == LoanStatus::Active, "Cannot provide collateral for non-active loan");
+    // Pool que financia os empréstimos do token (zero = saldo do contrato)
+    fn loan_token_pool(&self, token_id: &EgldOrEsdtTokenIdentifier) -> ManagedAddress {
+        if self.loan_token_config(token_id).is_empty() {
+            require!(
+                !self.liquidity_pool_address().is_empty() && token_id == &self.default_loan_token(),
+                "Pool de liquidez não configurado"
+            );
+            return self.liquidity_pool_address().get();
+        }
+        self.loan_token_config(token_id).get().liquidity_pool
+    }
 
-        // 2) Extrai valor enviado na chamada
-        let amount = self.call_value().egld_or_single_esdt().amount.clone();
-        require!(amount > BigUint::from(0u64), "Collateral amount must be greater than zero");
+    #[view(getLiquidityPoolAddress)]
+    fn get_liquidity_pool_address(&self) -> ManagedAddress {
+        self.liquidity_pool_address().get()
+    }
 
-        // 3) Atualiza o armazenamento de garantia
-        let mut current = self.loan_collateral(loan_id).get();
-        current += amount;
-        self.loan_collateral(loan_id).set(&current);
+    #[view(getLendingTokenId)]
+    fn get_lending_token_id(&self) -> TokenIdentifier {
+        self.lending_token_id().get()
+    }
 
-        // (o saldo do contrato já é creditado automaticamente pelo VM)
+    // Pagamentos só são aceitos no token em que o empréstimo foi concedido
+    fn require_loan_payment_token(&self, loan_id: u64, payment_token: &EgldOrEsdtTokenIdentifier) {
+        require!(payment_token == &self.loan_payment_token(loan_id), "Token de pagamento inválido");
     }
 
-    // Após quitação, devolve a garantia ao tomador
-    #[endpoint(withdrawCollateral)]
-    fn withdraw_collateral(&self, loan_id: u64) {
-        let caller = self.blockchain().get_caller();
+    // Contabiliza um pagamento do empréstimo. O principal é devolvido primeiro;
+    // o que exceder (sem as taxas de atraso) é juros. Empréstimos do pool repassam
+    // o pagamento inteiro ao pool que os financiou; os do saldo do contrato
+    // devolvem o principal ao saldo emprestável e os juros vão aos investidores
+    fn book_loan_payment(&self, loan_id: u64, loan: &Loan<Self::Api>, payment: &BigUint, late_fee: &BigUint) {
+        let settled = payment - late_fee;
+        let returned = self.pool_principal_returned(loan_id).get();
+        let outstanding = if loan.amount > returned {
+            &loan.amount - &returned
+        } else {
+            BigUint::zero()
+        };
+        let principal_part = core::cmp::min(settled.clone(), outstanding);
+        self.pool_principal_returned(loan_id)
+            .update(|current| *current += &principal_part);
 
-        // 1) Verifica existência e status do empréstimo
-        require!(!self.loans(loan_id).is_empty(), "Empréstimo não existe");
-        let loan = self.loans(loan_id).get();
-        require!(loan.borrower == caller, "Only the borrower can withdraw collateral");
-        require!(loan.status == LoanStatus::Repaid, "Cannot withdraw collateral for non-repaid loan");
+        // Empréstimos encerrados já saíram da exposição do tomador
+        if loan.status == LoanStatus::Active && self.sync_borrower_exposure(&loan.borrower) {
+            self.release_borrower_principal(&loan.borrower, &principal_part);
+        }
 
-        // 2) Lê a garantia acumulada
-        let collateral = self.loan_collateral(loan_id).get();
-        require!(collateral > BigUint::from(0u64), "No collateral to withdraw");
+        if !self.loan_pool_token(loan_id).is_empty() {
+            let token_id = self.loan_pool_token(loan_id).get();
+            self.liquidity_pool_proxy(self.funding_pool_address(loan_id))
+                .receive_loan_repayment(principal_part)
+                .with_esdt_transfer((token_id, 0u64, payment.clone()))
+                .transfer_execute();
+            return;
+        }
 
-        // 3) Zera o armazenamento
-        self.loan_collateral(loan_id).set(&BigUint::from(0u64));
+        let token_id = self.loan_payment_token(loan_id);
+        self.lendable_balance(&token_id).update(|balance| *balance += &principal_part);
+        self.total_interest_earned().update(|total| *total += &settled - &principal_part);
+        if late_fee > &0u64 {
+            self.protocol_fees(&token_id).update(|fees| *fees += late_fee);
+        }
+    }
 
-        // 4) Envia o EGLD de volta ao tomador
-        self.send().direct_egld(&caller, &collateral);
+    // Pool que financiou o empréstimo (zero se veio do saldo do contrato).
+    // Empréstimos anteriores ao registro por empréstimo usam o pool atual do token
+    fn funding_pool_address(&self, loan_id: u64) -> ManagedAddress {
+        if self.loan_pool_token(loan_id).is_empty() {
+            return ManagedAddress::zero();
+        }
+        if !self.loan_pool_address(loan_id).is_empty() {
+            return self.loan_pool_address(loan_id).get();
+        }
+        let token_id = EgldOrEsdtTokenIdentifier::esdt(self.loan_pool_token(loan_id).get());
+        self.loan_token_pool(&token_id)
     }
 
-    // Marca manualmente um empréstimo como inadimplente
-    #[only_owner]
-    #[endpoint(markLoanDefaulted)]
-    fn mark_loan_defaulted(&self, loan_id: u64) {
-        // 1) Verifica que o empréstimo existe
-        require!(!self.loans(loan_id).is_empty(), "Empréstimo não existe");
+    // Marca o empréstimo como financiado pelo pool
+    fn set_loan_funding_pool(&self, loan_id: u64, token_id: &TokenIdentifier, pool_address: &ManagedAddress) {
+        self.loan_pool_token(loan_id).set(token_id);
+        self.loan_pool_address(loan_id).set(pool_address);
+    }
 
-        // 2) Carrega o empréstimo e garante que ainda está ativo
-        let mut loan = self.loans(loan_id).get();
-        require!(loan.status == LoanStatus::Active, "Só é possível marcar empréstimos ativos");
+    // Configura o contrato DebtToken que emite o NFT de dívida de cada empréstimo
+    #[endpoint(setDebtToken)]
+    fn set_debt_token(&self, debt_token_address: ManagedAddress, token_id: TokenIdentifier) {
+        self.require_role(Role::Admin);
+        require!(!debt_token_address.is_zero(), "Endereço do DebtToken inválido");
+        require!(token_id.is_valid_esdt_identifier(), "Token inválido");
 
-        // 3) Atualiza o status para Defaulted
-        loan.status = LoanStatus::Defaulted;
-        self.loans(loan_id).set(loan);
+        self.debt_token_address().set(debt_token_address);
+        self.debt_nft_token_id().set(token_id);
     }
 
+    #[view(getDebtTokenAddress)]
+    fn get_debt_token_address(&self) -> ManagedAddress {
+        self.debt_token_address().get()
+    }
 
-    // Apreende a garantia de um empréstimo inadimplente (owner)
-    #[only_owner]
-    #[endpoint(forfeitCollateral)]
-    fn forfeit_collateral(&self, loan_id: u64) {
-        // 1) Verifica que o empréstimo existe
-        require!(!self.loans(loan_id).is_empty(), "Empréstimo não existe");
+    /// Retorna o nonce do NFT de dívida de um empréstimo, ou zero se não existir
+    #[view(getLoanDebtNftNonce)]
+    fn get_loan_debt_nft_nonce(&self, loan_id: u64) -> u64 {
+        self.loan_debt_nft_nonce(loan_id).get()
+    }
 
-        // 2) Carrega o empréstimo e exige que esteja Defaulted
-        let loan = self.loans(loan_id).get();
-        require!(loan.status == LoanStatus::Defaulted, "Somente inadimplentes");
+    /// Retorna o empréstimo associado a um nonce de NFT de dívida
+    #[view(getDebtNftLoanId)]
+    fn get_debt_nft_loan_id(&self, nft_nonce: u64) -> u64 {
+        // O id 0 é um empréstimo válido, então confirma o mapeamento inverso
+        let loan_id = self.debt_nft_loan_id(nft_nonce).get();
+        require!(
+            nft_nonce > 0 && self.loan_debt_nft_nonce(loan_id).get() == nft_nonce,
+            "NFT de dívida não encontrado"
+        );
+        loan_id
+    }
 
-        // 3) Lê o valor da garantia
-        let collateral = self.loan_collateral(loan_id).get();
-        require!(collateral > BigUint::from(0u64), "Sem garantia para apreender");
+    // Etapas comuns a todo empréstimo recém-registrado
+    fn on_loan_created(&self, loan_id: u64) {
+        self.index_active_loan(loan_id);
+        self.mint_debt_nft(loan_id);
 
-        // 4) Zera o storage de collateral
-        self.loan_collateral(loan_id).set(BigUint::from(0u64));
+        let loan = self.loans(loan_id).get();
+        self.register_loan_apr(loan_id, &loan);
+        if self.sync_borrower_exposure(&loan.borrower) {
+            self.borrower_active_loans(&loan.borrower).update(|count| *count += 1);
+            let outstanding = self.loan_outstanding_principal(loan_id, &loan);
+            self.borrower_outstanding_principal(&loan.borrower).update(|total| *total += outstanding);
+        }
+        self.loan_created_event(
+            loan_id,
+            &loan.borrower,
+            &loan.amount,
+            &loan.repayment_amount,
+            loan.interest_rate,
+            loan.due_timestamp,
+            loan.creation_timestamp,
+        );
+    }
 
-        // Observação: os EGLD já estão em posse do contrato (foram bloqueados em provideCollateral)
-        // portanto não é necessário fazer send() aqui.
+    // Etapas comuns a todo empréstimo que deixa o estado Active
+    // (ou Defaulted, quando a garantia é liquidada)
+    fn on_loan_closed(&self, loan_id: u64, previous_status: &LoanStatus, status: &LoanStatus) {
+        self.unindex_active_loan(loan_id);
+        if previous_status == &LoanStatus::Active {
+            let loan = self.loans(loan_id).get();
+            if self.sync_borrower_exposure(&loan.borrower) {
+                self.borrower_active_loans(&loan.borrower).update(|count| *count = count.saturating_sub(1));
+                let outstanding = self.loan_outstanding_principal(loan_id, &loan);
+                self.release_borrower_principal(&loan.borrower, &outstanding);
+            }
+        }
+        self.settle_debt_nft(loan_id, status);
+        self.settle_guarantor_stakes(loan_id, status);
+
+        self.loan_status_changed_event(
+            loan_id,
+            &self.loans(loan_id).get().borrower,
+            previous_status,
+            status,
+            self.current_timestamp(),
+        );
     }
 
-    #[payable("*")]
-    #[endpoint(provideCollateralForNewLoan)]
-    fn provide_collateral_for_new_loan(&self) {
-        let caller = self.blockchain().get_caller();
-        // quanto EGLD o usuário enviou?
-        let deposit = self.call_value()
-            .egld_or_single_esdt()
-            .amount
-            .clone();
-        // não aceitar zero
-        require!(deposit > BigUint::from(0u64), "Collateral deve ser maior que zero");
-        // acumula em pending_collateral(caller)
+    // Emite o NFT de dívida de um empréstimo recém-criado, se o DebtToken estiver configurado
+    fn mint_debt_nft(&self, loan_id: u64) {
+        if self.debt_token_address().is_empty() {
+            return;
+        }
+        let loan = self.loans(loan_id).get();
+        let nft_nonce: u64 = self
+            .debt_token_proxy(self.debt_token_address().get())
+            .create_debt_nft(
+                loan_id,
+                loan.borrower,
+                loan.amount,
+                loan.interest_rate,
+                loan.due_timestamp,
+            )
+            .execute_on_dest_context();
+
+        self.loan_debt_nft_nonce(loan_id).set(nft_nonce);
+        self.debt_nft_loan_id(nft_nonce).set(loan_id);
+    }
+
+    // Encerra o NFT de dívida conforme o novo status do empréstimo:
+    // inadimplentes são remarcados; pagos e liquidados são devolvidos e queimados.
+    // Os mapeamentos loan_id <-> nonce são mantidos para o histórico.
+    fn settle_debt_nft(&self, loan_id: u64, status: &LoanStatus) {
+        if self.loan_debt_nft_nonce(loan_id).is_empty() {
+            return;
+        }
+        let debt_token_address = self.debt_token_address().get();
+        match status {
+            LoanStatus::Defaulted => {
+                self.debt_token_proxy(debt_token_address)
+                    .mark_debt_nft_defaulted(loan_id)
+                    .execute_on_dest_context::<()>();
+            },
+            LoanStatus::Repaid | LoanStatus::Liquidated | LoanStatus::Refinanced => {
+                let nft_nonce = self.loan_debt_nft_nonce(loan_id).get();
+                self.debt_token_proxy(debt_token_address)
+                    .burn_debt_nft(loan_id)
+                    .with_esdt_transfer((self.debt_nft_token_id().get(), nft_nonce, BigUint::from(1u32)))
+                    .execute_on_dest_context::<()>();
+            },
+            LoanStatus::Active => {}
+        }
+    }
+
+    // Reporta a quitação ao ReputationScore: em dia ou com os dias de atraso
+    fn report_repayment(&self, loan: &Loan<Self::Api>, paid_at: u64) {
+        let rs_address = self.reputation_score_address().get();
+        if paid_at <= loan.due_timestamp {
+            self.reputation_score_proxy(rs_address)
+                .update_score_after_repayment(loan.borrower.clone(), loan.amount.clone())
+                .transfer_execute();
+        } else {
+            // Dias de atraso, arredondados para cima
+            let delay_days = (paid_at - loan.due_timestamp).div_ceil(86400);
+            self.reputation_score_proxy(rs_address)
+                .update_score_after_late_payment(loan.borrower.clone(), loan.amount.clone(), delay_days)
+                .transfer_execute();
+        }
+    }
+
+    // Reporta a inadimplência ao ReputationScore
+    fn report_default(&self, loan: &Loan<Self::Api>) {
+        self.reputation_score_proxy(self.reputation_score_address().get())
+            .update_score_after_default(loan.borrower.clone(), loan.amount.clone())
+            .transfer_execute();
+    }
+
+
+
+
+    //-----------------------------------------------
+
+    // Adia o vencimento de um empréstimo (pagando a taxa de extensão)
+    #[payable("*")]
+    #[endpoint(extendLoanDeadline)]
+    fn extend_loan_deadline(&self, loan_id: u64, extra_days: u64) {
+        let caller = self.blockchain().get_caller();
+
+        // 1) Verifica existência e estado do empréstimo
+        require!(!self.loans(loan_id).is_empty(), "Empréstimo não existe");
+        let mut loan = self.loans(loan_id).get();
+        require!(loan.borrower == caller, "Only the borrower can extend their loan");
+        require!(loan.status == LoanStatus::Active, "Cannot extend a non-active loan");
+
+        // 2) Não pode estender se já venceu
+        let now = self.current_timestamp();
+        require!(now < loan.due_timestamp, "Cannot extend an expired loan");
+
+        // 3) Calcula e exige a fee de extensão (percentual sobre repayment_amount)
+        let fee_bp = self.extension_fee_percent().get(); // em basis points (1000 = 10%)
+        let expected_fee = &loan.repayment_amount * &BigUint::from(fee_bp) / &BigUint::from(10_000u64);
+        let paid_token = self.call_value().egld_or_single_esdt().token_identifier.clone();
+        let paid = self.call_value().egld_or_single_esdt().amount.clone();
+        if expected_fee > 0u64 {
+            self.require_loan_payment_token(loan_id, &paid_token);
+        }
+        require!(paid == expected_fee, "Incorrect extension fee amount");
+        if expected_fee > 0u64 {
+            self.protocol_fees(&paid_token).update(|fees| *fees += &expected_fee);
+        }
+
+        // 4) Atualiza due_timestamp e repayment_amount
+        let was_indexed = self.unindex_active_loan(loan_id);
+        let previous_due = loan.due_timestamp;
+        loan.due_timestamp += extra_days * 86_400u64;
+        loan.repayment_amount = &loan.repayment_amount + &expected_fee;
+
+        // 5) Grava as alterações e move o empréstimo para o novo dia de vencimento
+        self.loans(loan_id).set(&loan);
+        if was_indexed {
+            self.index_active_loan(loan_id);
+        }
+        self.loan_extended_event(loan_id, &caller, previous_due, loan.due_timestamp, &expected_fee, now);
+    }
+
+    // Consolida empréstimos ativos do tomador em um único empréstimo novo,
+    // reprecificado pela pontuação atual consultada no ReputationScore
+    #[endpoint(refinanceLoans)]
+    fn refinance_loans(&self, loan_ids: ManagedVec<u64>, new_term: LoanTerm) {
+        require!(!self.paused().get(), "Contract is paused");
+        let caller = self.blockchain().get_caller();
+        self.require_refinanceable_loans(&caller, &loan_ids);
+
+        let rs_address = self.reputation_score_address().get();
+        self.reputation_score_proxy(rs_address)
+            .get_user_score(caller.clone())
+            .with_callback(self.callbacks().refinance_callback(caller, loan_ids, new_term))
+            .call_and_exit();
+    }
+
+    /// Empréstimo que substituiu `loan_id` em um refinanciamento, se houver
+    #[view(getRefinancedInto)]
+    fn get_refinanced_into(&self, loan_id: u64) -> OptionalValue<u64> {
+        if self.loan_refinanced_into(loan_id).is_empty() {
+            return OptionalValue::None;
+        }
+        OptionalValue::Some(self.loan_refinanced_into(loan_id).get())
+    }
+
+    /// Empréstimos consolidados em `loan_id`
+    #[view(getRefinancedFrom)]
+    fn get_refinanced_from(&self, loan_id: u64) -> MultiValueEncoded<u64> {
+        self.loan_refinanced_from(loan_id).iter().collect()
+    }
+
+    // Todos os empréstimos precisam ser do tomador, estar ativos e em dia, sem
+    // repetição, no mesmo token, com a mesma origem de fundos e garantia no mesmo
+    // token. Um empréstimo vencido (ou com parcela em atraso) segue para o
+    // default; refinanciá-lo adiaria a inadimplência indefinidamente
+    fn require_refinanceable_loans(&self, borrower: &ManagedAddress, loan_ids: &ManagedVec<u64>) {
+        require!(!loan_ids.is_empty(), "Nenhum empréstimo informado");
+        let first_id = loan_ids.get(0);
+        let now = self.current_timestamp();
+
+        for (i, loan_id) in loan_ids.iter().enumerate() {
+            require!(!self.loans(loan_id).is_empty(), "Empréstimo não existe");
+            let loan = self.loans(loan_id).get();
+            require!(&loan.borrower == borrower, "Apenas o tomador pode refinanciar o empréstimo");
+            require!(loan.status == LoanStatus::Active, "Empréstimo não está ativo");
+            require!(
+                now <= loan.due_timestamp
+                    && self.loan_late_fee_balance(loan_id).get() == 0u64
+                    && self.late_fee_since_checkpoint(loan_id, now) == 0u64,
+                "Empréstimo em atraso não pode ser refinanciado"
+            );
+            require!(
+                !loan_ids.iter().take(i).any(|previous| previous == loan_id),
+                "Empréstimo repetido na lista"
+            );
+            require!(
+                self.loan_payment_token(loan_id) == self.loan_payment_token(first_id),
+                "Empréstimos em tokens diferentes não podem ser consolidados"
+            );
+            require!(
+                self.loan_pool_token(loan_id).is_empty() == self.loan_pool_token(first_id).is_empty(),
+                "Empréstimos do pool e do contrato não podem ser consolidados"
+            );
+            require!(
+                self.funding_pool_address(loan_id) == self.funding_pool_address(first_id),
+                "Empréstimos de pools diferentes não podem ser consolidados"
+            );
+            require!(
+                self.loan_guarantee_request(loan_id).is_empty(),
+                "Empréstimos com fiadores não podem ser refinanciados"
+            );
+        }
+
+        let mut collateral_token: Option<EgldOrEsdtTokenIdentifier> = None;
+        for loan_id in loan_ids.iter() {
+            if self.loan_collateral(loan_id).get() == 0u64 {
+                continue;
+            }
+            let token = self.collateral_token(loan_id);
+            if let Some(expected) = &collateral_token {
+                require!(expected == &token, "Garantias em tokens diferentes não podem ser consolidadas");
+            }
+            collateral_token = Some(token);
+        }
+    }
+
+    // Encerra os empréstimos como Refinanced e abre o novo com a dívida
+    // total (saldo com juros decorridos + taxas de atraso) como principal. Garantias e o
+    // principal ainda não devolvido passam para o novo empréstimo; os juros capitalizados
+    // entram como principal já devolvido, para serem contabilizados como juros no pagamento
+    fn consolidate_loans(
+        &self,
+        borrower: &ManagedAddress,
+        loan_ids: &ManagedVec<u64>,
+        new_term: LoanTerm,
+        user_score: u64,
+    ) -> u64 {
+        let first_id = loan_ids.get(0);
+        let token_id = self.loan_payment_token(first_id);
+        let pool_token = self.loan_pool_token(first_id);
+        let pool_address = self.funding_pool_address(first_id);
+
+        let mut principal = BigUint::zero();
+        let mut outstanding_principal = BigUint::zero();
+        let mut collateral = BigUint::zero();
+        let mut collateral_token = EgldOrEsdtTokenIdentifier::egld();
+        let now = self.current_timestamp();
+        for loan_id in loan_ids.iter() {
+            self.accrue_late_fees(loan_id);
+            let mut loan = self.loans(loan_id).get();
+            principal += &loan.repayment_amount - &self.early_repayment_rebate(loan_id, &loan, now)
+                + self.loan_late_fee_balance(loan_id).take();
+
+            let returned = self.pool_principal_returned(loan_id).get();
+            if loan.amount > returned {
+                outstanding_principal += &loan.amount - &returned;
+            }
+
+            let loan_collateral = self.loan_collateral(loan_id).take();
+            if loan_collateral > 0u64 {
+                collateral_token = self.collateral_token(loan_id);
+                collateral += loan_collateral;
+            }
+
+            loan.status = LoanStatus::Refinanced;
+            self.loans(loan_id).set(&loan);
+            self.on_loan_closed(loan_id, &LoanStatus::Active, &LoanStatus::Refinanced);
+        }
+
+        let base_rate = self.calculate_token_interest_rate(&token_id, user_score);
+        let interest_rate = self.calculate_interest_rate_for_term(base_rate, new_term);
+        let repayment_amount = &principal + &(&principal * interest_rate / 10000u64);
+        let new_loan_id = self.store_new_loan(borrower, &principal, repayment_amount, &token_id, interest_rate, new_term);
+
+        // Só o principal original volta ao pool (ou ao saldo emprestável) como principal
+        if !pool_token.is_empty() {
+            self.set_loan_funding_pool(new_loan_id, &pool_token.get(), &pool_address);
+        }
+        if principal > outstanding_principal {
+            self.pool_principal_returned(new_loan_id).set(&principal - &outstanding_principal);
+        }
+        if collateral > 0u64 {
+            self.loan_collateral(new_loan_id).set(&collateral);
+            self.loan_collateral_token(new_loan_id).set(&collateral_token);
+        }
+        self.on_loan_created(new_loan_id);
+
+        for loan_id in loan_ids.iter() {
+            self.loan_refinanced_into(loan_id).set(new_loan_id);
+            self.loan_refinanced_from(new_loan_id).push(&loan_id);
+        }
+        self.loans_refinanced_event(new_loan_id, borrower, loan_ids, &principal, interest_rate);
+
+        new_loan_id
+    }
+
+    // ===== Linhas de crédito rotativo =====
+
+    // Abre uma linha de crédito no token padrão; o limite vem de
+    // ReputationScore::calculateMaxLoanAmount
+    #[endpoint(openCreditLine)]
+    fn open_credit_line(&self) {
+        require!(!self.paused().get(), "Contract is paused");
+        let caller = self.blockchain().get_caller();
+        require!(self.credit_lines(&caller).is_empty(), "Linha de crédito já existe");
+        self.require_not_blacklisted(&caller);
+        let token_id = self.default_loan_token();
+        require!(
+            self.loan_token_pool(&token_id).is_zero(),
+            "Linhas de crédito são financiadas pelo saldo do contrato"
+        );
+
+        self.request_credit_limit(caller, OptionalValue::Some(token_id));
+    }
+
+    // Recalcula o limite com a pontuação atual (qualquer um pode chamar)
+    #[endpoint(refreshCreditLimit)]
+    fn refresh_credit_limit(&self, borrower: ManagedAddress) {
+        require!(!self.credit_lines(&borrower).is_empty(), "Linha de crédito não existe");
+        self.request_credit_limit(borrower, OptionalValue::None);
+    }
+
+    // Saca do crédito disponível
+    #[endpoint(drawCredit)]
+    fn draw_credit(&self, amount: BigUint) {
+        require!(!self.paused().get(), "Contract is paused");
+        let caller = self.blockchain().get_caller();
+        require!(!self.credit_lines(&caller).is_empty(), "Linha de crédito não existe");
+        require!(amount > 0u64, "Valor do empréstimo deve ser maior que zero");
+        self.require_not_blacklisted(&caller);
+
+        let mut line = self.accrued_credit_line(&caller);
+        require!(amount <= self.available_credit(&line), "Valor excede o crédito disponível");
+        require!(!self.is_credit_line_delinquent(&caller, &line), "Linha de crédito em atraso");
+        self.take_lendable_funds(&line.token_id, &amount);
+
+        // Saldo zerado: o período de pagamento começa no novo saque
+        if line.principal == 0u64 && line.accrued_interest == 0u64 {
+            self.credit_line_paid_through(&caller).set(self.current_timestamp());
+        }
+        line.principal += &amount;
+        self.credit_lines(&caller).set(&line);
+        self.send().direct(&caller, &line.token_id, 0, &amount);
+        self.credit_drawn_event(&caller, &amount, &line.principal);
+    }
+
+    // Paga a linha: primeiro os juros acumulados, depois o principal
+    #[payable("*")]
+    #[endpoint(repayCredit)]
+    fn repay_credit(&self) {
+        let caller = self.blockchain().get_caller();
+        require!(!self.credit_lines(&caller).is_empty(), "Linha de crédito não existe");
+        let (payment_token, payment) = self.call_value().egld_or_single_fungible_esdt();
+
+        let mut line = self.accrued_credit_line(&caller);
+        require!(payment_token == line.token_id, "Token de pagamento inválido");
+        require!(payment > 0u64, "Pagamentos devem ser maiores que zero");
+        require!(
+            payment <= &line.principal + &line.accrued_interest,
+            "Valor de pagamento excede o montante devido"
+        );
+
+        let interest_paid = core::cmp::min(payment.clone(), line.accrued_interest.clone());
+        let principal_paid = &payment - &interest_paid;
+        line.accrued_interest -= &interest_paid;
+        line.principal -= &principal_paid;
+        self.credit_lines(&caller).set(&line);
+        // Juros quitados: a linha fica em dia por mais um período
+        if line.accrued_interest == 0u64 && !self.credit_line_defaulted(&caller).get() {
+            self.credit_line_paid_through(&caller).set(self.current_timestamp());
+        }
+
+        self.lendable_balance(&line.token_id).update(|balance| *balance += &principal_paid);
+        self.total_interest_earned().update(|total| *total += &interest_paid);
+        self.credit_repaid_event(&caller, &principal_paid, &interest_paid);
+    }
+
+    // Encerra uma linha sem saldo devedor
+    #[endpoint(closeCreditLine)]
+    fn close_credit_line(&self) {
+        let caller = self.blockchain().get_caller();
+        require!(!self.credit_lines(&caller).is_empty(), "Linha de crédito não existe");
+        let line = self.accrued_credit_line(&caller);
+        require!(
+            line.principal == 0u64 && line.accrued_interest == 0u64,
+            "Linha de crédito possui saldo devedor"
+        );
+
+        self.credit_lines(&caller).clear();
+        self.credit_line_paid_through(&caller).clear();
+        self.credit_line_defaulted(&caller).clear();
+        self.credit_line_closed_event(&caller);
+    }
+
+    // Declara inadimplente uma linha com juros sem pagamento além do prazo de
+    // default. A linha é congelada (sem saques nem novos juros), o default é
+    // reportado ao ReputationScore e o tomador ainda pode quitar o saldo
+    #[endpoint(defaultCreditLine)]
+    fn default_credit_line(&self, borrower: ManagedAddress) {
+        require!(!self.credit_lines(&borrower).is_empty(), "Linha de crédito não existe");
+        require!(!self.credit_line_defaulted(&borrower).get(), "Linha de crédito já está inadimplente");
+        let line = self.accrued_credit_line(&borrower);
+        require!(
+            line.principal > 0u64
+                && self.current_timestamp() > self.credit_line_due_timestamp(&borrower, &line) + CREDIT_LINE_DEFAULT_PERIOD,
+            "Linha de crédito não está inadimplente"
+        );
+
+        self.credit_lines(&borrower).set(&line);
+        self.credit_line_defaulted(&borrower).set(true);
+        self.reputation_score_proxy(self.reputation_score_address().get())
+            .update_score_after_default(borrower.clone(), line.principal.clone())
+            .transfer_execute();
+        self.credit_line_defaulted_event(&borrower, &line.principal, &line.accrued_interest);
+    }
+
+    /// Prazo para quitar os juros da linha (zero se não há saldo devedor)
+    #[view(getCreditLineDueTimestamp)]
+    fn get_credit_line_due_timestamp(&self, borrower: ManagedAddress) -> u64 {
+        if self.credit_lines(&borrower).is_empty() {
+            return 0;
+        }
+        let line = self.accrued_credit_line(&borrower);
+        if line.principal == 0u64 && line.accrued_interest == 0u64 {
+            return 0;
+        }
+        self.credit_line_due_timestamp(&borrower, &line)
+    }
+
+    #[view(isCreditLineDefaulted)]
+    fn is_credit_line_defaulted(&self, borrower: ManagedAddress) -> bool {
+        self.credit_line_defaulted(&borrower).get()
+    }
+
+    #[view(getCreditLine)]
+    fn get_credit_line(&self, borrower: ManagedAddress) -> OptionalValue<CreditLine<Self::Api>> {
+        if self.credit_lines(&borrower).is_empty() {
+            return OptionalValue::None;
+        }
+        OptionalValue::Some(self.accrued_credit_line(&borrower))
+    }
+
+    /// Crédito ainda disponível para saque (limite - principal - juros)
+    #[view(getAvailableCredit)]
+    fn get_available_credit(&self, borrower: ManagedAddress) -> BigUint {
+        if self.credit_lines(&borrower).is_empty() {
+            return BigUint::zero();
+        }
+        self.available_credit(&self.accrued_credit_line(&borrower))
+    }
+
+    /// Utilização do limite em pontos base (pode passar de 10000 se o limite cair)
+    #[view(getCreditUtilization)]
+    fn get_credit_utilization(&self, borrower: ManagedAddress) -> u64 {
+        if self.credit_lines(&borrower).is_empty() {
+            return 0;
+        }
+        let line = self.accrued_credit_line(&borrower);
+        if line.limit == 0u64 {
+            return 0;
+        }
+        let used = (&line.principal + &line.accrued_interest) * 10000u64 / &line.limit;
+        used.to_u64().unwrap_or(u64::MAX)
+    }
+
+    /// Juros acumulados até agora e ainda não pagos
+    #[view(getCreditLineInterest)]
+    fn get_credit_line_interest(&self, borrower: ManagedAddress) -> BigUint {
+        if self.credit_lines(&borrower).is_empty() {
+            return BigUint::zero();
+        }
+        self.accrued_credit_line(&borrower).accrued_interest
+    }
+
+    // Consulta o limite no ReputationScore; com `token_id` abre a linha
+    fn request_credit_limit(&self, borrower: ManagedAddress, token_id: OptionalValue<EgldOrEsdtTokenIdentifier>) {
+        let rs_address = self.reputation_score_address().get();
+        let base_amount = self.base_loan_amount().get();
+        self.reputation_score_proxy(rs_address)
+            .calculate_max_loan_amount(borrower.clone(), base_amount)
+            .with_callback(self.callbacks().credit_limit_callback(borrower, token_id))
+            .call_and_exit();
+    }
+
+    // Linha com os juros incorporados até o bloco atual (não grava).
+    // Linhas inadimplentes não acumulam novos juros
+    fn accrued_credit_line(&self, borrower: &ManagedAddress) -> CreditLine<Self::Api> {
+        let mut line = self.credit_lines(borrower).get();
+        let now = self.current_timestamp();
+        if now > line.last_accrual_timestamp && line.principal > 0u64 && !self.credit_line_defaulted(borrower).get() {
+            let elapsed = now - line.last_accrual_timestamp;
+            line.accrued_interest +=
+                &line.principal * line.interest_rate * elapsed / (10000u64 * SECONDS_PER_YEAR);
+        }
+        line.last_accrual_timestamp = now;
+        line
+    }
+
+    // Linhas abertas antes do registro de pagamentos contam a partir do último acúmulo
+    fn credit_line_due_timestamp(&self, borrower: &ManagedAddress, line: &CreditLine<Self::Api>) -> u64 {
+        let paid_through = if self.credit_line_paid_through(borrower).is_empty() {
+            line.last_accrual_timestamp
+        } else {
+            self.credit_line_paid_through(borrower).get()
+        };
+        paid_through + CREDIT_LINE_PAYMENT_PERIOD
+    }
+
+    fn is_credit_line_delinquent(&self, borrower: &ManagedAddress, line: &CreditLine<Self::Api>) -> bool {
+        if self.credit_line_defaulted(borrower).get() {
+            return true;
+        }
+        line.principal > 0u64 && self.current_timestamp() > self.credit_line_due_timestamp(borrower, line)
+    }
+
+    fn available_credit(&self, line: &CreditLine<Self::Api>) -> BigUint {
+        let used = &line.principal + &line.accrued_interest;
+        if line.limit > used {
+            &line.limit - &used
+        } else {
+            BigUint::zero()
+        }
+    }
+
+    // Taxa anual das linhas: taxa base do token (ou a taxa base global)
+    fn credit_line_rate(&self, token_id: &EgldOrEsdtTokenIdentifier) -> u64 {
+        if self.loan_token_config(token_id).is_empty() {
+            return self.interest_rate_base().get();
+        }
+        self.loan_token_config(token_id).get().interest_rate_base
+    }
+
+
+    // ===== Empréstimos com fiadores =====
+
+    // Pede um empréstimo no token padrão indicando os fiadores; cada fiador
+    // bloqueia parte da garantia exigida com pledgeGuarantee
+    #[endpoint(requestGuaranteedLoan)]
+    fn request_guaranteed_loan(
+        &self,
+        amount: BigUint,
+        term: LoanTerm,
+        guarantors: MultiValueEncoded<ManagedAddress>,
+    ) -> u64 {
+        require!(!self.paused().get(), "Contract is paused");
+        let caller = self.blockchain().get_caller();
+        require!(amount > 0u64, "Valor do empréstimo deve ser maior que zero");
+        require!(!guarantors.is_empty(), "Nenhum fiador informado");
+        self.require_not_blacklisted(&caller);
+        require!(guarantors.len() <= MAX_GUARANTORS, "Número de fiadores excede o máximo");
+
+        let request_id = self.guarantee_request_counter().get() + 1;
+        self.guarantee_request_counter().set(request_id);
+        for guarantor in guarantors {
+            require!(!guarantor.is_zero(), "Endereço não pode ser zero");
+            require!(guarantor != caller, "O tomador não pode ser seu próprio fiador");
+            require!(
+                self.guarantee_request_guarantors(request_id).insert(guarantor),
+                "Fiador repetido na lista"
+            );
+        }
+
+        let required_stake = &amount * self.guarantor_stake_ratio_or_default() / 10000u64;
+        require!(required_stake > 0u64, "Garantia exigida dos fiadores é zero");
+        let request = GuaranteedLoanRequest {
+            borrower: caller.clone(),
+            amount: amount.clone(),
+            token_id: self.default_loan_token(),
+            term,
+            required_stake: required_stake.clone(),
+            pledged_stake: BigUint::zero(),
+            loan_id: None,
+        };
+        self.guarantee_requests(request_id).set(&request);
+
+        self.guaranteed_loan_requested_event(request_id, &caller, &amount, &required_stake);
+        request_id
+    }
+
+    // Fiador bloqueia sua garantia, no token do empréstimo, até o limite exigido
+    #[payable("*")]
+    #[endpoint(pledgeGuarantee)]
+    fn pledge_guarantee(&self, request_id: u64) {
+        let caller = self.blockchain().get_caller();
+        let mut request = self.open_guarantee_request(request_id);
+        require!(
+            self.guarantee_request_guarantors(request_id).contains(&caller),
+            "Apenas fiadores indicados podem garantir o empréstimo"
+        );
+
+        let (token, amount) = self.call_value().egld_or_single_fungible_esdt();
+        require!(token == request.token_id, "Token de garantia inválido");
+        require!(amount > 0u64, "Garantia deve ser maior que zero");
+        request.pledged_stake += &amount;
+        require!(request.pledged_stake <= request.required_stake, "Garantia excede o valor exigido");
+
+        self.guarantor_stake(request_id, &caller).update(|stake| *stake += &amount);
+        self.guarantee_requests(request_id).set(&request);
+        self.guarantee_pledged_event(request_id, &caller, &amount);
+    }
+
+    // Fiador retira sua garantia enquanto o empréstimo não foi aberto
+    #[endpoint(withdrawGuarantee)]
+    fn withdraw_guarantee(&self, request_id: u64) {
+        let caller = self.blockchain().get_caller();
+        let mut request = self.open_guarantee_request(request_id);
+        self.require_guarantee_request_not_opening(request_id);
+
+        let stake = self.guarantor_stake(request_id, &caller).take();
+        require!(stake > 0u64, "Fiador não possui garantia neste pedido");
+        request.pledged_stake -= &stake;
+        self.guarantee_requests(request_id).set(&request);
+
+        self.send().direct(&caller, &request.token_id, 0, &stake);
+        self.guarantee_released_event(request_id, &caller, &stake);
+    }
+
+    // Tomador cancela o pedido; as garantias voltam aos fiadores
+    #[endpoint(cancelGuaranteedLoan)]
+    fn cancel_guaranteed_loan(&self, request_id: u64) {
+        let request = self.open_guarantee_request(request_id);
+        require!(
+            request.borrower == self.blockchain().get_caller(),
+            "Apenas o tomador pode alterar o pedido"
+        );
+        self.require_guarantee_request_not_opening(request_id);
+
+        self.release_guarantor_stakes(request_id, &request.token_id);
+        self.guarantee_request_guarantors(request_id).clear();
+        self.guarantee_requests(request_id).clear();
+        self.guaranteed_loan_cancelled_event(request_id);
+    }
+
+    // Com a garantia exigida completa, segue o fluxo normal de elegibilidade;
+    // as garantias são vinculadas ao empréstimo quando ele é registrado
+    #[endpoint(openGuaranteedLoan)]
+    fn open_guaranteed_loan(&self, request_id: u64) {
+        require!(!self.paused().get(), "Contract is paused");
+        let caller = self.blockchain().get_caller();
+        let request = self.open_guarantee_request(request_id);
+        require!(request.borrower == caller, "Apenas o tomador pode alterar o pedido");
+        require!(
+            request.pledged_stake >= request.required_stake,
+            "Garantias dos fiadores insuficientes"
+        );
+        self.require_guarantee_request_not_opening(request_id);
+        self.require_within_unscored_exposure(&caller);
+
+        // O pedido segue junto com a decisão de crédito até o registro do empréstimo
+        self.request_credit_decision(caller, request.amount, request.token_id, request.term, None, request_id);
+    }
+
+    #[endpoint(setGuarantorStakeRatio)]
+    fn set_guarantor_stake_ratio(&self, ratio: u64) {
+        self.set_parameter_directly(ParamType::GuarantorStakeRatio, ratio);
+    }
+
+    #[view(getGuarantorStakeRatio)]
+    fn get_guarantor_stake_ratio(&self) -> u64 {
+        self.guarantor_stake_ratio_or_default()
+    }
+
+    #[view(getGuaranteedLoanRequest)]
+    fn get_guaranteed_loan_request(&self, request_id: u64) -> OptionalValue<GuaranteedLoanRequest<Self::Api>> {
+        if self.guarantee_requests(request_id).is_empty() {
+            return OptionalValue::None;
+        }
+        OptionalValue::Some(self.guarantee_requests(request_id).get())
+    }
+
+    // Fiadores de um pedido e a garantia bloqueada por cada um
+    #[view(getGuarantors)]
+    fn get_guarantors(&self, request_id: u64) -> MultiValueEncoded<MultiValue2<ManagedAddress, BigUint>> {
+        let mut result = MultiValueEncoded::new();
+        for guarantor in self.guarantee_request_guarantors(request_id).iter() {
+            let stake = self.guarantor_stake(request_id, &guarantor).get();
+            result.push((guarantor, stake).into());
+        }
+        result
+    }
+
+    #[view(getLoanGuaranteeRequest)]
+    fn get_loan_guarantee_request(&self, loan_id: u64) -> OptionalValue<u64> {
+        if self.loan_guarantee_request(loan_id).is_empty() {
+            return OptionalValue::None;
+        }
+        OptionalValue::Some(self.loan_guarantee_request(loan_id).get())
+    }
+
+    fn guarantor_stake_ratio_or_default(&self) -> u64 {
+        if self.guarantor_stake_ratio().get() == 0 {
+            DEFAULT_GUARANTOR_STAKE_RATIO
+        } else {
+            self.guarantor_stake_ratio().get()
+        }
+    }
+
+    // Pedido existente e ainda sem empréstimo aberto
+    fn open_guarantee_request(&self, request_id: u64) -> GuaranteedLoanRequest<Self::Api> {
+        require!(!self.guarantee_requests(request_id).is_empty(), "Pedido de empréstimo não existe");
+        let request = self.guarantee_requests(request_id).get();
+        require!(request.loan_id.is_none(), "Empréstimo já foi aberto");
+        request
+    }
+
+    // Enquanto o pool envia os fundos do empréstimo, as garantias ficam travadas
+    fn require_guarantee_request_not_opening(&self, request_id: u64) {
+        require!(!self.guarantee_request_opening(request_id).get(), "Empréstimo em abertura");
+    }
+
+    // Revalida o pedido no momento do desembolso: as garantias podem ter sido
+    // retiradas enquanto a decisão de crédito era consultada
+    fn require_guarantee_request_ready(&self, borrower: &ManagedAddress, request_id: u64) {
+        let request = self.open_guarantee_request(request_id);
+        require!(&request.borrower == borrower, "Apenas o tomador pode alterar o pedido");
+        require!(
+            request.pledged_stake >= request.required_stake,
+            "Garantias dos fiadores insuficientes"
+        );
+        self.require_guarantee_request_not_opening(request_id);
+    }
+
+    fn attach_guarantee_request(&self, loan_id: u64, request_id: u64) {
+        let mut request = self.guarantee_requests(request_id).get();
+        request.loan_id = Some(loan_id);
+        self.guarantee_requests(request_id).set(&request);
+        self.loan_guarantee_request(loan_id).set(request_id);
+        self.guaranteed_loan_opened_event(request_id, loan_id, &request.pledged_stake);
+    }
+
+    // Pagamento libera as garantias e rende bônus de reputação aos fiadores;
+    // inadimplência apreende as garantias antes da garantia do tomador
+    fn settle_guarantor_stakes(&self, loan_id: u64, status: &LoanStatus) {
+        if self.loan_guarantee_request(loan_id).is_empty() {
+            return;
+        }
+        let request_id = self.loan_guarantee_request(loan_id).get();
+        let token_id = self.guarantee_requests(request_id).get().token_id;
+        match status {
+            LoanStatus::Repaid => {
+                let rs_address = self.reputation_score_address().get();
+                for guarantor in self.guarantee_request_guarantors(request_id).iter() {
+                    let stake = self.guarantor_stake(request_id, &guarantor).get();
+                    if stake > 0u64 {
+                        self.reputation_score_proxy(rs_address.clone())
+                            .update_score_after_guarantee(guarantor, stake)
+                            .transfer_execute();
+                    }
+                }
+                self.release_guarantor_stakes(request_id, &token_id);
+            },
+            LoanStatus::Defaulted => self.seize_guarantor_stakes(loan_id, request_id, &token_id),
+            _ => {},
+        }
+    }
+
+    // Apreende das garantias, proporcionalmente a cada fiador, até o valor de
+    // reembolso em aberto; a dívida restante fica para a garantia do tomador
+    fn seize_guarantor_stakes(&self, loan_id: u64, request_id: u64, token_id: &EgldOrEsdtTokenIdentifier) {
+        let mut loan = self.loans(loan_id).get();
+        let request = self.guarantee_requests(request_id).get();
+        let total_stake = request.pledged_stake;
+        if total_stake == 0u64 {
+            return;
+        }
+        let to_seize = core::cmp::min(total_stake.clone(), loan.repayment_amount.clone());
+
+        let mut seized = BigUint::zero();
+        for guarantor in self.guarantee_request_guarantors(request_id).iter() {
+            let stake = self.guarantor_stake(request_id, &guarantor).take();
+            if stake == 0u64 {
+                continue;
+            }
+            let share = &stake * &to_seize / &total_stake;
+            let refund = &stake - &share;
+            if refund > 0u64 {
+                self.send().direct(&guarantor, token_id, 0, &refund);
+            }
+            seized += &share;
+            self.guarantee_seized_event(request_id, &guarantor, &share);
+        }
+        if seized == 0u64 {
+            return;
+        }
+
+        // Os valores apreendidos quitam parte da dívida como um pagamento: voltam
+        // ao pool que financiou o empréstimo ou ao saldo emprestável e aos investidores
+        self.book_loan_payment(loan_id, &loan, &seized, &BigUint::zero());
+        loan.repayment_amount -= &seized;
+        self.loans(loan_id).set(&loan);
+    }
+
+    fn release_guarantor_stakes(&self, request_id: u64, token_id: &EgldOrEsdtTokenIdentifier) {
+        for guarantor in self.guarantee_request_guarantors(request_id).iter() {
+            let stake = self.guarantor_stake(request_id, &guarantor).take();
+            if stake > 0u64 {
+                self.send().direct(&guarantor, token_id, 0, &stake);
+                self.guarantee_released_event(request_id, &guarantor, &stake);
+            }
+        }
+    }
+
+    // ===== Fila de aprovação de pedidos grandes =====
+
+    // Pedidos a partir deste valor viram uma solicitação Pending em vez de
+    // serem desembolsados automaticamente (zero = desativado)
+    #[endpoint(setUnderwritingThreshold)]
+    fn set_underwriting_threshold(&self, threshold: BigUint) {
+        self.set_config_directly(ConfigChange::UnderwritingThreshold { threshold });
+    }
+
+    #[endpoint(setApplicationLifetime)]
+    fn set_application_lifetime(&self, lifetime_seconds: u64) {
+        self.set_parameter_directly(ParamType::ApplicationLifetime, lifetime_seconds);
+    }
+
+    // Aprova um pedido pendente, opcionalmente reduzindo o valor ou ajustando a taxa
+    // (zero mantém o valor pedido e a taxa calculada pela pontuação)
+    #[endpoint(approveLoanApplication)]
+    fn approve_loan_application(&self, application_id: u64, amount: BigUint, interest_rate: u64) {
+        require!(!self.paused().get(), "Contract is paused");
+        self.require_role(Role::Underwriter);
+        let mut application = self.open_loan_application(application_id);
+
+        if amount > 0u64 {
+            require!(amount <= application.amount, "Valor aprovado excede o valor pedido");
+            application.amount = amount;
+        }
+        if interest_rate > 0 {
+            require!(interest_rate <= 10000, "Taxa de juros não pode exceder 100%");
+            application.interest_rate = interest_rate;
+        }
+
+        // A exposição do tomador pode ter mudado enquanto o pedido aguardava
+        self.require_within_exposure(&application.borrower, &application.amount, application.user_score);
+
+        application.status = ApplicationStatus::Approved;
+        self.loan_applications(application_id).set(&application);
+        self.pending_applications().swap_remove(&application_id);
+        self.loan_application_approved_event(
+            application_id,
+            &self.blockchain().get_caller(),
+            application.interest_rate,
+            &application.amount,
+        );
+
+        // Tokens sem pool são emprestados do saldo emprestável do contrato
+        let pool_address = self.loan_token_pool(&application.token_id);
+        if pool_address.is_zero() {
+            self.take_lendable_funds(&application.token_id, &application.amount);
+            self.disburse_loan_application(application_id);
+            return;
+        }
+
+        self.liquidity_pool_proxy(pool_address.clone())
+            .provide_funds_for_loan(application.amount, application.token_id.unwrap_esdt())
+            .with_callback(self.callbacks().application_funding_callback(application_id, pool_address))
+            .call_and_exit();
+    }
+
+    // Recusa um pedido pendente com um código de motivo e devolve a garantia retida
+    #[endpoint(rejectLoanApplication)]
+    fn reject_loan_application(&self, application_id: u64, reason_code: u32) {
+        self.require_role(Role::Underwriter);
+        let application = self.open_loan_application(application_id);
+
+        self.close_loan_application(application_id, application, ApplicationStatus::Rejected, reason_code);
+        self.loan_application_rejected_event(application_id, &self.blockchain().get_caller(), reason_code);
+    }
+
+    // Encerra um pedido vencido e devolve a garantia retida (qualquer um pode chamar)
+    #[endpoint(expireLoanApplication)]
+    fn expire_loan_application(&self, application_id: u64) {
+        require!(!self.loan_applications(application_id).is_empty(), "Pedido não existe");
+        let application = self.loan_applications(application_id).get();
+        require!(application.status == ApplicationStatus::Pending, "Pedido não está pendente");
+        require!(
+            self.current_timestamp() > application.expires_at,
+            "Pedido ainda não expirou"
+        );
+
+        self.close_loan_application(application_id, application, ApplicationStatus::Expired, 0);
+        self.loan_application_expired_event(application_id);
+    }
+
+    #[view(getLoanApplication)]
+    fn get_loan_application(&self, application_id: u64) -> OptionalValue<LoanApplication<Self::Api>> {
+        if self.loan_applications(application_id).is_empty() {
+            return OptionalValue::None;
+        }
+        OptionalValue::Some(self.loan_applications(application_id).get())
+    }
+
+    #[view(getPendingApplications)]
+    fn get_pending_applications(&self) -> MultiValueEncoded<u64> {
+        self.pending_applications().iter().collect()
+    }
+
+    #[view(getUnderwritingThreshold)]
+    fn get_underwriting_threshold(&self) -> BigUint {
+        self.underwriting_threshold().get()
+    }
+
+    #[view(getApplicationLifetime)]
+    fn get_application_lifetime(&self) -> u64 {
+        let lifetime = self.application_lifetime().get();
+        if lifetime == 0 {
+            DEFAULT_APPLICATION_LIFETIME
+        } else {
+            lifetime
+        }
+    }
+
+    fn requires_underwriting(&self, amount: &BigUint) -> bool {
+        let threshold = self.underwriting_threshold().get();
+        threshold > 0u64 && *amount >= threshold
+    }
+
+    // Cria o pedido com o parcelamento pedido e a garantia pendente do tomador
+    fn create_loan_application(
+        &self,
+        borrower: &ManagedAddress,
+        amount: &BigUint,
+        token_id: &EgldOrEsdtTokenIdentifier,
+        term: LoanTerm,
+        installment_period: Option<InstallmentPeriod>,
+        guarantee_request_id: u64,
+        interest_rate: u64,
+        user_score: u64,
+    ) -> u64 {
+        let now = self.current_timestamp();
+        let collateral_token = self.pending_collateral_token_or_egld(borrower);
+        self.pending_collateral_token(borrower).clear();
+        let collateral = self.pending_collateral(borrower.clone()).take();
+
+        let application_id = self.loan_application_counter().get() + 1;
+        self.loan_application_counter().set(application_id);
+        let application = LoanApplication {
+            borrower: borrower.clone(),
+            amount: amount.clone(),
+            token_id: token_id.clone(),
+            term,
+            interest_rate,
+            user_score,
+            installment_period,
+            guarantee_request_id,
+            collateral_token,
+            collateral,
+            created_at: now,
+            expires_at: now + self.get_application_lifetime(),
+            status: ApplicationStatus::Pending,
+            reason_code: 0,
+            loan_id: None,
+        };
+        self.loan_applications(application_id).set(&application);
+        self.pending_applications().insert(application_id);
+
+        // As garantias dos fiadores ficam travadas enquanto o pedido aguarda
+        if guarantee_request_id > 0 {
+            self.guarantee_request_opening(guarantee_request_id).set(true);
+        }
+
+        self.loan_application_created_event(application_id, borrower, application.expires_at, amount);
+        application_id
+    }
+
+    fn open_loan_application(&self, application_id: u64) -> LoanApplication<Self::Api> {
+        require!(!self.loan_applications(application_id).is_empty(), "Pedido não existe");
+        let application = self.loan_applications(application_id).get();
+        require!(application.status == ApplicationStatus::Pending, "Pedido não está pendente");
+        require!(
+            self.current_timestamp() <= application.expires_at,
+            "Pedido expirou"
+        );
+        application
+    }
+
+    // Rejeitado ou expirado: sai da fila e a garantia retida volta ao tomador
+    fn close_loan_application(
+        &self,
+        application_id: u64,
+        mut application: LoanApplication<Self::Api>,
+        status: ApplicationStatus,
+        reason_code: u32,
+    ) {
+        application.status = status;
+        application.reason_code = reason_code;
+        self.loan_applications(application_id).set(&application);
+        self.pending_applications().swap_remove(&application_id);
+        if application.guarantee_request_id > 0 {
+            self.guarantee_request_opening(application.guarantee_request_id).clear();
+        }
+
+        if application.collateral > 0u64 {
+            self.send().direct(
+                &application.borrower,
+                &application.collateral_token,
+                0,
+                &application.collateral,
+            );
+        }
+    }
+
+    // Registra o empréstimo de um pedido aprovado, vincula a garantia retida
+    // e envia os fundos ao tomador
+    fn disburse_loan_application(&self, application_id: u64) -> u64 {
+        let mut application = self.loan_applications(application_id).get();
+        let loan_id = self.register_loan(
+            &application.borrower,
+            &application.amount,
+            &application.token_id,
+            application.interest_rate,
+            application.term,
+            application.installment_period,
+            application.guarantee_request_id,
+        );
+
+        if application.collateral > 0u64 {
+            self.loan_collateral(loan_id).set(&application.collateral);
+            self.loan_collateral_token(loan_id).set(&application.collateral_token);
+            self.collateral_deposited_event(
+                loan_id,
+                &application.borrower,
+                &application.collateral_token,
+                &application.collateral,
+                &application.collateral,
+                self.current_timestamp(),
+            );
+        }
+
+        application.loan_id = Some(loan_id);
+        self.loan_applications(application_id).set(&application);
+        self.send().direct(&application.borrower, &application.token_id, 0, &application.amount);
+        loan_id
+    }
+
+
+    // Deposita EGLD como garantia para um empréstimo existente
+    #[payable("*")]
+    #[endpoint(provideCollateral)]
+    fn provide_collateral(&self, loan_id: u64) {
+        let caller = self.blockchain().get_caller();
+
+        // 1) Verifica existência e dono do empréstimo
+        require!(!self.loans(loan_id).is_empty(), "Empréstimo não existe");
+        let loan = self.loans(loan_id).get();
+        require!(loan.borrower == caller, "Only the borrower can provide collateral");
+        require!(loan.status == LoanStatus::Active, "Cannot provide collateral for non-active loan");
+
+        // 2) Extrai token e valor enviados na chamada
+        let (token, amount) = self.call_value().egld_or_single_fungible_esdt();
+        require!(amount > 0u64, "Collateral amount must be greater than zero");
+
+        // 3) Atualiza o armazenamento de garantia (um único token por empréstimo)
+        let mut current = self.loan_collateral(loan_id).get();
+        require!(
+            current == 0u64 || token == self.collateral_token(loan_id),
+            "Token de garantia diferente do já depositado"
+        );
+        current += &amount;
+        self.loan_collateral(loan_id).set(&current);
+        self.loan_collateral_token(loan_id).set(&token);
+        self.collateral_deposited_event(
+            loan_id,
+            &caller,
+            &token,
+            &amount,
+            &current,
+            self.current_timestamp(),
+        );
+
+        // (o saldo do contrato já é creditado automaticamente pelo VM)
+    }
+
+    // Após quitação, devolve a garantia ao tomador
+    #[endpoint(withdrawCollateral)]
+    fn withdraw_collateral(&self, loan_id: u64) {
+        let caller = self.blockchain().get_caller();
+
+        // 1) Verifica existência e status do empréstimo
+        require!(!self.loans(loan_id).is_empty(), "Empréstimo não existe");
+        let loan = self.loans(loan_id).get();
+        require!(loan.borrower == caller, "Only the borrower can withdraw collateral");
+        require!(loan.status == LoanStatus::Repaid, "Cannot withdraw collateral for non-repaid loan");
+
+        // 2) Lê a garantia acumulada
+        let collateral = self.loan_collateral(loan_id).get();
+        require!(collateral > 0u64, "No collateral to withdraw");
+
+        // 3) Zera o armazenamento
+        let token = self.collateral_token(loan_id);
+        self.loan_collateral(loan_id).set(BigUint::from(0u64));
+
+        // 4) Devolve a garantia ao tomador no token depositado
+        self.send().direct(&caller, &token, 0, &collateral);
+        self.collateral_withdrawn_event(
+            loan_id,
+            &caller,
+            &token,
+            &collateral,
+            self.current_timestamp(),
+        );
+    }
+
+    // Marca manualmente um empréstimo como inadimplente
+    #[endpoint(markLoanDefaulted)]
+    fn mark_loan_defaulted(&self, loan_id: u64) {
+        self.require_role(Role::Keeper);
+        // 1) Verifica que o empréstimo existe
+        require!(!self.loans(loan_id).is_empty(), "Empréstimo não existe");
+
+        // 2) Carrega o empréstimo e garante que ainda está ativo
+        let mut loan = self.loans(loan_id).get();
+        require!(loan.status == LoanStatus::Active, "Só é possível marcar empréstimos ativos");
+
+        // 3) Atualiza o status para Defaulted
+        loan.status = LoanStatus::Defaulted;
+        self.loans(loan_id).set(&loan);
+        self.on_loan_closed(loan_id, &LoanStatus::Active, &LoanStatus::Defaulted);
+        self.report_default(&loan);
+    }
+
+
+    // Apreende a garantia de um empréstimo inadimplente (owner ou Admin)
+    #[endpoint(forfeitCollateral)]
+    fn forfeit_collateral(&self, loan_id: u64) {
+        self.require_role(Role::Admin);
+        // 1) Verifica que o empréstimo existe
+        require!(!self.loans(loan_id).is_empty(), "Empréstimo não existe");
+
+        // 2) Carrega o empréstimo e exige que esteja Defaulted
+        let loan = self.loans(loan_id).get();
+        require!(loan.status == LoanStatus::Defaulted, "Somente inadimplentes");
+
+        // 3) Lê o valor da garantia
+        let collateral = self.loan_collateral(loan_id).get();
+        require!(collateral > BigUint::from(0u64), "Sem garantia para apreender");
+
+        // 4) Zera o storage de collateral e credita a garantia às taxas do
+        //    protocolo, no token da garantia, para ser sacada em withdrawFunds.
+        //    Os fundos já estão em posse do contrato (bloqueados em provideCollateral)
+        let token = self.collateral_token(loan_id);
+        self.loan_collateral(loan_id).set(BigUint::from(0u64));
+        self.protocol_fees(&token).update(|fees| *fees += &collateral);
+        self.collateral_forfeited_event(
+            loan_id,
+            &loan.borrower,
+            &token,
+            &collateral,
+            self.current_timestamp(),
+        );
+    }
+
+    #[payable("*")]
+    #[endpoint(provideCollateralForNewLoan)]
+    fn provide_collateral_for_new_loan(&self) {
+        let caller = self.blockchain().get_caller();
+        // qual token e quanto o usuário enviou?
+        let (token, deposit) = self.call_value().egld_or_single_fungible_esdt();
+        // não aceitar zero
+        require!(deposit > 0u64, "Collateral deve ser maior que zero");
+        // não misturar tokens na mesma garantia pendente
+        require!(
+            self.pending_collateral(caller.clone()).get() == 0u64
+                || token == self.pending_collateral_token_or_egld(&caller),
+            "Token de garantia diferente do já depositado"
+        );
+        self.pending_collateral_token(&caller).set(&token);
+        // acumula em pending_collateral(caller)
         self.pending_collateral(caller)
             .update(|current| *current += deposit);
     }
@@ -342,13 +2114,17 @@ pub trait LoanController {
         require!(pending >= min_col, "Insufficient collateral provided");
 
         // Calcula valor máximo de empréstimo: collateral_ratio em basis points
+        // sobre o valor da garantia em EGLD
+        let collateral_token = self.pending_collateral_token_or_egld(&caller);
+        let collateral_value =
+            self.collateral_value_in(&collateral_token, &pending, &EgldOrEsdtTokenIdentifier::egld());
         let ratio_bp = self.collateral_ratio().get();
-        let amount = &pending * &BigUint::from(ratio_bp) / &BigUint::from(10000u64);
-        require!(amount > BigUint::from(0u64), "Insufficient collateral provided");
+        let amount = &collateral_value * &BigUint::from(ratio_bp) / &BigUint::from(10000u64);
+        require!(amount > 0u64, "Insufficient collateral provided");
+        self.require_within_exposure_cached(&caller, &amount);
 
         // Cria ID e incrementa contador
-        let loan_id = self.loan_counter().get() + 1;
-        self.loan_counter().set(loan_id);
+        let loan_id = self.next_loan_id();
 
         // Calcula juros e valor de reembolso
         let interest_rate = self.interest_rate_base().get();
@@ -356,7 +2132,7 @@ pub trait LoanController {
         let repayment_amount = &amount + &interest_amount;
 
         // Timestamps
-        let now = self.blockchain().get_block_timestamp();
+        let now = self.current_timestamp();
         let due = now + self.standard_term_days().get() * 24 * 60 * 60;
 
         // Armazena o empréstimo
@@ -373,168 +2149,837 @@ pub trait LoanController {
 
         // Move a garantia: pending → loan_collateral
         self.loan_collateral(loan_id).set(pending.clone());
+        self.loan_collateral_token(loan_id).set(&collateral_token);
         // Zera o pending_collateral do usuário
-        self.pending_collateral(caller).set(BigUint::from(0u64));
+        self.pending_collateral_token(&caller).clear();
+        self.pending_collateral(caller.clone()).set(BigUint::from(0u64));
+
+        self.on_loan_created(loan_id);
+        self.collateral_deposited_event(loan_id, &caller, &collateral_token, &pending, &pending, now);
+
+        loan_id
+    }
+
+
+    #[endpoint(cancelLoanRequest)]
+    fn cancel_loan_request(&self) {
+        let caller = self.blockchain().get_caller();
+
+        // Lê o valor de garantia pendente
+        let pending = self.pending_collateral(caller.clone()).get();
+        require!(pending > BigUint::from(0u64), "No collateral to return");
+
+        // Zera o pending_collateral
+        let token = self.pending_collateral_token_or_egld(&caller);
+        self.pending_collateral_token(&caller).clear();
+        self.pending_collateral(caller.clone()).set(BigUint::from(0u64));
+
+        // Devolve a garantia ao tomador no token depositado
+        self.send().direct(&caller, &token, 0, &pending);
+    }
+
+
+    /// Abre o leilão da garantia de um empréstimo inadimplente (qualquer um pode chamar)
+    /// O preço inicial é a dívida mais a penalidade de liquidação; o piso aplica o
+    /// desconto de liquidação sobre ele.
+    #[endpoint(startCollateralAuction)]
+    fn start_collateral_auction(&self, loan_id: u64, auction_type: AuctionType) {
+        require!(!self.loans(loan_id).is_empty(), "Empréstimo não existe");
+        let loan = self.loans(loan_id).get();
+        require!(loan.status == LoanStatus::Defaulted, "Empréstimo não está inadimplente");
+        require!(self.loan_collateral(loan_id).get() > 0u64, "Sem garantia para liquidar");
+        require!(self.collateral_auction(loan_id).is_empty(), "Leilão já iniciado");
+
+        self.open_collateral_auction(loan_id, auction_type);
+    }
+
+    /// Liquida um empréstimo ativo cujo fator de saúde caiu abaixo do
+    /// liquidation_threshold (qualquer um pode chamar). O empréstimo é marcado
+    /// como inadimplente e a garantia vai a leilão holandês.
+    #[endpoint(liquidate)]
+    fn liquidate(&self, loan_id: u64) {
+        require!(!self.loans(loan_id).is_empty(), "Empréstimo não existe");
+        let mut loan = self.loans(loan_id).get();
+        require!(loan.status == LoanStatus::Active, "Empréstimo não está ativo");
+        require!(self.loan_collateral(loan_id).get() > 0u64, "Sem garantia para liquidar");
+        let threshold = self.liquidation_threshold().get();
+        require!(threshold > 0, "Limite de liquidação não configurado");
+
+        let health_factor = self.get_health_factor(loan_id);
+        require!(
+            health_factor < threshold,
+            "Fator de saúde acima do limite de liquidação"
+        );
+
+        // 1) Consolida as taxas de atraso para que entrem na dívida leiloada
+        self.accrue_late_fees(loan_id);
+
+        // 2) Marca como inadimplente
+        loan.status = LoanStatus::Defaulted;
+        self.loans(loan_id).set(&loan);
+        self.on_loan_closed(loan_id, &LoanStatus::Active, &LoanStatus::Defaulted);
+        self.report_default(&loan);
+        self.loan_liquidated_event(loan_id, &self.blockchain().get_caller(), &health_factor);
+
+        // 3) Abre o leilão da garantia
+        self.open_collateral_auction(loan_id, AuctionType::Dutch);
+    }
+
+    /// Fator de saúde em basis points: valor da garantia (no token do empréstimo)
+    /// sobre a dívida em aberto. 10000 = garantia igual à dívida.
+    #[view(getHealthFactor)]
+    fn get_health_factor(&self, loan_id: u64) -> BigUint {
+        let debt = self.get_amount_due(loan_id);
+        if debt == 0u64 {
+            return BigUint::from(u64::MAX);
+        }
+        self.get_collateral_value(loan_id) * 10000u64 / debt
+    }
+
+    /// Valor da garantia de um empréstimo no token em que ele é pago
+    #[view(getCollateralValue)]
+    fn get_collateral_value(&self, loan_id: u64) -> BigUint {
+        let amount = self.loan_collateral(loan_id).get();
+        if amount == 0u64 {
+            return BigUint::zero();
+        }
+        self.collateral_value_in(&self.collateral_token(loan_id), &amount, &self.loan_payment_token(loan_id))
+    }
+
+    #[view(getCollateralToken)]
+    fn get_collateral_token(&self, loan_id: u64) -> EgldOrEsdtTokenIdentifier {
+        self.collateral_token(loan_id)
+    }
+
+    #[endpoint(setLiquidationThreshold)]
+    fn set_liquidation_threshold(&self, threshold: u64) {
+        self.set_parameter_directly(ParamType::LiquidationThreshold, threshold);
+    }
+
+    #[view(getLiquidationThreshold)]
+    fn get_liquidation_threshold(&self) -> u64 {
+        self.liquidation_threshold().get()
+    }
+
+    /// Fonte de preços externa consultada via getPrice(base, quote)
+    #[endpoint(setPriceSource)]
+    fn set_price_source(&self, address: ManagedAddress) {
+        self.require_role(Role::Admin);
+        self.require_multisig_not_configured();
+        self.apply_price_source(&address);
+    }
+
+    // A garantia é avaliada com chamadas síncronas (inclusive nos leilões de
+    // liquidação), que só alcançam contratos do mesmo shard
+    fn apply_price_source(&self, address: &ManagedAddress) {
+        require!(
+            self.blockchain().is_smart_contract(address),
+            "Fonte de preços deve ser um contrato"
+        );
+        require!(
+            self.is_same_shard(address),
+            "Fonte de preços deve estar no mesmo shard do controlador"
+        );
+        self.price_source_address().set(address);
+    }
+
+    /// Preço fixo usado quando não há fonte de preços configurada
+    /// (em unidades de quote_token por unidade de base_token, escala 10^18)
+    #[endpoint(setCollateralPrice)]
+    fn set_collateral_price(
+        &self,
+        base_token: EgldOrEsdtTokenIdentifier,
+        quote_token: EgldOrEsdtTokenIdentifier,
+        price: BigUint,
+    ) {
+        self.require_role(Role::Oracle);
+        require!(price > 0u64, "Preço deve ser maior que zero");
+        self.collateral_price(&base_token, &quote_token).set(&price);
+    }
+
+    // Token da garantia de um empréstimo (empréstimos antigos: EGLD)
+    fn collateral_token(&self, loan_id: u64) -> EgldOrEsdtTokenIdentifier {
+        if self.loan_collateral_token(loan_id).is_empty() {
+            EgldOrEsdtTokenIdentifier::egld()
+        } else {
+            self.loan_collateral_token(loan_id).get()
+        }
+    }
+
+    fn pending_collateral_token_or_egld(&self, user: &ManagedAddress) -> EgldOrEsdtTokenIdentifier {
+        if self.pending_collateral_token(user).is_empty() {
+            EgldOrEsdtTokenIdentifier::egld()
+        } else {
+            self.pending_collateral_token(user).get()
+        }
+    }
+
+    // Converte uma quantidade de garantia para o token de cotação
+    fn collateral_value_in(
+        &self,
+        token: &EgldOrEsdtTokenIdentifier,
+        amount: &BigUint,
+        quote_token: &EgldOrEsdtTokenIdentifier,
+    ) -> BigUint {
+        if token == quote_token {
+            return amount.clone();
+        }
+        let price: BigUint = if !self.price_source_address().is_empty() {
+            self.price_source_proxy(self.price_source_address().get())
+                .get_price(token.clone(), quote_token.clone())
+                .execute_on_dest_context()
+        } else {
+            require!(
+                !self.collateral_price(token, quote_token).is_empty(),
+                "Preço da garantia não disponível"
+            );
+            self.collateral_price(token, quote_token).get()
+        };
+        amount * &price / PRICE_PRECISION
+    }
+
+    // Cria o leilão da garantia a partir da dívida e da penalidade de liquidação
+    fn open_collateral_auction(&self, loan_id: u64, auction_type: AuctionType) {
+        let (debt, penalty) = self.liquidation_claim(loan_id);
+        let start_price = debt + penalty;
+        let discount = self.liquidation_discount().get();
+        let floor_price = &start_price * (10000u64 - discount) / 10000u64;
+
+        let now = self.current_timestamp();
+        let duration = if self.auction_duration().is_empty() {
+            DEFAULT_AUCTION_DURATION
+        } else {
+            self.auction_duration().get()
+        };
+        let auction = CollateralAuction {
+            auction_type,
+            start_timestamp: now,
+            end_timestamp: now + duration,
+            start_price,
+            floor_price,
+            highest_bidder: ManagedAddress::zero(),
+            highest_bid: BigUint::zero(),
+        };
+        self.collateral_auction(loan_id).set(&auction);
+        self.auction_started_event(loan_id, auction_type, &auction.start_price, &auction.floor_price, auction.end_timestamp);
+    }
+
+    /// Lance no leilão da garantia, pago no token do empréstimo.
+    /// Holandês: compra pelo preço atual (o excedente é devolvido) e liquida na hora.
+    /// Inglês: lance acima do piso e do maior lance; o lance superado fica
+    /// disponível em claimAuctionRefund.
+    #[payable("*")]
+    #[endpoint(liquidateCollateralViaAuction)]
+    fn liquidate_collateral_via_auction(&self, loan_id: u64) {
+        let caller = self.blockchain().get_caller();
+        require!(!self.collateral_auction(loan_id).is_empty(), "Leilão não iniciado");
+        let mut auction = self.collateral_auction(loan_id).get();
+
+        let (bid_token, bid) = self.call_value().egld_or_single_fungible_esdt();
+        require!(bid_token == self.loan_payment_token(loan_id), "Token de pagamento inválido");
+
+        match auction.auction_type {
+            AuctionType::Dutch => {
+                let price = self.get_auction_price(loan_id);
+                require!(bid >= price, "Lance abaixo do preço atual");
+                if bid > price {
+                    self.send().direct(&caller, &bid_token, 0, &(&bid - &price));
+                }
+                self.auction_bid_event(loan_id, &caller, &price);
+                self.settle_liquidation(loan_id, &caller, &price);
+            },
+            AuctionType::English => {
+                let now = self.current_timestamp();
+                require!(now < auction.end_timestamp, "Leilão encerrado");
+                require!(bid >= auction.floor_price, "Lance abaixo do preço mínimo");
+                require!(bid > auction.highest_bid, "Lance deve superar o maior lance");
+
+                // O lance superado é creditado ao licitante, que o saca depois;
+                // um envio direto falhando (ex.: contrato sem payable) travaria o leilão
+                if !auction.highest_bidder.is_zero() {
+                    self.auction_refunds(&auction.highest_bidder, &bid_token)
+                        .update(|refund| *refund += &auction.highest_bid);
+                }
+                auction.highest_bidder = caller.clone();
+                auction.highest_bid = bid.clone();
+                self.collateral_auction(loan_id).set(&auction);
+                self.auction_bid_event(loan_id, &caller, &bid);
+            },
+        }
+    }
+
+    /// Encerra um leilão após o prazo. Sem lances (o holandês nunca tem lance
+    /// pendente), o leilão é descartado e pode ser reaberto com preços atualizados.
+    #[endpoint(settleCollateralAuction)]
+    fn settle_collateral_auction(&self, loan_id: u64) {
+        require!(!self.collateral_auction(loan_id).is_empty(), "Leilão não iniciado");
+        let auction = self.collateral_auction(loan_id).get();
+        require!(
+            self.current_timestamp() >= auction.end_timestamp,
+            "Leilão ainda em andamento"
+        );
+
+        if auction.highest_bidder.is_zero() {
+            self.collateral_auction(loan_id).clear();
+            self.auction_cancelled_event(loan_id);
+            return;
+        }
+        self.settle_liquidation(loan_id, &auction.highest_bidder, &auction.highest_bid);
+    }
+
+    /// Preço atual do leilão: no holandês cai linearmente até o piso; no inglês
+    /// é o lance mínimo aceito (piso ou maior lance).
+    #[view(getAuctionPrice)]
+    fn get_auction_price(&self, loan_id: u64) -> BigUint {
+        require!(!self.collateral_auction(loan_id).is_empty(), "Leilão não iniciado");
+        let auction = self.collateral_auction(loan_id).get();
+        if auction.auction_type == AuctionType::English {
+            return core::cmp::max(auction.floor_price, auction.highest_bid);
+        }
+
+        let now = self.current_timestamp();
+        if now >= auction.end_timestamp {
+            return auction.floor_price;
+        }
+        let elapsed = now - auction.start_timestamp;
+        let duration = auction.end_timestamp - auction.start_timestamp;
+        let drop = (&auction.start_price - &auction.floor_price) * elapsed / duration;
+        auction.start_price - drop
+    }
+
+    /// Saca os lances superados do chamador no token informado
+    #[endpoint(claimAuctionRefund)]
+    fn claim_auction_refund(&self, token_id: EgldOrEsdtTokenIdentifier) -> BigUint {
+        let caller = self.blockchain().get_caller();
+        let refund = self.auction_refunds(&caller, &token_id).take();
+        require!(refund > 0u64, "Nenhum reembolso disponível");
+
+        self.send().direct(&caller, &token_id, 0, &refund);
+        refund
+    }
+
+    #[view(getAuctionRefund)]
+    fn get_auction_refund(&self, bidder: ManagedAddress, token_id: EgldOrEsdtTokenIdentifier) -> BigUint {
+        self.auction_refunds(&bidder, &token_id).get()
+    }
+
+    #[view(getCollateralAuction)]
+    fn get_collateral_auction(&self, loan_id: u64) -> OptionalValue<CollateralAuction<Self::Api>> {
+        if self.collateral_auction(loan_id).is_empty() {
+            return OptionalValue::None;
+        }
+        OptionalValue::Some(self.collateral_auction(loan_id).get())
+    }
+
+    #[endpoint(setAuctionDuration)]
+    fn set_auction_duration(&self, duration_seconds: u64) {
+        self.set_parameter_directly(ParamType::AuctionDuration, duration_seconds);
+    }
+
+    #[endpoint(setLiquidationPenalty)]
+    fn set_liquidation_penalty(&self, penalty: u64) {
+        self.set_parameter_directly(ParamType::LiquidationPenalty, penalty);
+    }
+
+    #[view(getTotalLiquidationPenalties)]
+    fn get_total_liquidation_penalties(&self) -> BigUint {
+        self.total_liquidation_penalties().get()
+    }
+
+    // Dívida em aberto (restante + taxas de atraso) e penalidade de liquidação sobre ela
+    fn liquidation_claim(&self, loan_id: u64) -> (BigUint, BigUint) {
+        let loan = self.loans(loan_id).get();
+        let debt = loan.repayment_amount + self.loan_late_fee_balance(loan_id).get();
+        let penalty = &debt * self.liquidation_penalty().get() / 10000u64;
+        (debt, penalty)
+    }
+
+    // Token em que o empréstimo é pago (e os lances do leilão são feitos).
+    // Empréstimos anteriores aos tokens aceitos: token do pool ou EGLD
+    fn loan_payment_token(&self, loan_id: u64) -> EgldOrEsdtTokenIdentifier {
+        if !self.loan_token(loan_id).is_empty() {
+            self.loan_token(loan_id).get()
+        } else if !self.loan_pool_token(loan_id).is_empty() {
+            EgldOrEsdtTokenIdentifier::esdt(self.loan_pool_token(loan_id).get())
+        } else {
+            EgldOrEsdtTokenIdentifier::egld()
+        }
+    }
+
+    // Liquida o empréstimo com o valor arrecadado: quita a dívida, cobra a
+    // penalidade, devolve o excedente ao tomador e entrega a garantia ao vencedor
+    fn settle_liquidation(&self, loan_id: u64, winner: &ManagedAddress, proceeds: &BigUint) {
+        let mut loan = self.loans(loan_id).get();
+        let (debt, penalty) = self.liquidation_claim(loan_id);
+        let token = self.loan_payment_token(loan_id);
+
+        let to_debt = core::cmp::min(proceeds.clone(), debt.clone());
+        let after_debt = proceeds - &to_debt;
+        let to_penalty = core::cmp::min(after_debt.clone(), penalty);
+        let surplus = &after_debt - &to_penalty;
+
+        if to_debt > 0u64 {
+            self.book_loan_payment(loan_id, &loan, &to_debt, &BigUint::zero());
+        }
+        self.total_liquidation_penalties().update(|total| *total += &to_penalty);
+        if to_penalty > 0u64 {
+            self.protocol_fees(&token).update(|fees| *fees += &to_penalty);
+        }
+        if surplus > 0u64 {
+            self.send().direct(&loan.borrower, &token, 0, &surplus);
+        }
+
+        let collateral = self.loan_collateral(loan_id).get();
+        self.loan_collateral(loan_id).set(BigUint::zero());
+        self.send().direct(winner, &self.collateral_token(loan_id), 0, &collateral);
+
+        let shortfall = &debt - &to_debt;
+        loan.status = LoanStatus::Liquidated;
+        loan.repayment_amount = shortfall.clone();
+        self.loans(loan_id).set(&loan);
+        self.loan_late_fee_balance(loan_id).clear();
+        self.collateral_auction(loan_id).clear();
+        self.on_loan_closed(loan_id, &LoanStatus::Defaulted, &LoanStatus::Liquidated);
+
+        self.auction_settled_event(loan_id, winner, proceeds, &surplus, &shortfall);
+    }
+    
+
+    // Permite habilitar pagamentos parciais
+    #[storage_mapper("allow_partial_repayments")]
+    fn allow_partial_repayments(&self) -> SingleValueMapper<bool>;
+
+    // Pagamentos parciais de um empréstimo
+    #[payable("*")]
+    #[endpoint(partialRepayLoan)]
+    fn partial_repay_loan(&self, loan_id: u64) {
+        let caller = self.blockchain().get_caller();
+
+        // 1) Verifica se pagamentos parciais estão habilitados
+        //    (empréstimos parcelados sempre aceitam pagamento de parcelas)
+        require!(
+            self.allow_partial_repayments().get() || !self.loan_installments(loan_id).is_empty(),
+            "Pagamentos parciais não estão permitidos"
+        );
+
+        // 2) Verifica existência e pertence ao tomador
+        require!(!self.loans(loan_id).is_empty(), "Empréstimo não existe");
+        let mut loan = self.loans(loan_id).get();
+        require!(loan.borrower == caller, "Apenas o tomador pode pagar o empréstimo");
+        require!(loan.status == LoanStatus::Active, "Empréstimo não está ativo");
+
+        // 3) Montante enviado como pagamento
+        let paid_token = self.call_value().egld_or_single_esdt().token_identifier.clone();
+        let mut paid = self.call_value().egld_or_single_esdt().amount.clone();
+        self.require_loan_payment_token(loan_id, &paid_token);
+        require!(paid > BigUint::from(0u64), "Pagamentos devem ser maiores que zero");
+
+        //    O pagamento quita primeiro as taxas de atraso acumuladas
+        self.accrue_late_fees(loan_id);
+        let late_fee_balance = self.loan_late_fee_balance(loan_id).get();
+
+        //    Se cobre a quitação (inclusive a última parcela), o empréstimo é
+        //    liquidado como em repayLoan: desconta os juros ainda não decorridos
+        //    e devolve o excedente
+        let rebate = self.early_repayment_rebate(loan_id, &loan, self.current_timestamp());
+        let payoff = &loan.repayment_amount - &rebate + &late_fee_balance;
+        let rebate = if paid >= payoff {
+            let change = &paid - &payoff;
+            if change > 0u64 {
+                self.send().direct(&caller, &paid_token, 0, &change);
+            }
+            paid = payoff;
+            rebate
+        } else {
+            BigUint::zero()
+        };
+
+        let late_fee = core::cmp::min(paid.clone(), late_fee_balance);
+        let installment_paid = &paid - &late_fee;
+        require!(
+            installment_paid <= loan.repayment_amount,
+            "Valor de pagamento excede o montante devido"
+        );
+        self.collect_late_fee(loan_id, &late_fee);
+
+        // 4) Debita do repayment_amount e grava o empréstimo
+        loan.repayment_amount = &loan.repayment_amount - &installment_paid - &rebate;
+        self.loans(loan_id).set(loan.clone());
+
+        // 5) Acumula em loan_payments (somente principal + juros)
+        self.loan_payments(loan_id).update(|current| *current += installment_paid);
+        self.loan_partially_repaid_event(
+            loan_id,
+            &caller,
+            &paid,
+            &loan.repayment_amount,
+            self.current_timestamp(),
+        );
+
+        // Cada parcela devolve o principal à origem dos fundos
+        self.book_loan_payment(loan_id, &loan, &paid, &late_fee);
+
+        // 6) Se zerou (e não restam taxas), marca como Repaid e incrementa contador
+        if loan.repayment_amount == 0u64 && self.loan_late_fee_balance(loan_id).get() == 0u64 {
+            // Marca como pago
+            let mut paid_loan = loan;
+            paid_loan.status = LoanStatus::Repaid;
+            self.loans(loan_id).set(&paid_loan);
+            self.repaid_loans_count().update(|cnt| *cnt += 1u64);
+            self.on_loan_closed(loan_id, &LoanStatus::Active, &LoanStatus::Repaid);
+            self.report_repayment(&paid_loan, self.current_timestamp());
+        } else if !self.loan_installments(loan_id).is_empty() {
+            // A parcela paga deixa de ser a que a varredura de vencidos observa
+            self.reindex_active_loan(loan_id);
+        }
+    }
+
+    /// Valor total devido agora: principal + juros decorridos + taxas de atraso
+    #[view(getAmountDue)]
+    fn get_amount_due(&self, loan_id: u64) -> BigUint {
+        self.quote_repayment(loan_id, self.current_timestamp())
+    }
+
+    /// Valor exato para quitar o empréstimo em `at_timestamp`: saldo devedor
+    /// menos os juros ainda não decorridos, mais as taxas de atraso até lá
+    #[view(quoteRepayment)]
+    fn quote_repayment(&self, loan_id: u64, at_timestamp: u64) -> BigUint {
+        require!(!self.loans(loan_id).is_empty(), "Empréstimo não existe");
+        let loan = self.loans(loan_id).get();
+        if loan.status != LoanStatus::Active {
+            return BigUint::zero();
+        }
+        let late_fee = self.loan_late_fee_balance(loan_id).get() + self.late_fee_since_checkpoint(loan_id, at_timestamp);
+        &loan.repayment_amount - &self.early_repayment_rebate(loan_id, &loan, at_timestamp) + late_fee
+    }
+
+    /// Taxa anual (pontos base) com que os juros do empréstimo correm por segundo
+    #[view(getLoanApr)]
+    fn get_loan_apr(&self, loan_id: u64) -> u64 {
+        self.loan_apr(loan_id).get()
+    }
+
+    /// Juros decorridos desde a concessão, limitados aos juros contratados
+    #[view(getAccruedInterest)]
+    fn get_accrued_interest(&self, loan_id: u64) -> BigUint {
+        require!(!self.loans(loan_id).is_empty(), "Empréstimo não existe");
+        let loan = self.loans(loan_id).get();
+        self.accrued_interest_at(loan_id, &loan, self.current_timestamp())
+    }
+
+    #[endpoint(setMinInterestFloor)]
+    fn set_min_interest_floor(&self, floor: u64) {
+        self.set_parameter_directly(ParamType::MinInterestFloor, floor);
+    }
+
+    #[view(getMinInterestFloor)]
+    fn get_min_interest_floor(&self) -> u64 {
+        self.min_interest_floor().get()
+    }
+
+    // A taxa do prazo vira taxa anual: juros contratados distribuídos pela
+    // duração original do empréstimo (extensões não alteram a taxa)
+    fn register_loan_apr(&self, loan_id: u64, loan: &Loan<Self::Api>) {
+        let duration = loan.due_timestamp.saturating_sub(loan.creation_timestamp);
+        if duration == 0 {
+            return;
+        }
+        self.loan_apr(loan_id).set(loan.interest_rate * SECONDS_PER_YEAR / duration);
+    }
+
+    fn contracted_interest(&self, loan: &Loan<Self::Api>) -> BigUint {
+        &loan.amount * loan.interest_rate / 10000u64
+    }
+
+    // Juros por segundo à taxa anual; empréstimos sem taxa anual registrada
+    // (anteriores à apuração por tempo) mantêm os juros fixos
+    fn accrued_interest_at(&self, loan_id: u64, loan: &Loan<Self::Api>, at_timestamp: u64) -> BigUint {
+        let contracted = self.contracted_interest(loan);
+        if self.loan_apr(loan_id).is_empty() {
+            return contracted;
+        }
+        let elapsed = at_timestamp.saturating_sub(loan.creation_timestamp);
+        let accrued = &loan.amount * self.loan_apr(loan_id).get() * elapsed / (10000u64 * SECONDS_PER_YEAR);
+        core::cmp::min(accrued, contracted)
+    }
+
+    // Desconto da quitação antecipada: juros contratados ainda não decorridos,
+    // respeitando o piso de juros mínimos (pontos base dos juros contratados)
+    fn early_repayment_rebate(&self, loan_id: u64, loan: &Loan<Self::Api>, at_timestamp: u64) -> BigUint {
+        let contracted = self.contracted_interest(loan);
+        let floor = &contracted * self.min_interest_floor().get() / 10000u64;
+        let charged = core::cmp::max(self.accrued_interest_at(loan_id, loan, at_timestamp), floor);
+        if charged >= contracted {
+            return BigUint::zero();
+        }
+        core::cmp::min(contracted - charged, loan.repayment_amount.clone())
+    }
+
+    /// Taxas de atraso devidas agora (ainda não pagas)
+    #[view(getLateFeeDue)]
+    fn get_late_fee_due(&self, loan_id: u64) -> BigUint {
+        let now = self.current_timestamp();
+        self.loan_late_fee_balance(loan_id).get() + self.late_fee_since_checkpoint(loan_id, now)
+    }
+
+    /// Total de taxas de atraso já pagas em um empréstimo
+    #[view(getLateFeesPaid)]
+    fn get_late_fees_paid(&self, loan_id: u64) -> BigUint {
+        self.loan_late_fees_paid(loan_id).get()
+    }
 
-        loan_id
+    /// Total de taxas de atraso recebidas pelo contrato
+    #[view(getTotalLateFeesEarned)]
+    fn get_total_late_fees_earned(&self) -> BigUint {
+        self.total_late_fees_earned().get()
     }
 
+    // Configura a taxa diária progressiva cobrada após `threshold_days` de atraso
+    #[endpoint(setProgressiveLateFee)]
+    fn set_progressive_late_fee(&self, threshold_days: u64, daily_rate: u64) {
+        self.set_parameter_directly(ParamType::ProgressiveLateFeeDailyRate, daily_rate);
+        self.set_parameter_directly(ParamType::ProgressiveLateFeeThresholdDays, threshold_days);
+    }
 
-    #[endpoint(cancelLoanRequest)]
-    fn cancel_loan_request(&self) {
-        let caller = self.blockchain().get_caller();
+    // Incorpora ao saldo de taxas o que acumulou desde o último pagamento
+    fn accrue_late_fees(&self, loan_id: u64) {
+        let now = self.current_timestamp();
+        let fee = self.late_fee_since_checkpoint(loan_id, now);
+        if fee > 0u64 {
+            self.loan_late_fee_balance(loan_id).update(|balance| *balance += fee);
+        }
+        self.late_fee_checkpoint(loan_id).set(now);
+    }
 
-        // Lê o valor de garantia pendente
-        let pending = self.pending_collateral(caller.clone()).get();
-        require!(pending > BigUint::from(0u64), "No collateral to return");
+    // Baixa do saldo as taxas pagas e contabiliza-as separadamente dos juros
+    fn collect_late_fee(&self, loan_id: u64, fee: &BigUint) {
+        if fee == &0u64 {
+            return;
+        }
+        self.loan_late_fee_balance(loan_id).update(|balance| *balance -= fee);
+        self.loan_late_fees_paid(loan_id).update(|paid| *paid += fee);
+        self.total_late_fees_earned().update(|total| *total += fee);
+    }
 
-        // Zera o pending_collateral
-        self.pending_collateral(caller.clone()).set(BigUint::from(0u64));
+    // Taxas de atraso acumuladas entre o último pagamento e `at_timestamp`.
+    // Cada parcela vencida paga, sobre o valor que ainda resta dela, a taxa
+    // diária por dia inteiro de atraso.
+    fn late_fee_since_checkpoint(&self, loan_id: u64, at_timestamp: u64) -> BigUint {
+        let checkpoint = self.late_fee_checkpoint(loan_id).get();
+        let mut fee = BigUint::zero();
+        for installment in self.get_remaining_schedule(loan_id).iter() {
+            if installment.due_timestamp >= at_timestamp {
+                break;
+            }
+            let days_late = (at_timestamp - installment.due_timestamp) / 86400;
+            let days_charged = if checkpoint > installment.due_timestamp {
+                (checkpoint - installment.due_timestamp) / 86400
+            } else {
+                0
+            };
+            if days_late <= days_charged {
+                continue;
+            }
+            let rate = self.cumulative_late_fee_rate(days_late) - self.cumulative_late_fee_rate(days_charged);
+            let outstanding = &installment.principal + &installment.interest;
+            fee += outstanding * rate / 10000u64;
+        }
+        fee
+    }
 
-        // Devolve o EGLD ao tomador
-        self.send().direct_egld(&caller, &pending);
+    // Soma das taxas diárias (em pontos base) para `days` dias de atraso:
+    // taxa base até o limite progressivo e a taxa progressiva depois dele
+    fn cumulative_late_fee_rate(&self, days: u64) -> u64 {
+        let base_rate = self.late_fee_daily_rate().get();
+        let threshold = self.progressive_late_fee_threshold_days().get();
+        let progressive_rate = self.progressive_late_fee_daily_rate().get();
+        if threshold == 0 || progressive_rate == 0 || days <= threshold {
+            return base_rate * days;
+        }
+        base_rate * threshold + progressive_rate * (days - threshold)
     }
 
+    /// Próxima parcela em aberto, já descontado o que foi pago dela
+    #[view(getNextInstallment)]
+    fn get_next_installment(&self, loan_id: u64) -> OptionalValue<Installment<Self::Api>> {
+        let remaining = self.get_remaining_schedule(loan_id);
+        if remaining.is_empty() {
+            return OptionalValue::None;
+        }
+        let next = remaining.get(0).clone();
+        OptionalValue::Some(next)
+    }
 
-    /// Leiloar garantia de um empréstimo inadimplente
-    #[payable("*")]
-    #[endpoint(liquidateCollateralViaAuction)]
-    fn liquidate_collateral_via_auction(&self, loan_id: u64) {
-        let caller = self.blockchain().get_caller();
+    /// Valor das parcelas vencidas e ainda não pagas
+    #[view(getOverdueAmount)]
+    fn get_overdue_amount(&self, loan_id: u64) -> BigUint {
+        let now = self.current_timestamp();
+        let mut overdue = BigUint::zero();
+        for installment in self.get_remaining_schedule(loan_id).iter() {
+            if installment.due_timestamp > now {
+                break;
+            }
+            overdue += &installment.principal + &installment.interest;
+        }
+        overdue
+    }
 
-        // 1) Verifica existência do empréstimo
+    /// Parcelas restantes do empréstimo. Os pagamentos quitam as parcelas
+    /// em ordem, juros antes do principal.
+    #[view(getRemainingSchedule)]
+    fn get_remaining_schedule(&self, loan_id: u64) -> ManagedVec<Installment<Self::Api>> {
         require!(!self.loans(loan_id).is_empty(), "Empréstimo não existe");
+        let loan = self.loans(loan_id).get();
+        let mut remaining = ManagedVec::new();
+        if loan.status != LoanStatus::Active {
+            return remaining;
+        }
 
-        // 2) Busca o empréstimo e exige que esteja em Defaulted
-        let mut loan = self.loans(loan_id).get();
-        require!(loan.status == LoanStatus::Defaulted, "Empréstimo não está inadimplente");
-
-        // 3) Lê o valor da garantia
-        let collateral_amount = self.loan_collateral(loan_id).get();
-        require!(collateral_amount > BigUint::from(0u64), "Sem garantia para liquidar");
+        let mut paid = self.loan_payments(loan_id).get();
+        for mut installment in self.loan_schedule(loan_id).into_iter() {
+            let interest_paid = core::cmp::min(paid.clone(), installment.interest.clone());
+            installment.interest -= &interest_paid;
+            paid -= &interest_paid;
 
-        // 4) Atualiza o status do empréstimo e zera a garantia armazenada
-        loan.status = LoanStatus::Liquidated;
-        self.loans(loan_id).set(loan);
-        self.loan_collateral(loan_id).set(BigUint::from(0u64));
+            let principal_paid = core::cmp::min(paid.clone(), installment.principal.clone());
+            installment.principal -= &principal_paid;
+            paid -= &principal_paid;
 
-        // 5) Transfere a garantia para o licitante vencedor
-        //    (o bid já foi enviado ao contrato via `payable("*")`)
-        let token_id = self.call_value().egld_or_single_esdt().token_identifier;
-        self.send().direct(&caller, &token_id, 0, &collateral_amount);
+            if installment.principal > 0u64 || installment.interest > 0u64 {
+                remaining.push(installment);
+            }
+        }
+        remaining
     }
-    
-
-    // Permite habilitar pagamentos parciais
-    #[storage_mapper("allow_partial_repayments")]
-    fn allow_partial_repayments(&self) -> SingleValueMapper<bool>;
-
-    // Pagamentos parciais de um empréstimo
-    #[payable("*")]
-    #[endpoint(partialRepayLoan)]
-    fn partial_repay_loan(&self, loan_id: u64) {
-        let caller = self.blockchain().get_caller();
-
-        // 1) Verifica se pagamentos parciais estão habilitados
-        require!(
-            self.allow_partial_repayments().get(),
-            "Pagamentos parciais não estão permitidos"
-        );
 
-        // 2) Verifica existência e pertence ao tomador
-        require!(!self.loans(loan_id).is_empty(), "Empréstimo não existe");
-        let mut loan = self.loans(loan_id).get();
-        require!(loan.borrower == caller, "Apenas o tomador pode pagar o empréstimo");
-        require!(loan.status == LoanStatus::Active, "Empréstimo não está ativo");
+    // Cronograma completo do empréstimo. Empréstimos sem parcelamento têm uma
+    // única parcela (balão) no vencimento.
+    fn loan_schedule(&self, loan_id: u64) -> ManagedVec<Installment<Self::Api>> {
+        let mut schedule = ManagedVec::new();
+        if !self.loan_installments(loan_id).is_empty() {
+            for installment in self.loan_installments(loan_id).iter() {
+                schedule.push(installment);
+            }
+            return schedule;
+        }
 
-        // 3) Montante enviado como pagamento
-        let paid = self.call_value().egld_or_single_esdt().amount.clone();
-        require!(paid > BigUint::from(0u64), "Pagamentos devem ser maiores que zero");
-        require!(
-            paid <= loan.repayment_amount,
-            "Valor de pagamento excede o montante devido"
-        );
+        let loan = self.loans(loan_id).get();
+        let total_due = &loan.repayment_amount + &self.loan_payments(loan_id).get();
+        let interest = if total_due > loan.amount {
+            &total_due - &loan.amount
+        } else {
+            BigUint::zero()
+        };
+        schedule.push(Installment {
+            due_timestamp: loan.due_timestamp,
+            principal: loan.amount,
+            interest,
+        });
+        schedule
+    }
 
-        // 4) Debita do repayment_amount e grava o empréstimo
-        loan.repayment_amount = &loan.repayment_amount - &paid;
-        self.loans(loan_id).set(loan.clone());
+    // Número de parcelas de um prazo (em segundos); ao menos uma
+    fn installment_count(&self, duration: u64, period: InstallmentPeriod) -> u64 {
+        core::cmp::max(1u64, duration / (period.get_days() * 24 * 60 * 60))
+    }
 
-        // 5) Acumula em loan_payments
-        self.loan_payments(loan_id).update(|current| *current += paid.clone());
+    // Monta o cronograma de um empréstimo parcelado: principal amortizado em
+    // partes iguais e juros proporcionais ao saldo devedor de cada período.
+    // Os restos das divisões ficam na primeira parcela; a última vence no
+    // vencimento do empréstimo.
+    fn create_installment_schedule(&self, loan_id: u64, period: InstallmentPeriod) {
+        let loan = self.loans(loan_id).get();
+        let period_seconds = period.get_days() * 24 * 60 * 60;
+        let count = self.installment_count(loan.due_timestamp - loan.creation_timestamp, period);
+
+        let total_interest = &loan.repayment_amount - &loan.amount;
+        let principal_part = &loan.amount / count;
+        let principal_rest = &loan.amount - &(&principal_part * count);
+
+        // Pesos n, n-1, ..., 1 (saldo devedor decrescente)
+        let weight_sum = count * (count + 1) / 2;
+        let mut interest_assigned = BigUint::zero();
+        let mut installments: ManagedVec<Installment<Self::Api>> = ManagedVec::new();
+        for i in 0..count {
+            let interest = &total_interest * (count - i) / weight_sum;
+            interest_assigned += &interest;
+            let due_timestamp = if i == count - 1 {
+                loan.due_timestamp
+            } else {
+                loan.creation_timestamp + (i + 1) * period_seconds
+            };
+            installments.push(Installment {
+                due_timestamp,
+                principal: principal_part.clone(),
+                interest,
+            });
+        }
 
-        // 6) Se zerou, marca como Repaid e incrementa contador
-        if loan.repayment_amount == BigUint::from(0u64) {
-            // Marca como pago
-            let mut paid_loan = loan;
-            paid_loan.status = LoanStatus::Repaid;
-            self.loans(loan_id).set(paid_loan);
-            self.repaid_loans_count().update(|cnt| *cnt += 1u64);
+        let interest_rest = &total_interest - &interest_assigned;
+        for (i, mut installment) in installments.into_iter().enumerate() {
+            if i == 0 {
+                installment.principal += &principal_rest;
+                installment.interest += &interest_rest;
+            }
+            self.loan_installments(loan_id).push(&installment);
         }
     }
 
 
 
-    // 1) Lista de investidores cadastrados
+    // 1) Investidores com participação ativa
     #[storage_mapper("investors")]
-    fn investors(&self) -> VecMapper<ManagedAddress>;
+    fn investors(&self) -> UnorderedSetMapper<ManagedAddress>;
 
-    // 2) Juros totais acumulados (para distribuir)
+    // 2) Juros acumulados ainda não repassados ao acumulador por participação
     #[storage_mapper("total_interest_earned")]
     fn total_interest_earned(&self) -> SingleValueMapper<BigUint>;
 
-    #[endpoint]
-    fn add_investor(&self, investor: ManagedAddress, shares: u64) {
-        // validações existentes...
-        let current_shares = self.investor_shares(&investor).get();
-        self.investor_shares(&investor).set(current_shares + shares);
-
-        let total_shares = self.total_investor_shares().get();
-        self.total_investor_shares().set(total_shares + shares);
+    // 3) Lucro acumulado por participação (escala PROFIT_PER_SHARE_PRECISION)
+    #[storage_mapper("profit_per_share")]
+    fn profit_per_share(&self) -> SingleValueMapper<BigUint>;
 
-        // armazena na lista (poderá duplicar, mas os testes não verificam remoção)
-        self.investors().push(&investor);
-    }
+    // 4) Valor de profit_per_share na última liquidação de cada investidor
+    #[storage_mapper("investor_profit_checkpoint")]
+    fn investor_profit_checkpoint(&self, investor: &ManagedAddress) -> SingleValueMapper<BigUint>;
 
-    // -------------- novo endpoint --------------
+    // 5) Lucros já apurados e ainda não sacados
+    #[storage_mapper("investor_unclaimed_profits")]
+    fn investor_unclaimed_profits(&self, investor: &ManagedAddress) -> SingleValueMapper<BigUint>;
 
-    /// Distribui os juros acumulados entre os investidores, conforme suas participações,
-    /// e então zera o total_interest_earned.
-    #[only_owner]
-    #[endpoint(distributeProfits)]
-    fn distribute_profits(&self) {
-        let total_interest = self.total_interest_earned().get();
-        require!(total_interest > BigUint::from(0u64), "Não há lucros a distribuir");
-        
+    #[endpoint]
+    fn add_investor(&self, investor: ManagedAddress, shares: u64) {
+        self.require_role(Role::Admin);
+        require!(!investor.is_zero(), "Invalid investor address");
         let total_shares = self.total_investor_shares().get();
-        require!(total_shares > 0, "Nenhum investidor cadastrado");
-
-        // Para cada investidor na lista, calcula e envia sua parte proporcional
-        for investor in self.investors().iter() {
-            let shares = self.investor_shares(&investor).get();
-            let amount = &total_interest * &BigUint::from(shares) / &BigUint::from(total_shares);
-            if amount > BigUint::from(0u64) {
-                self.send().direct_egld(&investor, &amount);
-            }
-        }
-
-        // Zera o acumulador de juros
-        self.total_interest_earned().set(BigUint::from(0u64));
-    }
-
+        require!(
+            total_shares + shares <= MAX_TOTAL_INVESTOR_SHARES,
+            "Total investor shares cannot exceed 100%"
+        );
 
+        // Juros anteriores são divididos pelas participações antigas
+        self.settle_investor_profits(&investor);
 
+        self.investor_shares(&investor).update(|current| *current += shares);
+        self.total_investor_shares().set(total_shares + shares);
+        self.investors().insert(investor);
+    }
 
-    /// Remove um investidor e ajusta o total de participações
-    #[only_owner]
+    /// Remove um investidor e ajusta o total de participações.
+    /// Os lucros apurados até aqui continuam disponíveis em claimProfits
     #[endpoint(removeInvestor)]
     fn remove_investor(&self, investor: ManagedAddress) {
+        self.require_role(Role::Admin);
         // Recupera quantas shares o investidor tinha
         let shares = self.investor_shares(&investor).get();
         // Só permite remover quem realmente existe
         require!(shares > 0u64, "Investidor não encontrado");
 
+        self.settle_investor_profits(&investor);
+
         // Zera as participações deste investidor
         self.investor_shares(&investor).set(0u64);
+        self.investors().swap_remove(&investor);
 
         // Atualiza o total de participações
         let total = self.total_investor_shares().get();
@@ -542,31 +2987,99 @@ pub trait LoanController {
         self.total_investor_shares().set(total - shares);
     }
 
+    /// Saca os lucros do investidor (custo constante, independe do número de investidores)
+    #[endpoint(claimProfits)]
+    fn claim_profits(&self) -> BigUint {
+        let caller = self.blockchain().get_caller();
+        self.settle_investor_profits(&caller);
+
+        let amount = self.investor_unclaimed_profits(&caller).take();
+        require!(amount > 0u64, "Não há lucros a receber");
+
+        self.send().direct_egld(&caller, &amount);
+        self.profits_claimed_event(&caller, &amount);
+        amount
+    }
+
+    #[view(pendingProfits)]
+    fn pending_profits(&self, investor: ManagedAddress) -> BigUint {
+        let profit_per_share = self.profit_per_share().get() + self.unaccrued_profit_per_share().0;
+        self.investor_unclaimed_profits(&investor).get()
+            + self.profit_since_checkpoint(&investor, &profit_per_share)
+    }
+
+    #[view(getInvestors)]
+    fn get_investors(&self) -> MultiValueEncoded<ManagedAddress> {
+        self.investors().iter().collect()
+    }
+
+    // Repassa total_interest_earned ao acumulador por participação. O resto
+    // da divisão fica em total_interest_earned para a próxima rodada
+    fn accrue_investor_profits(&self) {
+        let (increment, distributed) = self.unaccrued_profit_per_share();
+        if increment == 0u64 {
+            return;
+        }
+        self.profit_per_share().update(|pps| *pps += &increment);
+        self.total_interest_earned().update(|pending| *pending -= &distributed);
+    }
+
+    // (incremento por participação, valor efetivamente distribuído)
+    fn unaccrued_profit_per_share(&self) -> (BigUint, BigUint) {
+        let total_shares = self.total_investor_shares().get();
+        let pending = self.total_interest_earned().get();
+        if total_shares == 0 || pending == 0u64 {
+            return (BigUint::zero(), BigUint::zero());
+        }
+        let increment = &pending * PROFIT_PER_SHARE_PRECISION / total_shares;
+        let distributed = &increment * total_shares / PROFIT_PER_SHARE_PRECISION;
+        (increment, distributed)
+    }
+
+    // Apura os lucros do investidor com as participações atuais e move o checkpoint
+    fn settle_investor_profits(&self, investor: &ManagedAddress) {
+        self.accrue_investor_profits();
+        let profit_per_share = self.profit_per_share().get();
+        let owed = self.profit_since_checkpoint(investor, &profit_per_share);
+        if owed > 0u64 {
+            self.investor_unclaimed_profits(investor).update(|unclaimed| *unclaimed += &owed);
+        }
+        self.investor_profit_checkpoint(investor).set(&profit_per_share);
+    }
+
+    fn profit_since_checkpoint(&self, investor: &ManagedAddress, profit_per_share: &BigUint) -> BigUint {
+        let shares = self.investor_shares(investor).get();
+        let checkpoint = self.investor_profit_checkpoint(investor).get();
+        if shares == 0 || *profit_per_share <= checkpoint {
+            return BigUint::zero();
+        }
+        (profit_per_share - &checkpoint) * shares / PROFIT_PER_SHARE_PRECISION
+    }
 
-    /// Modo de emergência: sacar todo o saldo
-    #[only_owner]
-    #[endpoint(emergencyWithdraw)]
-    fn emergency_withdraw(&self) {
-        // Verifica se o modo de emergência está ativo
-        require!(
-            self.emergency_mode().get(),
-            "Emergency mode is not active"
-        );
 
-        // Saldo atual do contrato
-        let sc_address = self.blockchain().get_sc_address();
-        let balance = self.blockchain().get_balance(&sc_address);
+    /// Saque de emergência (via performAction): envia ao owner o EGLD do
+    /// próprio protocolo. Garantias, fianças, reembolsos de leilão e lucros
+    /// dos investidores continuam no contrato para os seus donos
+    fn emergency_withdraw(&self) {
+        let amount = self.take_protocol_egld();
+        if amount > 0u64 {
+            let owner = self.blockchain().get_owner_address();
+            self.send().direct_egld(&owner, &amount);
+        }
+    }
 
-        // Envia tudo para o proprietário
-        let owner = self.blockchain().get_owner_address();
-        self.send().direct_egld(&owner, &balance);
+    // Zera e retorna o EGLD que pertence ao protocolo: saldo emprestável,
+    // reserva de bônus dos keepers e taxas apuradas
+    fn take_protocol_egld(&self) -> BigUint {
+        let egld = EgldOrEsdtTokenIdentifier::egld();
+        self.lendable_balance(&egld).take() + self.keeper_bounty_reserve().take() + self.protocol_fees(&egld).take()
     }
 
 
     /// Lista negra: impede que um usuário solicite empréstimos
-    #[only_owner]
     #[endpoint(addToBlacklist)]
     fn add_to_blacklist(&self, user: ManagedAddress) {
+        self.require_role(Role::Admin);
         // Marca o usuário como bloqueado
         self.blacklist(user).set(true);
     }
@@ -578,26 +3091,224 @@ pub trait LoanController {
         }
 
      /// Remove um usuário da blacklist, permitindo que ele solicite empréstimos de novo
-     #[only_owner]
      #[endpoint(removeFromBlacklist)]
      fn remove_from_blacklist(&self, user: ManagedAddress) {
+        self.require_role(Role::Admin);
          // Desmarca o usuário como bloqueado
          self.blacklist(user).set(false);
      }
  
 
     // Limite de empréstimos por usuário
-    #[only_owner]
     #[endpoint(setMaxLoansPerUser)]
     fn set_max_loans_per_user(&self, max: u64) {
-        self.max_loans_per_user().set(max);
+        self.set_parameter_directly(ParamType::MaxLoansPerUser, max);
+    }
+
+    // ===== Limites de exposição por tomador =====
+
+    // Define (ou substitui) os limites da faixa que começa em min_score.
+    // Vale a faixa de maior min_score que não ultrapassa a pontuação do tomador
+    #[endpoint(setExposureTier)]
+    fn set_exposure_tier(&self, min_score: u64, max_active_loans: u64, max_outstanding_principal: BigUint) {
+        self.set_config_directly(ConfigChange::ExposureTier {
+            min_score,
+            max_active_loans,
+            max_outstanding_principal,
+        });
+    }
+
+    #[endpoint(removeExposureTier)]
+    fn remove_exposure_tier(&self, min_score: u64) {
+        self.set_config_directly(ConfigChange::RemoveExposureTier { min_score });
+    }
+
+    // Faixas configuradas: (pontuação mínima, empréstimos ativos, principal em aberto)
+    #[view(getExposureTiers)]
+    fn get_exposure_tiers(&self) -> MultiValueEncoded<MultiValue3<u64, u64, BigUint>> {
+        let mut tiers = MultiValueEncoded::new();
+        for (min_score, limit) in self.exposure_tiers().iter() {
+            tiers.push((min_score, limit.max_active_loans, limit.max_outstanding_principal).into());
+        }
+        tiers
+    }
+
+    // Limites efetivos para uma pontuação (faixa + maxLoansPerUser)
+    #[view(getExposureLimit)]
+    fn get_exposure_limit(&self, score: u64) -> ExposureLimit<Self::Api> {
+        self.exposure_limit_for_score(score)
+    }
+
+    // Exposição atual do tomador: (empréstimos ativos, principal em aberto)
+    #[view(getBorrowerExposure)]
+    fn get_borrower_exposure(&self, borrower: ManagedAddress) -> MultiValue2<u64, BigUint> {
+        self.borrower_exposure(&borrower).into()
+    }
+
+    fn require_not_blacklisted(&self, user: &ManagedAddress) {
+        require!(!self.blacklist(user.clone()).get(), "User is blacklisted");
+    }
+
+    // Motor de exposição: roda antes de qualquer desembolso. Recusa endereços
+    // na blacklist, respeita o limite global de empréstimos ativos e os limites
+    // da faixa de pontuação do tomador (quantidade e principal em aberto)
+    fn require_within_exposure(&self, borrower: &ManagedAddress, amount: &BigUint, score: u64) {
+        match self.exposure_verdict(borrower, amount, score) {
+            ELIGIBILITY_BLACKLISTED => require!(false, "User is blacklisted"),
+            ELIGIBILITY_GLOBAL_LOAN_LIMIT => require!(false, "Global loan limit reached"),
+            ELIGIBILITY_MAX_ACTIVE_LOANS => require!(false, "User has reached maximum allowed loans"),
+            ELIGIBILITY_MAX_OUTSTANDING_PRINCIPAL => {
+                require!(false, "User has reached maximum outstanding principal")
+            },
+            _ => {},
+        }
+    }
+
+    // Primeiro limite de exposição violado pelo pedido (ELIGIBILITY_OK se nenhum);
+    // usado tanto na originação quanto em quoteLoan
+    fn exposure_verdict(&self, borrower: &ManagedAddress, amount: &BigUint, score: u64) -> u32 {
+        if self.blacklist(borrower.clone()).get() {
+            return ELIGIBILITY_BLACKLISTED;
+        }
+
+        let max_active_loans = self.max_active_loans().get();
+        if max_active_loans > 0 && self.active_loans_count().get() >= max_active_loans {
+            return ELIGIBILITY_GLOBAL_LOAN_LIMIT;
+        }
+
+        let limit = self.exposure_limit_for_score(score);
+        let (active_loans, outstanding_principal) = self.borrower_exposure(borrower);
+        if limit.max_active_loans > 0 && active_loans >= limit.max_active_loans {
+            return ELIGIBILITY_MAX_ACTIVE_LOANS;
+        }
+        if limit.max_outstanding_principal > 0u64
+            && outstanding_principal + amount > limit.max_outstanding_principal
+        {
+            return ELIGIBILITY_MAX_OUTSTANDING_PRINCIPAL;
+        }
+        ELIGIBILITY_OK
+    }
+
+    // Caminhos síncronos sem decisão de crédito usam a última pontuação conhecida
+    // (sem pontuação registrada, vale a pontuação 0); os pedidos
+    // assíncronos são verificados em open_scored_loan com a pontuação atual
+    fn require_within_exposure_cached(&self, borrower: &ManagedAddress, amount: &BigUint) {
+        let score = self.user_reputation_scores(borrower).get();
+        self.require_within_exposure(borrower, amount, score);
+    }
+
+    // Pré-verificação dos pedidos assíncronos: só os limites que não dependem da
+    // pontuação; as faixas são conferidas em open_scored_loan com a pontuação da decisão
+    fn require_within_unscored_exposure(&self, borrower: &ManagedAddress) {
+        self.require_not_blacklisted(borrower);
+
+        let max_active_loans = self.max_active_loans().get();
+        require!(
+            max_active_loans == 0 || self.active_loans_count().get() < max_active_loans,
+            "Global loan limit reached"
+        );
+
+        let max_loans_per_user = self.max_loans_per_user().get();
+        require!(
+            max_loans_per_user == 0 || self.borrower_exposure(borrower).0 < max_loans_per_user,
+            "User has reached maximum allowed loans"
+        );
+    }
+
+    // Faixa de maior min_score que não ultrapassa a pontuação; maxLoansPerUser,
+    // quando configurado, continua sendo o teto de quantidade para todas as faixas
+    fn exposure_limit_for_score(&self, score: u64) -> ExposureLimit<Self::Api> {
+        let mut tier: Option<(u64, ExposureLimit<Self::Api>)> = None;
+        for (min_score, limit) in self.exposure_tiers().iter() {
+            let is_better = match &tier {
+                Some((best_min_score, _)) => min_score > *best_min_score,
+                None => true,
+            };
+            if min_score <= score && is_better {
+                tier = Some((min_score, limit));
+            }
+        }
+        let mut limit = match tier {
+            Some((_, limit)) => limit,
+            None => ExposureLimit {
+                max_active_loans: 0,
+                max_outstanding_principal: BigUint::zero(),
+            },
+        };
+
+        let max_loans_per_user = self.max_loans_per_user().get();
+        if max_loans_per_user > 0 && (limit.max_active_loans == 0 || max_loans_per_user < limit.max_active_loans) {
+            limit.max_active_loans = max_loans_per_user;
+        }
+        limit
+    }
+
+    // Empréstimos ativos do tomador e o principal ainda em aberto, mantidos
+    // pelos contadores do tomador (ou apurados dos empréstimos, se ainda não sincronizados)
+    fn borrower_exposure(&self, borrower: &ManagedAddress) -> (u64, BigUint) {
+        let (active_loans, mut outstanding_principal) = if self.borrower_exposure_synced(borrower).get() {
+            (
+                self.borrower_active_loans(borrower).get(),
+                self.borrower_outstanding_principal(borrower).get(),
+            )
+        } else {
+            self.exposure_from_user_loans(borrower)
+        };
+        // Principal sacado da linha de crédito também conta na exposição
+        if !self.credit_lines(borrower).is_empty() {
+            outstanding_principal += self.credit_lines(borrower).get().principal;
+        }
+        (active_loans, outstanding_principal)
+    }
+
+    // Percorre os empréstimos do tomador; usado uma única vez por tomador,
+    // para os empréstimos anteriores aos contadores
+    fn exposure_from_user_loans(&self, borrower: &ManagedAddress) -> (u64, BigUint) {
+        let mut active_loans = 0u64;
+        let mut outstanding_principal = BigUint::zero();
+        for loan_id in self.user_loans(borrower.clone()).iter() {
+            let loan = self.loans(loan_id).get();
+            if loan.status == LoanStatus::Active {
+                active_loans += 1;
+                outstanding_principal += self.loan_outstanding_principal(loan_id, &loan);
+            }
+        }
+        (active_loans, outstanding_principal)
+    }
+
+    // Inicializa os contadores do tomador a partir do estado já gravado.
+    // Retorna false quando acabou de inicializar: a alteração em curso já está incluída
+    fn sync_borrower_exposure(&self, borrower: &ManagedAddress) -> bool {
+        if self.borrower_exposure_synced(borrower).get() {
+            return true;
+        }
+        let (active_loans, outstanding_principal) = self.exposure_from_user_loans(borrower);
+        self.borrower_active_loans(borrower).set(active_loans);
+        self.borrower_outstanding_principal(borrower).set(outstanding_principal);
+        self.borrower_exposure_synced(borrower).set(true);
+        false
+    }
+
+    // Principal de um empréstimo ainda não devolvido à origem dos fundos
+    fn loan_outstanding_principal(&self, loan_id: u64, loan: &Loan<Self::Api>) -> BigUint {
+        let returned = self.pool_principal_returned(loan_id).get();
+        if loan.amount > returned {
+            &loan.amount - &returned
+        } else {
+            BigUint::zero()
+        }
+    }
+
+    fn release_borrower_principal(&self, borrower: &ManagedAddress, amount: &BigUint) {
+        self.borrower_outstanding_principal(borrower).update(|total| {
+            *total = if *total > *amount { &*total - amount } else { BigUint::zero() };
+        });
     }
 
     // Quantia mínima de garantia exigida
-    #[only_owner]
     #[endpoint(setMinCollateralAmount)]
     fn set_min_collateral_amount(&self, amount: BigUint) {
-        self.min_collateral_amount().set(amount);
+        self.set_config_directly(ConfigChange::MinCollateralAmount { amount });
     }
 
 
@@ -606,12 +3317,12 @@ pub trait LoanController {
 
     #[endpoint]
     fn set_min_interest_rate(&self, rate: u64) {
-        self.min_interest_rate().set(rate);
+        self.set_parameter_directly(ParamType::MinInterestRate, rate);
     }
     
     #[endpoint]
     fn set_max_interest_rate(&self, rate: u64) {
-        self.max_interest_rate().set(rate);
+        self.set_parameter_directly(ParamType::MaxInterestRate, rate);
     }
     
     #[view(getMinInterestRate)]
@@ -624,22 +3335,9 @@ pub trait LoanController {
         self.max_interest_rate().get()
     }
 
-    #[storage_mapper("mock_timestamp")]
-    fn mock_timestamp(&self) -> SingleValueMapper<u64>;
-
-    #[endpoint]
-    fn set_mock_timestamp(&self, timestamp: u64) {
-        self.mock_timestamp().set(timestamp);
-    }
-
-    #[view]
-    fn get_block_timestamp(&self) -> u64 {
-        self.mock_timestamp().get()
-    }
-
     #[endpoint]
     fn set_max_active_loans(&self, max_loans: u64) {
-        self.max_active_loans().set(max_loans);
+        self.set_parameter_directly(ParamType::MaxActiveLoans, max_loans);
     }
 
     #[view(getMaxActiveLoans)]
@@ -664,14 +3362,11 @@ pub trait LoanController {
     fn request_loan_sync(&self, amount: BigUint, duration_days: u64) -> u64 {
         let caller = self.blockchain().get_caller();
 
-        // Verificar se o usuário já atingiu o limite de empréstimos ativos
-        let active_loans = self.user_loans(caller.clone()).len();
-        let max_active_loans = self.max_active_loans().get();
-        require!(active_loans < max_active_loans as usize, "Limite de empréstimos ativos atingido");
+        // Verificar os limites de exposição (global e do tomador)
+        self.require_within_exposure_cached(&caller, &amount);
 
         // Gerar um novo ID de empréstimo
-        let loan_id = self.loan_counter().get();
-        self.loan_counter().set(loan_id + 1);
+        let loan_id = self.next_loan_id();
 
         // Calcular o valor total a ser pago
         let interest_rate = self.interest_rate_base().get();
@@ -679,7 +3374,7 @@ pub trait LoanController {
         let repayment_amount = &amount + &interest_amount;
 
         // Criar o empréstimo
-        let current_timestamp = self.blockchain().get_block_timestamp();
+        let current_timestamp = self.current_timestamp();
         let due_timestamp = current_timestamp + duration_days * 86400;
 
         let loan = Loan {
@@ -692,41 +3387,218 @@ pub trait LoanController {
             status: LoanStatus::Active,
         };
 
-        // Armazenar o empréstimo
-        self.loans(loan_id).set(loan);
+        // Armazenar o empréstimo
+        self.loans(loan_id).set(loan);
+
+        // Associar o empréstimo ao usuário
+        self.user_loans(caller.clone()).push(&loan_id);
+
+        // Emitir o NFT de dívida
+        self.on_loan_created(loan_id);
+
+        // Retornar o ID do empréstimo
+        loan_id
+    }
+
+    // Varre por id no máximo `max_items` empréstimos a partir de `from_id`
+    // (limitado a MAX_LOANS_SCAN_PER_PAGE), inclusive os que não estão no índice
+    // por dia de vencimento. Retorna o id onde continuar, ou nada ao chegar ao fim.
+    #[endpoint]
+    fn mark_expired_loans(&self, from_id: u64, max_items: u64) -> OptionalValue<u64> {
+        self.require_role(Role::Keeper);
+        require!(max_items > 0, "max_items deve ser maior que zero");
+        let current_timestamp = self.current_timestamp();
+
+        let last_id = self.loan_counter().get();
+        let scan_end = from_id.saturating_add(max_items.min(MAX_LOANS_SCAN_PER_PAGE));
+        let mut loan_id = from_id;
+        while loan_id <= last_id && loan_id < scan_end {
+            if !self.loans(loan_id).is_empty() {
+                let loan = self.loans(loan_id).get();
+                if loan.status == LoanStatus::Active && current_timestamp >= self.sweep_due_timestamp(loan_id, &loan) {
+                    self.default_overdue_loan(loan_id, loan);
+                }
+            }
+            loan_id += 1;
+        }
+
+        if loan_id <= last_id {
+            OptionalValue::Some(loan_id)
+        } else {
+            OptionalValue::None
+        }
+    }
+
+    // Varredura paginada e sem permissão dos empréstimos vencidos, em ordem de
+    // vencimento. Percorre o índice por dia a partir do cursor, processando no
+    // máximo `max_items` itens (empréstimos ou dias vazios). Quem chama recebe
+    // o bônus de keeper por empréstimo marcado como inadimplente.
+    #[endpoint(processOverdueLoans)]
+    fn process_overdue_loans(&self, max_items: u64) -> u64 {
+        require!(max_items > 0, "max_items deve ser maior que zero");
+        let now = self.current_timestamp();
+        let today = now / 86400;
+
+        let mut budget = max_items;
+        let mut defaulted = 0u64;
+        let mut day = self.overdue_cursor_day().get();
+        while budget > 0 && day <= today {
+            let mut bucket = self.due_day_loans(day);
+            if bucket.is_empty() {
+                day += 1;
+                budget -= 1;
+                continue;
+            }
+
+            let mut due_ids = ManagedVec::<Self::Api, u64>::new();
+            for loan_id in bucket.iter() {
+                if due_ids.len() as u64 >= budget {
+                    break;
+                }
+                due_ids.push(loan_id);
+            }
+            for loan_id in due_ids.iter() {
+                let loan = self.loans(loan_id).get();
+                if loan.status != LoanStatus::Active {
+                    bucket.swap_remove(&loan_id);
+                    budget -= 1;
+                    continue;
+                }
+                if self.sweep_due_timestamp(loan_id, &loan) > now {
+                    continue;
+                }
+                self.default_overdue_loan(loan_id, loan);
+                defaulted += 1;
+                budget -= 1;
+            }
+
+            // Empréstimos que vencem mais tarde hoje mantêm o cursor neste dia
+            if !self.due_day_loans(day).is_empty() {
+                break;
+            }
+            day += 1;
+        }
+        self.overdue_cursor_day().set(day);
+
+        // O bônus sai apenas da reserva depositada em fundKeeperBounty, nunca
+        // do saldo emprestável ou das garantias; reserva insuficiente paga o que houver
+        let caller = self.blockchain().get_caller();
+        let bounty = self.keeper_bounty().get() * defaulted;
+        let reserve = self.keeper_bounty_reserve().get();
+        let bounty_paid = core::cmp::min(bounty, reserve);
+        if bounty_paid > 0u64 {
+            self.keeper_bounty_reserve().update(|reserve| *reserve -= &bounty_paid);
+            self.send().direct_egld(&caller, &bounty_paid);
+        }
+        self.overdue_sweep_event(&caller, defaulted, &bounty_paid);
+
+        defaulted
+    }
+
+    // Bônus pago (em EGLD) ao keeper por empréstimo marcado como inadimplente
+    #[endpoint(setKeeperBounty)]
+    fn set_keeper_bounty(&self, bounty: BigUint) {
+        self.set_config_directly(ConfigChange::KeeperBounty { bounty });
+    }
+
+    #[view(getKeeperBounty)]
+    fn get_keeper_bounty(&self) -> BigUint {
+        self.keeper_bounty().get()
+    }
+
+    // Deposita EGLD na reserva que paga os bônus dos keepers
+    #[payable("EGLD")]
+    #[endpoint(fundKeeperBounty)]
+    fn fund_keeper_bounty(&self) {
+        let amount = self.call_value().egld().clone();
+        require!(amount > 0u64, "Valor deve ser maior que zero");
+        self.keeper_bounty_reserve().update(|reserve| *reserve += &amount);
+    }
+
+    #[view(getKeeperBountyReserve)]
+    fn get_keeper_bounty_reserve(&self) -> BigUint {
+        self.keeper_bounty_reserve().get()
+    }
+
+    /// Dia (timestamp / 86400) a partir do qual a próxima varredura começa
+    #[view(getOverdueCursorDay)]
+    fn get_overdue_cursor_day(&self) -> u64 {
+        self.overdue_cursor_day().get()
+    }
+
+    /// Empréstimos ativos que vencem no dia informado (timestamp / 86400)
+    #[view(getLoansDueOnDay)]
+    fn get_loans_due_on_day(&self, day: u64) -> MultiValueEncoded<u64> {
+        let mut result = MultiValueEncoded::new();
+        for loan_id in self.due_day_loans(day).iter() {
+            result.push(loan_id);
+        }
+        result
+    }
+
+    // Marca um empréstimo vencido como inadimplente
+    fn default_overdue_loan(&self, loan_id: u64, mut loan: Loan<Self::Api>) {
+        loan.status = LoanStatus::Defaulted;
+        self.loans(loan_id).set(&loan);
+        self.on_loan_closed(loan_id, &LoanStatus::Active, &LoanStatus::Defaulted);
+        self.report_default(&loan);
+
+        // incrementa contador de overdue
+        self.overdue_loans_count().update(|cnt| *cnt += 1u64);
+        self.loan_defaulted_event(loan_id, &loan.borrower, loan.due_timestamp);
+    }
 
-        // Associar o empréstimo ao usuário
-        self.user_loans(caller.clone()).push(&loan_id);
+    // Vencimento que as varreduras observam: a parcela em aberto mais antiga
+    // dos empréstimos parcelados, ou o vencimento final dos demais
+    fn sweep_due_timestamp(&self, loan_id: u64, loan: &Loan<Self::Api>) -> u64 {
+        if self.loan_installments(loan_id).is_empty() {
+            return loan.due_timestamp;
+        }
+        match self.get_remaining_schedule(loan_id).iter().next() {
+            Some(installment) => installment.due_timestamp,
+            None => loan.due_timestamp,
+        }
+    }
 
-        // Retornar o ID do empréstimo
-        loan_id
+    // Inclui um empréstimo ativo no índice por dia de vencimento
+    fn index_active_loan(&self, loan_id: u64) {
+        let loan = self.loans(loan_id).get();
+        let day = self.sweep_due_timestamp(loan_id, &loan) / 86400;
+        if self.due_day_loans(day).insert(loan_id) {
+            self.active_loans_count().update(|cnt| *cnt += 1u64);
+        }
+        self.loan_index_day(loan_id).set(day);
+        if self.overdue_cursor_day().is_empty() || day < self.overdue_cursor_day().get() {
+            self.overdue_cursor_day().set(day);
+        }
     }
 
-    #[endpoint]
-    fn mark_expired_loans(&self) {
-        let current_timestamp = self.blockchain().get_block_timestamp();
-    
-        // Iterate over all loans
-        let loan_counter = self.loan_counter().get();
-        for loan_id in 0..loan_counter {
-            let mut loan = self.loans(loan_id).get(); // Use `get` to retrieve the loan
-            // Check if the loan is active and expired
-            if loan.status == LoanStatus::Active && current_timestamp >= loan.due_timestamp {
-                // marca defaulted
-                loan.status = LoanStatus::Defaulted;
-                self.loans(loan_id).set(loan);
-
-                // incrementa contador de overdue
-                self.overdue_loans_count().update(|cnt| *cnt += 1u64);
-            }
+    // Remove um empréstimo do índice; retorna false se ele não estava indexado.
+    // Entradas antigas, sem o dia gravado, estão no dia do vencimento final
+    fn unindex_active_loan(&self, loan_id: u64) -> bool {
+        let day = if self.loan_index_day(loan_id).is_empty() {
+            self.loans(loan_id).get().due_timestamp / 86400
+        } else {
+            self.loan_index_day(loan_id).take()
+        };
+        let removed = self.due_day_loans(day).swap_remove(&loan_id);
+        if removed {
+            self.active_loans_count().update(|cnt| *cnt = cnt.saturating_sub(1));
+        }
+        removed
+    }
+
+    // Move o empréstimo para o dia da parcela em aberto mais antiga
+    fn reindex_active_loan(&self, loan_id: u64) {
+        if self.unindex_active_loan(loan_id) {
+            self.index_active_loan(loan_id);
         }
     }
 
     // Adicione um endpoint para configurar a taxa de extensão
     #[endpoint(setExtensionFeePercent)]
     fn set_extension_fee_percent(&self, fee_percent: u64) {
-        require!(fee_percent <= 10000, "A taxa de extensão não pode exceder 100%");
-        self.extension_fee_percent().set(fee_percent);
+        self.set_parameter_directly(ParamType::ExtensionFeePercent, fee_percent);
     }
 
     // Adicione a view para acessar a taxa de extensão
@@ -738,8 +3610,7 @@ pub trait LoanController {
     // Adicione um endpoint para configurar a taxa diária de atraso
     #[endpoint(setLateFeeDailyRate)]
     fn set_late_fee_daily_rate(&self, rate: u64) {
-        require!(rate <= 10000, "A taxa diária de atraso não pode exceder 100%");
-        self.late_fee_daily_rate().set(rate);
+        self.set_parameter_directly(ParamType::LateFeeDailyRate, rate);
     }
 
     // Adicione uma view para consultar a taxa diária de atraso
@@ -751,14 +3622,12 @@ pub trait LoanController {
     // Endpoints para configuração
     #[endpoint(setCollateralRatio)]
     fn set_collateral_ratio(&self, ratio: u64) {
-        require!(ratio <= 10000, "A razão de garantia não pode exceder 100%");
-        self.collateral_ratio().set(ratio);
+        self.set_parameter_directly(ParamType::CollateralRatio, ratio);
     }
 
     #[endpoint(setLiquidationDiscount)]
     fn set_liquidation_discount(&self, discount: u64) {
-        require!(discount <= 10000, "O desconto de liquidação não pode exceder 100%");
-        self.liquidation_discount().set(discount);
+        self.set_parameter_directly(ParamType::LiquidationDiscount, discount);
     }
 
     // Views para consulta
@@ -793,39 +3662,29 @@ pub trait LoanController {
         let caller = self.blockchain().get_caller();
         let amount = self.base_loan_amount().get();
         let term = LoanTerm::Standard;
-        let rs_address = self.reputation_score_address().get();
-        let min_score = self.min_required_score().get();
 
-        // Initiate the on-chain eligibility call
-        self.reputation_score_proxy(rs_address.clone())
-            .is_eligible_for_loan(caller.clone(), min_score)
-            .with_callback(self.callbacks().check_eligibility_callback(
-                caller,
-                amount,
-                term
-            ))
-            .call_and_exit();
+        // Initiate the on-chain credit decision
+        self.request_credit_decision(caller, amount, self.default_loan_token(), term, None, 0);
     }
 
     // --- Wrapper to request a "standard" loan in tests ---
     #[endpoint(requestLoanStandard)]
     #[payable("*")]
-    fn request_loan_standard(&self) -> u64 {
+    fn request_loan_standard(&self) -> OptionalValue<u64> {
         let caller = self.blockchain().get_caller();
         let amount = self.base_loan_amount().get();
         let term = LoanTerm::Standard;
-        let rs_address = self.reputation_score_address().get();
-        let min_score = self.min_required_score().get();
 
         // Use the same pipeline as `request_loan` but with default values
-        self.reputation_score_proxy(rs_address.clone())
-            .is_eligible_for_loan(caller.clone(), min_score)
-            .with_callback(self.callbacks().check_eligibility_callback(
-                caller.clone(),
-                amount.clone(),
-                term
-            ))
-            .call_and_exit();
+        let last_loan_id = self.loan_counter().get();
+        self.request_credit_decision(caller, amount, self.default_loan_token(), term, None, 0);
+
+        // Only the same-shard path gets here; None when the request was queued for underwriting
+        if self.loan_counter().get() > last_loan_id {
+            OptionalValue::Some(self.loan_counter().get())
+        } else {
+            OptionalValue::None
+        }
     }
 
 
@@ -834,19 +3693,28 @@ pub trait LoanController {
 
 
 
+    // Os prazos individuais seguem a mesma validação e o mesmo timelock de setLoanTerms
     #[endpoint]
     fn set_standard_loan_term_days(&self, days: u64) {
-        self.standard_loan_term_days().set(days);
+        self.set_config_directly(ConfigChange::LoanTerms {
+            standard: days,
+            extended: self.extended_term_days().get(),
+            short: self.short_term_days().get(),
+        });
     }
     
     #[endpoint]
     fn set_extended_loan_term_days(&self, days: u64) {
-        self.extended_loan_term_days().set(days);
+        self.set_config_directly(ConfigChange::LoanTerms {
+            standard: self.standard_term_days().get(),
+            extended: days,
+            short: self.short_term_days().get(),
+        });
     }
     
     #[endpoint]
     fn set_max_loan_term_days(&self, days: u64) {
-        self.max_loan_term_days().set(days);
+        self.set_parameter_directly(ParamType::MaxLoanTermDays, days);
     }
 
     #[endpoint]
@@ -855,17 +3723,28 @@ pub trait LoanController {
         user_address: ManagedAddress,
         score: u64,
     ) {
-        self.user_reputation_scores(&user_address).set(score);
+        let caller = self.blockchain().get_caller();
+        require!(
+            caller == self.reputation_score_address().get()
+                || self.is_owner_or_has_role(Role::Oracle, &caller),
+            "Only reputation score contract can call this function"
+        );
+        let previous_score = self.user_reputation_scores(&user_address).replace(score);
+
+        // Mudança de pontuação recalcula o limite da linha de crédito
+        if previous_score != score && !self.credit_lines(&user_address).is_empty() {
+            self.request_credit_limit(user_address, OptionalValue::None);
+        }
     }
     
     #[view(getStandardLoanTermDays)]
     fn get_standard_loan_term_days(&self) -> u64 {
-        self.standard_loan_term_days().get()
+        self.standard_term_days().get()
     }
     
     #[view(getExtendedLoanTermDays)]
     fn get_extended_loan_term_days(&self) -> u64 {
-        self.extended_loan_term_days().get()
+        self.extended_term_days().get()
     }
     
     #[view(getMaxLoanTermDays)]
@@ -876,124 +3755,74 @@ pub trait LoanController {
     // Endpoints para configuração
     #[endpoint]
     fn set_interest_rate_base(&self, rate: u64) {
-        self.interest_rate_base().set(rate);
+        self.set_parameter_directly(ParamType::InterestRate, rate);
     }
 
     #[endpoint]
     fn set_extended_term_rate_multiplier(&self, multiplier: u64) {
-        self.extended_term_rate_multiplier().set(multiplier);
+        self.set_parameter_directly(ParamType::ExtendedTermRateMultiplier, multiplier);
     }
 
     #[endpoint]
     fn set_max_term_rate_multiplier(&self, multiplier: u64) {
-        self.max_term_rate_multiplier().set(multiplier);
+        self.set_parameter_directly(ParamType::MaxTermRateMultiplier, multiplier);
     }
 
     #[endpoint]
     fn set_min_required_score(&self, score: u64) {
-        require!(self.blockchain().get_caller() == self.blockchain().get_owner_address(), "Only owner can call this function");
-        self.min_required_score().set(score);
-    }
-
-    #[endpoint(initiateContractDestruction)]
-    fn initiate_contract_destruction(&self) {
-        // Only owner can destroy the contract
-        require!(
-            self.blockchain().get_caller() == self.blockchain().get_owner_address(),
-            "Only owner can initiate contract destruction"
-        );
-
-        // Optional: Add a timelock for safety
-        let current_timestamp = self.blockchain().get_block_timestamp();
-        self.destruction_timelock().set(current_timestamp + 86400); // 24 hour delay
-    }
-
-    // Add method to execute the destruction after timelock
-    #[endpoint(executeContractDestruction)]
-    fn execute_contract_destruction(&self) {
-        // Only owner can execute destruction
-        require!(
-            self.blockchain().get_caller() == self.blockchain().get_owner_address(),
-            "Only owner can execute contract destruction"
-        );
-
-        // Check if timelock has passed
-        let current_timestamp = self.blockchain().get_block_timestamp();
-        let destruction_time = self.destruction_timelock().get();
-        require!(
-            current_timestamp >= destruction_time,
-            "Destruction timelock has not expired"
-        );
-
-        // Perform contract cleanup and destruction
-        self.cleanup_and_destroy();
+        self.set_parameter_directly(ParamType::MinScore, score);
     }
 
-    // Helper method for cleanup
+    // Destruição aprovada pelo multisig: pausa o contrato em definitivo e
+    // devolve ao owner o EGLD do protocolo. Pagamentos, resgates de garantia
+    // e liquidações seguem abertos, então os fundos dos usuários ficam
     fn cleanup_and_destroy(&self) {
-        // Add cleanup logic here
-        // For example: return funds to owner, clear storage, etc.
-        self.send()
-            .direct_egld(&self.blockchain().get_owner_address(), &self.blockchain().get_balance(&self.blockchain().get_sc_address()));
+        self.contract_destroyed().set(true);
+        self.paused().set(true);
+        self.emergency_withdraw();
     }
-    
 
-    // Renomear de initiate_contract_destruction para initiate_contract_destruction_v2
-    #[endpoint(initiateContractDestructionV2)]
-    fn initiate_contract_destruction_v2(&self) {
-        require!(
-            self.blockchain().get_caller() == self.blockchain().get_owner_address(),
-            "Only owner can initiate contract destruction"
-        );
-        self.destruction_pending_v2().set(true);
-        self.destruction_confirmation_count().set(1u32);
+    // Executa uma proposta do multisig que atingiu o quórum
+    #[endpoint(performAction)]
+    fn perform_action(&self, action_id: u64) {
+        let action = self.take_approved_action(action_id);
+        if self.perform_signer_action(&action) {
+            return;
+        }
+
+        match action {
+            MultisigAction::ContractDestruction => self.cleanup_and_destroy(),
+            MultisigAction::EmergencyWithdraw => self.emergency_withdraw(),
+            MultisigAction::SetOracle { address } => self.apply_price_source(&address),
+            _ => sc_panic!("Ação não suportada por este contrato"),
+        }
     }
 
-    #[only_owner]
     #[endpoint(pauseContract)]
     fn pause_contract(&self) {
+        self.require_role(Role::Pauser);
         self.paused().set(true);
     }
 
-    #[only_owner]
     #[endpoint(unpauseContract)]
     fn unpause_contract(&self) {
+        self.require_role(Role::Pauser);
+        require!(!self.contract_destroyed().get(), "Contrato destruído");
         self.paused().set(false);
     }
 
 
-    // Renomear de confirm_contract_destruction para confirm_contract_destruction_v2
-    #[endpoint(confirmContractDestructionV2)]
-    fn confirm_contract_destruction_v2(&self) {
-        require!(
-            self.blockchain().get_caller() == self.blockchain().get_owner_address(),
-            "Only owner can confirm contract destruction"
-        );
-        let count = self.destruction_confirmation_count().get();
-        self.destruction_confirmation_count().set(count + 1);
-    }
-
-    // Renomear de cancel_contract_destruction para cancel_contract_destruction_v2
-    #[endpoint(cancelContractDestructionV2)]
-    fn cancel_contract_destruction_v2(&self) {
-        require!(
-            self.blockchain().get_caller() == self.blockchain().get_owner_address(),
-            "Only owner can cancel contract destruction"
-        );
-        self.destruction_pending_v2().set(false);
-        self.destruction_timelock().set(0u64);
-    }
-
     #[endpoint(requestLoanWithTerm)]
     fn request_loan_with_term(&self, term: LoanTerm) -> u64 {
-        let loan_id = self.loan_counter().get() + 1;
-        self.loan_counter().set(loan_id);
+        self.require_within_exposure_cached(&self.blockchain().get_caller(), &self.base_loan_amount().get());
+
+        let loan_id = self.next_loan_id();
 
         let due_date = match term {
-            LoanTerm::Standard => self.blockchain().get_block_timestamp() + 30 * 24 * 60 * 60,
-            LoanTerm::Extended => self.blockchain().get_block_timestamp() + 90 * 24 * 60 * 60,
-            LoanTerm::Short => self.blockchain().get_block_timestamp() + 15 * 24 * 60 * 60,
-            LoanTerm::Maximum => self.blockchain().get_block_timestamp() + 180 * 24 * 60 * 60,
+            LoanTerm::Standard => self.current_timestamp() + 30 * 24 * 60 * 60,
+            LoanTerm::Extended => self.current_timestamp() + 90 * 24 * 60 * 60,
+            LoanTerm::Short => self.current_timestamp() + 15 * 24 * 60 * 60,
+            LoanTerm::Maximum => self.current_timestamp() + 180 * 24 * 60 * 60,
         };
 
         let loan = Loan {
@@ -1001,13 +3830,14 @@ pub trait LoanController {
             amount: self.base_loan_amount().get(),
             repayment_amount: self.base_loan_amount().get() + self.calculate_interest_rate(800u64),
             interest_rate: self.calculate_interest_rate(800u64),
-            creation_timestamp: self.blockchain().get_block_timestamp(),
+            creation_timestamp: self.current_timestamp(),
             due_timestamp: due_date,
             status: LoanStatus::Active,
         };
 
         self.loans(loan_id).set(&loan);
         self.user_loans(self.blockchain().get_caller()).push(&loan_id);
+        self.on_loan_created(loan_id);
 
         loan_id
     }
@@ -1035,7 +3865,7 @@ pub trait LoanController {
 
     #[view(calculateDueDateSafely)]
     fn calculate_due_date_safely(&self, term_in_seconds: u64) -> u64 {
-        let current_timestamp = self.blockchain().get_block_timestamp();
+        let current_timestamp = self.current_timestamp();
         let max_seconds = 3650u64 * 24u64 * 60u64 * 60u64; // 10 years in seconds
 
         // Ensure the term does not exceed the maximum allowed duration
@@ -1066,7 +3896,7 @@ pub trait LoanController {
 
     #[endpoint]
     fn set_operation_timelock(&self, timelock: u64) {
-        self.operation_timelock().set(timelock);
+        self.set_parameter_directly(ParamType::OperationTimelock, timelock);
     }
 
     #[view(getOperationTimelock)]
@@ -1074,18 +3904,425 @@ pub trait LoanController {
         self.operation_timelock().get()
     }
 
+    // ----- Governança de parâmetros com timelock -----
+    // Com operation_timelock zero (configuração inicial) os setters aplicam
+    // na hora; depois disso toda alteração de risco passa por
+    // proposta → espera do timelock → execução (ou cancelamento).
+
+    /// Propõe uma alteração de parâmetro, executável após operation_timelock
+    #[endpoint(proposeParameterChange)]
+    fn propose_parameter_change(&self, param: ParamType, value: u64) {
+        self.require_parameter_role(param);
+        require!(
+            self.pending_parameter_changes(param).is_empty(),
+            "Já existe alteração pendente para este parâmetro"
+        );
+        self.validate_parameter(param, value);
+
+        let now = self.current_timestamp();
+        let executable_at = now + self.operation_timelock().get();
+        self.pending_parameter_changes(param).set(ParameterChange { value, timestamp: now, executable_at });
+        self.pending_parameter_types().insert(param);
+
+        self.parameter_change_proposed_event(param, value, executable_at);
+    }
+
+    /// Aplica uma alteração cujo timelock terminou, dentro do prazo de execução
+    #[endpoint(executeParameterChange)]
+    fn execute_parameter_change(&self, param: ParamType) {
+        self.require_parameter_role(param);
+        require!(
+            !self.pending_parameter_changes(param).is_empty(),
+            "Nenhuma alteração pendente para este parâmetro"
+        );
+        let change = self.pending_parameter_changes(param).get();
+        self.require_change_executable(change.executable_at);
+
+        self.clear_pending_parameter_change(param);
+        self.apply_parameter(param, change.value);
+        self.parameter_change_executed_event(param, change.value);
+    }
+
+    #[endpoint(cancelParameterChange)]
+    fn cancel_parameter_change(&self, param: ParamType) {
+        self.require_parameter_role(param);
+        require!(
+            !self.pending_parameter_changes(param).is_empty(),
+            "Nenhuma alteração pendente para este parâmetro"
+        );
+        self.clear_pending_parameter_change(param);
+        self.parameter_change_cancelled_event(param);
+    }
+
+    /// Alterações pendentes: (parâmetro, novo valor, proposta em, executável a partir de)
+    #[view(getPendingParameterChanges)]
+    fn get_pending_parameter_changes(&self) -> MultiValueEncoded<MultiValue4<ParamType, u64, u64, u64>> {
+        let mut result = MultiValueEncoded::new();
+        for param in self.pending_parameter_types().iter() {
+            let change = self.pending_parameter_changes(param).get();
+            result.push((param, change.value, change.timestamp, change.executable_at).into());
+        }
+        result
+    }
+
+    /// Propõe uma alteração de configuração (token, bônus, faixa de exposição,
+    /// limite de aprovação, prazos, garantia mínima), executável após operation_timelock
+    #[endpoint(proposeConfigChange)]
+    fn propose_config_change(&self, change: ConfigChange<Self::Api>) -> u64 {
+        self.require_config_change_role(&change);
+        self.validate_config_change(&change);
+
+        let now = self.current_timestamp();
+        let executable_at = now + self.operation_timelock().get();
+        let change_id = self.config_change_counter().update(|counter| {
+            *counter += 1;
+            *counter
+        });
+        self.pending_config_changes().insert(
+            change_id,
+            PendingConfigChange {
+                change,
+                proposed_at: now,
+                executable_at,
+            },
+        );
+
+        self.config_change_proposed_event(change_id, executable_at);
+        change_id
+    }
+
+    #[endpoint(executeConfigChange)]
+    fn execute_config_change(&self, change_id: u64) {
+        let pending = self.pending_config_change(change_id);
+        self.require_config_change_role(&pending.change);
+        self.require_change_executable(pending.executable_at);
+
+        self.pending_config_changes().remove(&change_id);
+        // Revalida contra o estado atual (ex.: número de faixas)
+        self.validate_config_change(&pending.change);
+        self.apply_config_change(pending.change);
+        self.config_change_executed_event(change_id);
+    }
+
+    #[endpoint(cancelConfigChange)]
+    fn cancel_config_change(&self, change_id: u64) {
+        let pending = self.pending_config_change(change_id);
+        self.require_config_change_role(&pending.change);
+
+        self.pending_config_changes().remove(&change_id);
+        self.config_change_cancelled_event(change_id);
+    }
+
+    /// Alterações de configuração pendentes: (id, alteração, proposta em, executável a partir de)
+    #[view(getPendingConfigChanges)]
+    fn get_pending_config_changes(&self) -> MultiValueEncoded<MultiValue4<u64, ConfigChange<Self::Api>, u64, u64>> {
+        let mut result = MultiValueEncoded::new();
+        for (change_id, pending) in self.pending_config_changes().iter() {
+            result.push((change_id, pending.change, pending.proposed_at, pending.executable_at).into());
+        }
+        result
+    }
+
+    fn pending_config_change(&self, change_id: u64) -> PendingConfigChange<Self::Api> {
+        match self.pending_config_changes().get(&change_id) {
+            Some(pending) => pending,
+            None => sc_panic!("Nenhuma alteração de configuração pendente com este id"),
+        }
+    }
+
+    // O prazo de execução foi fixado na proposta: mudar o timelock depois
+    // não antecipa alterações já pendentes
+    fn require_change_executable(&self, executable_at: u64) {
+        let now = self.current_timestamp();
+        require!(now >= executable_at, "Timelock ainda não expirou");
+        require!(
+            now <= executable_at + PARAMETER_CHANGE_GRACE_PERIOD,
+            "Alteração pendente expirou"
+        );
+    }
+
+    // Tokens, bônus de keeper e prazos são configuração de Admin; o resto, de RiskManager
+    fn require_config_change_role(&self, change: &ConfigChange<Self::Api>) {
+        match change {
+            ConfigChange::LoanToken { .. } | ConfigChange::KeeperBounty { .. } | ConfigChange::LoanTerms { .. } => {
+                self.require_role(Role::Admin)
+            },
+            _ => self.require_role(Role::RiskManager),
+        }
+    }
+
+    fn set_config_directly(&self, change: ConfigChange<Self::Api>) {
+        self.require_config_change_role(&change);
+        require!(
+            self.operation_timelock().get() == 0,
+            "Parâmetro sob timelock: use proposeConfigChange"
+        );
+        self.validate_config_change(&change);
+        self.apply_config_change(change);
+    }
+
+    fn validate_config_change(&self, change: &ConfigChange<Self::Api>) {
+        match change {
+            ConfigChange::LoanToken { token_id, interest_rate_base, liquidity_pool, .. } => {
+                require!(token_id.is_valid(), "Token inválido");
+                require!(*interest_rate_base <= 10000, "Taxa de juros não pode exceder 100%");
+                require!(
+                    liquidity_pool.is_zero() || token_id.is_esdt(),
+                    "Pool de liquidez só financia tokens ESDT"
+                );
+                // Os juros do saldo do contrato são distribuídos aos investidores em EGLD
+                require!(
+                    !liquidity_pool.is_zero() || token_id.is_egld(),
+                    "Tokens ESDT precisam de um pool de liquidez"
+                );
+            },
+            ConfigChange::ExposureTier { min_score, .. } => {
+                require!(
+                    self.exposure_tiers().contains_key(min_score) || self.exposure_tiers().len() < MAX_EXPOSURE_TIERS,
+                    "Número de faixas de exposição excede o máximo"
+                );
+            },
+            ConfigChange::RemoveExposureTier { min_score } => {
+                require!(self.exposure_tiers().contains_key(min_score), "Faixa de exposição não existe");
+            },
+            ConfigChange::LoanTerms { standard, extended, short } => {
+                require!(*standard > 0 && *extended > 0 && *short > 0, "Termos devem ser maiores que zero");
+                require!(extended > standard && standard > short, "Termos devem seguir a hierarquia");
+            },
+            ConfigChange::KeeperBounty { .. }
+            | ConfigChange::UnderwritingThreshold { .. }
+            | ConfigChange::MinCollateralAmount { .. } => {},
+        }
+    }
+
+    fn apply_config_change(&self, change: ConfigChange<Self::Api>) {
+        match change {
+            ConfigChange::LoanToken { token_id, interest_rate_base, max_loan_amount, liquidity_pool } => {
+                self.loan_token_config(&token_id).set(LoanTokenConfig {
+                    interest_rate_base,
+                    max_loan_amount,
+                    liquidity_pool,
+                });
+                self.loan_tokens().insert(token_id);
+            },
+            ConfigChange::KeeperBounty { bounty } => self.keeper_bounty().set(bounty),
+            ConfigChange::ExposureTier { min_score, max_active_loans, max_outstanding_principal } => {
+                self.exposure_tiers().insert(
+                    min_score,
+                    ExposureLimit {
+                        max_active_loans,
+                        max_outstanding_principal: max_outstanding_principal.clone(),
+                    },
+                );
+                self.exposure_tier_set_event(min_score, max_active_loans, &max_outstanding_principal);
+            },
+            ConfigChange::RemoveExposureTier { min_score } => {
+                self.exposure_tiers().remove(&min_score);
+                self.exposure_tier_removed_event(min_score);
+            },
+            ConfigChange::UnderwritingThreshold { threshold } => self.underwriting_threshold().set(threshold),
+            ConfigChange::LoanTerms { standard, extended, short } => {
+                self.standard_term_days().set(standard);
+                self.extended_term_days().set(extended);
+                self.short_term_days().set(short);
+            },
+            ConfigChange::MinCollateralAmount { amount } => self.min_collateral_amount().set(amount),
+        }
+    }
+
+    // O próprio timelock e o prazo máximo são configuração de Admin; os demais
+    // parâmetros, de RiskManager
+    fn require_parameter_role(&self, param: ParamType) {
+        match param {
+            ParamType::OperationTimelock | ParamType::MaxLoanTermDays => self.require_role(Role::Admin),
+            _ => self.require_role(Role::RiskManager),
+        }
+    }
+
+    // Setters diretos só valem enquanto não há timelock configurado
+    fn set_parameter_directly(&self, param: ParamType, value: u64) {
+        self.require_parameter_role(param);
+        require!(
+            self.operation_timelock().get() == 0,
+            "Parâmetro sob timelock: use proposeParameterChange"
+        );
+        self.validate_parameter(param, value);
+        self.apply_parameter(param, value);
+    }
+
+    fn clear_pending_parameter_change(&self, param: ParamType) {
+        self.pending_parameter_changes(param).clear();
+        self.pending_parameter_types().swap_remove(&param);
+    }
+
+    fn validate_parameter(&self, param: ParamType, value: u64) {
+        match param {
+            ParamType::MaxScore => require!(value > 0, "Pontuação máxima deve ser maior que zero"),
+            ParamType::InterestRate | ParamType::MinInterestRate | ParamType::MaxInterestRate => {
+                require!(value <= 10000, "Taxa de juros não pode exceder 100%")
+            },
+            ParamType::CollateralRatio => require!(value <= 10000, "A razão de garantia não pode exceder 100%"),
+            ParamType::LiquidationPenalty => {
+                require!(value <= 10000, "A penalidade de liquidação não pode exceder 100%")
+            },
+            ParamType::LiquidationDiscount => {
+                require!(value <= 10000, "O desconto de liquidação não pode exceder 100%")
+            },
+            ParamType::LateFeeDailyRate | ParamType::ProgressiveLateFeeDailyRate => {
+                require!(value <= 10000, "A taxa diária de atraso não pode exceder 100%")
+            },
+            ParamType::ExtensionFeePercent => require!(value <= 10000, "A taxa de extensão não pode exceder 100%"),
+            ParamType::AuctionDuration => require!(value > 0, "Duração deve ser maior que zero"),
+            ParamType::MinInterestFloor => require!(value <= 10000, "O piso de juros não pode exceder 100%"),
+            ParamType::GuarantorStakeRatio => {
+                require!(value > 0 && value <= 10000, "A garantia dos fiadores deve estar entre 0,01% e 100%")
+            },
+            ParamType::ApplicationLifetime => require!(value > 0, "Prazo do pedido deve ser maior que zero"),
+            _ => {},
+        }
+    }
+
+    fn apply_parameter(&self, param: ParamType, value: u64) {
+        match param {
+            ParamType::MinScore => self.min_required_score().set(value),
+            ParamType::MaxScore => self.max_score().set(value),
+            ParamType::InterestRate => self.interest_rate_base().set(value),
+            ParamType::MinInterestRate => self.min_interest_rate().set(value),
+            ParamType::MaxInterestRate => self.max_interest_rate().set(value),
+            ParamType::ExtendedTermRateMultiplier => self.extended_term_rate_multiplier().set(value),
+            ParamType::MaxTermRateMultiplier => self.max_term_rate_multiplier().set(value),
+            ParamType::CollateralRatio => self.collateral_ratio().set(value),
+            ParamType::LiquidationThreshold => self.liquidation_threshold().set(value),
+            ParamType::LiquidationPenalty => self.liquidation_penalty().set(value),
+            ParamType::LiquidationDiscount => self.liquidation_discount().set(value),
+            ParamType::LateFeeDailyRate => self.late_fee_daily_rate().set(value),
+            ParamType::ProgressiveLateFeeThresholdDays => self.progressive_late_fee_threshold_days().set(value),
+            ParamType::ProgressiveLateFeeDailyRate => self.progressive_late_fee_daily_rate().set(value),
+            ParamType::ExtensionFeePercent => self.extension_fee_percent().set(value),
+            ParamType::MaxActiveLoans => self.max_active_loans().set(value),
+            ParamType::MaxLoansPerUser => self.max_loans_per_user().set(value),
+            ParamType::AuctionDuration => self.auction_duration().set(value),
+            ParamType::OperationTimelock => self.operation_timelock().set(value),
+            ParamType::GuarantorStakeRatio => self.guarantor_stake_ratio().set(value),
+            ParamType::MinInterestFloor => self.min_interest_floor().set(value),
+            ParamType::ApplicationLifetime => self.application_lifetime().set(value),
+            ParamType::MaxLoanTermDays => self.max_loan_term_days().set(value),
+        }
+    }
+
     #[view(getLoanDetails)]
     fn get_loan_details(&self, loan_id: u64) -> Option<Loan<Self::Api>> {
-        if loan_id == 0 || loan_id > self.loan_counter().get() {
+        if self.loans(loan_id).is_empty() {
             return None;
         }
-        
+
         Some(self.loans(loan_id).get())
     }
 
+    // Prévia de um pedido de empréstimo: taxa, valor a pagar, vencimento, garantia
+    // equivalente e o veredito de elegibilidade. Sem token vale o token padrão
+    // (requestLoan); com token, requestLoanInToken; com periodicidade,
+    // requestInstallmentLoan. No shard do ReputationScore usa a mesma decisão de
+    // crédito da originação; em outro shard, a última pontuação registrada, sem
+    // o limite da decisão, e a prévia não é vinculante
+    #[allow_multiple_var_args]
+    #[view(quoteLoan)]
+    fn quote_loan(
+        &self,
+        user: ManagedAddress,
+        amount: BigUint,
+        term: LoanTerm,
+        token_id: OptionalValue<EgldOrEsdtTokenIdentifier>,
+        installment_period: OptionalValue<InstallmentPeriod>,
+    ) -> LoanQuote<Self::Api> {
+        let rs_address = self.reputation_score_address().get();
+        let min_score = self.min_required_score().get();
+        let decision: Option<CreditDecision<Self::Api>> = if self.is_same_shard(&rs_address) {
+            Some(
+                self.reputation_score_proxy(rs_address)
+                    .get_credit_decision(user.clone(), amount.clone(), min_score, self.base_loan_amount().get())
+                    .execute_on_dest_context(),
+            )
+        } else {
+            None
+        };
+        let user_score = match &decision {
+            Some(decision) => decision.score,
+            None => self.user_reputation_scores(&user).get(),
+        };
+
+        // Token explícito segue a lista de aceitos; o padrão, só se configurado
+        let (token_id, token_accepted) = match token_id.into_option() {
+            Some(token_id) => {
+                let accepted = self.is_within_loan_token_limit(&token_id, &amount);
+                (token_id, accepted)
+            },
+            None => {
+                let token_id = self.default_loan_token();
+                let accepted = self.loan_token_config(&token_id).is_empty()
+                    || self.is_within_loan_token_limit(&token_id, &amount);
+                (token_id, accepted)
+            },
+        };
+        let installment_period = installment_period.into_option();
+        let (installment_count, period_valid) = match installment_period {
+            Some(period) => (
+                self.installment_count(term.get_days() * 24 * 60 * 60, period),
+                period.get_days() <= term.get_days(),
+            ),
+            None => (1u64, true),
+        };
+
+        let base_rate = self.calculate_token_interest_rate(&token_id, user_score);
+        let interest_rate = self.calculate_interest_rate_for_term(base_rate, term);
+        let repayment_amount = self.calculate_repayment_amount(&amount, interest_rate);
+
+        // Garantia em EGLD que requestLoanWithCollateral exigiria para o mesmo valor
+        let ratio_bp = self.collateral_ratio().get();
+        let required_collateral = if ratio_bp == 0 || !token_id.is_egld() {
+            BigUint::zero()
+        } else {
+            let by_ratio = (&amount * 10_000u64 + ratio_bp - 1u64) / ratio_bp;
+            core::cmp::max(by_ratio, self.min_collateral_amount().get())
+        };
+
+        let reason_code = if self.paused().get() {
+            ELIGIBILITY_PAUSED
+        } else if !token_accepted {
+            ELIGIBILITY_TOKEN_NOT_ACCEPTED
+        } else if !period_valid {
+            ELIGIBILITY_INVALID_INSTALLMENT_PERIOD
+        } else {
+            match (self.exposure_verdict(&user, &amount, user_score), &decision) {
+                (ELIGIBILITY_OK, Some(decision)) if !decision.eligible => ELIGIBILITY_SCORE_TOO_LOW,
+                (ELIGIBILITY_OK, Some(decision)) if !decision.within_limit => ELIGIBILITY_AMOUNT_ABOVE_LIMIT,
+                (ELIGIBILITY_OK, None) if user_score < min_score => ELIGIBILITY_SCORE_TOO_LOW,
+                (ELIGIBILITY_OK, None) => ELIGIBILITY_LIMIT_UNAVAILABLE,
+                (verdict, _) => verdict,
+            }
+        };
+
+        LoanQuote {
+            user_score,
+            interest_rate,
+            repayment_amount,
+            due_timestamp: self.calculate_due_date(term),
+            max_amount: match &decision {
+                Some(decision) => decision.max_amount.clone(),
+                None => BigUint::zero(),
+            },
+            required_collateral,
+            requires_underwriting: self.requires_underwriting(&amount),
+            eligible: reason_code == ELIGIBILITY_OK,
+            reason_code,
+            binding: decision.is_some(),
+            installment_count,
+        }
+    }
+
     #[view(calculateDueDate)]
     fn calculate_due_date(&self, term: LoanTerm) -> u64 {
-        let current_timestamp = self.blockchain().get_block_timestamp();
+        let current_timestamp = self.current_timestamp();
         let duration_seconds = term.get_days() * 24u64 * 60u64 * 60u64;
         current_timestamp + duration_seconds
     }
@@ -1144,163 +4381,396 @@ pub trait LoanController {
 
     /// Add these view methods inside the `#[multiversx_sc::contract] pub trait LoanController`:
 
-    /// Returns all loan IDs associated with a user
-    #[view(getUserLoanHistory)]
-    fn get_user_loan_history(&self, user: ManagedAddress) -> ManagedVec<Self::Api, u64> {
-        self.user_loans(user.clone())
-            .iter()
-            .collect()
+    /// Returns all loan IDs associated with a user
+    #[view(getUserLoanHistory)]
+    fn get_user_loan_history(&self, user: ManagedAddress) -> ManagedVec<Self::Api, u64> {
+        self.user_loans(user.clone())
+            .iter()
+            .collect()
+    }
+
+    /// Returns only the active loan IDs for a user
+    #[view(getUserActiveLoans)]
+    fn get_user_active_loans(&self, user: ManagedAddress) -> ManagedVec<Self::Api, u64> {
+        let mut active = ManagedVec::new();
+        for loan_id in self.user_loans(user.clone()).iter() {
+            if self.loans(loan_id).get().status == LoanStatus::Active {
+                active.push(loan_id);
+            }
+        }
+        active
+    }
+
+    /// Returns only the repaid loan IDs for a user
+    #[view(getUserRepaidLoans)]
+    fn get_user_repaid_loans(&self, user: ManagedAddress) -> ManagedVec<Self::Api, u64> {
+        let mut repaid = ManagedVec::new();
+        for loan_id in self.user_loans(user.clone()).iter() {
+            if self.loans(loan_id).get().status == LoanStatus::Repaid {
+                repaid.push(loan_id);
+            }
+        }
+        repaid
+    }
+
+    /// Lista global paginada: a partir de `from_id`, até `count` empréstimos
+    /// (no máximo MAX_LOANS_PAGE_SIZE), opcionalmente só os do status informado.
+    /// Examina no máximo MAX_LOANS_SCAN_PER_PAGE ids por chamada e devolve o
+    /// `from_id` da próxima página, ou None quando não há mais ids
+    #[view(getLoans)]
+    fn get_loans(
+        &self,
+        from_id: u64,
+        count: u64,
+        status_filter: OptionalValue<LoanStatus>,
+    ) -> MultiValue2<Option<u64>, MultiValueEncoded<LoanView<Self::Api>>> {
+        let status_filter = status_filter.into_option();
+        let limit = count.min(MAX_LOANS_PAGE_SIZE);
+        let mut result = MultiValueEncoded::new();
+        let mut found = 0u64;
+
+        // ids começam em 1; o id 0 só existe em empréstimos antigos
+        let last_id = self.loan_counter().get();
+        let scan_end = from_id.saturating_add(MAX_LOANS_SCAN_PER_PAGE);
+        let mut loan_id = from_id;
+        while found < limit && loan_id <= last_id && loan_id < scan_end {
+            if !self.loans(loan_id).is_empty() {
+                let loan = self.loans(loan_id).get();
+                if status_filter.as_ref().is_none_or(|status| &loan.status == status) {
+                    result.push(self.loan_view(loan_id, loan));
+                    found += 1;
+                }
+            }
+            loan_id += 1;
+        }
+
+        let next_id = if loan_id <= last_id { Some(loan_id) } else { None };
+        (next_id, result).into()
+    }
+
+    /// Empréstimos de um usuário, na ordem de criação, a partir da posição `offset`
+    #[view(getUserLoansDetailed)]
+    fn get_user_loans_detailed(
+        &self,
+        user: ManagedAddress,
+        offset: u64,
+        limit: u64,
+    ) -> MultiValueEncoded<LoanView<Self::Api>> {
+        let user_loans = self.user_loans(user);
+        let total = user_loans.len() as u64;
+        let end = total.min(offset.saturating_add(limit.min(MAX_LOANS_PAGE_SIZE)));
+
+        let mut result = MultiValueEncoded::new();
+        for index in offset..end {
+            // VecMapper começa no índice 1
+            let loan_id = user_loans.get(index as usize + 1);
+            result.push(self.loan_view(loan_id, self.loans(loan_id).get()));
+        }
+        result
+    }
+
+    #[view(getUserLoanCount)]
+    fn get_user_loan_count(&self, user: ManagedAddress) -> u64 {
+        self.user_loans(user).len() as u64
+    }
+
+    fn loan_view(&self, loan_id: u64, loan: Loan<Self::Api>) -> LoanView<Self::Api> {
+        LoanView {
+            loan_id,
+            token_id: self.loan_payment_token(loan_id),
+            accrued_interest: self.accrued_interest_at(loan_id, &loan, self.current_timestamp()),
+            late_fee_due: self.get_late_fee_due(loan_id),
+            amount_due: self.get_amount_due(loan_id),
+            collateral: self.loan_collateral(loan_id).get(),
+            loan,
+        }
+    }
+
+    
+
+
+    //================================================
+
+    // Consulta a decisão de crédito no ReputationScore em uma única chamada:
+    // síncrona quando o contrato está no mesmo shard, assíncrona caso contrário.
+    // guarantee_request_id identifica o pedido com fiadores (zero = sem fiadores)
+    fn request_credit_decision(
+        &self,
+        caller: ManagedAddress,
+        amount: BigUint,
+        token_id: EgldOrEsdtTokenIdentifier,
+        term: LoanTerm,
+        installment_period: Option<InstallmentPeriod>,
+        guarantee_request_id: u64,
+    ) {
+        let rs_address = self.reputation_score_address().get();
+        let min_score = self.min_required_score().get();
+        let base_amount = self.base_loan_amount().get();
+
+        if self.is_same_shard(&rs_address) {
+            let decision: CreditDecision<Self::Api> = self
+                .reputation_score_proxy(rs_address)
+                .get_credit_decision(caller.clone(), amount.clone(), min_score, base_amount)
+                .execute_on_dest_context();
+            self.apply_credit_decision(caller, amount, token_id, term, installment_period, guarantee_request_id, decision);
+            return;
+        }
+
+        self.reputation_score_proxy(rs_address)
+            .get_credit_decision(caller.clone(), amount.clone(), min_score, base_amount)
+            .with_callback(self.callbacks().credit_decision_callback(
+                caller,
+                amount,
+                token_id,
+                term,
+                installment_period,
+                guarantee_request_id,
+            ))
+            .call_and_exit();
+    }
+
+    // Contratos no mesmo shard podem ser consultados de forma síncrona
+    fn is_same_shard(&self, address: &ManagedAddress) -> bool {
+        let own_shard = self.blockchain().get_shard_of_address(&self.blockchain().get_sc_address());
+        self.blockchain().get_shard_of_address(address) == own_shard
+    }
+
+    fn apply_credit_decision(
+        &self,
+        caller: ManagedAddress,
+        amount: BigUint,
+        token_id: EgldOrEsdtTokenIdentifier,
+        term: LoanTerm,
+        installment_period: Option<InstallmentPeriod>,
+        guarantee_request_id: u64,
+        decision: CreditDecision<Self::Api>,
+    ) {
+        require!(decision.eligible, "Pontuação do usuário muito baixa para empréstimo");
+        require!(decision.within_limit, "Valor solicitado excede o limite permitido");
+        // A decisão traz a pontuação atual: mantém o registro local em dia
+        self.user_reputation_scores(&caller).set(decision.score);
+        self.open_scored_loan(caller, amount, token_id, term, installment_period, guarantee_request_id, decision.score);
     }
 
-    /// Returns only the active loan IDs for a user
-    #[view(getUserActiveLoans)]
-    fn get_user_active_loans(&self, user: ManagedAddress) -> ManagedVec<Self::Api, u64> {
-        let mut active = ManagedVec::new();
-        for loan_id in self.user_loans(user.clone()).iter() {
-            if self.loans(loan_id).get().status == LoanStatus::Active {
-                active.push(loan_id);
-            }
+    // Abre o empréstimo com a pontuação já validada: fila de underwriting,
+    // saldo do contrato ou fundos do pool de liquidez
+    fn open_scored_loan(
+        &self,
+        caller: ManagedAddress,
+        amount: BigUint,
+        token_id: EgldOrEsdtTokenIdentifier,
+        term: LoanTerm,
+        installment_period: Option<InstallmentPeriod>,
+        guarantee_request_id: u64,
+        user_score: u64,
+    ) {
+        // Limites de exposição com a pontuação atual, antes de qualquer desembolso
+        self.require_within_exposure(&caller, &amount, user_score);
+        if guarantee_request_id > 0 {
+            self.require_guarantee_request_ready(&caller, guarantee_request_id);
         }
-        active
-    }
 
-    /// Returns only the repaid loan IDs for a user
-    #[view(getUserRepaidLoans)]
-    fn get_user_repaid_loans(&self, user: ManagedAddress) -> ManagedVec<Self::Api, u64> {
-        let mut repaid = ManagedVec::new();
-        for loan_id in self.user_loans(user.clone()).iter() {
-            if self.loans(loan_id).get().status == LoanStatus::Repaid {
-                repaid.push(loan_id);
-            }
+        // Tokens configurados seguem a lista de aceitos e o limite por token
+        // (o EGLD sem configuração segue só os limites globais)
+        if !self.loan_token_config(&token_id).is_empty() {
+            self.require_loan_token_limit(&token_id, &amount);
         }
-        repaid
-    }
 
-    
+        let base_rate = self.calculate_token_interest_rate(&token_id, user_score);
+        let term_adjusted_rate = self.calculate_interest_rate_for_term(base_rate, term);
+
+        // Valores acima do limite automático aguardam um underwriter,
+        // com ou sem fiadores
+        if self.requires_underwriting(&amount) {
+            self.create_loan_application(
+                &caller,
+                &amount,
+                &token_id,
+                term,
+                installment_period,
+                guarantee_request_id,
+                term_adjusted_rate,
+                user_score,
+            );
+            return;
+        }
+
+        // Tokens sem pool são emprestados do saldo emprestável do contrato
+        let pool_address = self.loan_token_pool(&token_id);
+        if pool_address.is_zero() {
+            self.take_lendable_funds(&token_id, &amount);
+            self.register_loan(&caller, &amount, &token_id, term_adjusted_rate, term, installment_period, guarantee_request_id);
+            self.send().direct(&caller, &token_id, 0, &amount);
+            return;
+        }
 
+        // O principal sai do pool de liquidez; o empréstimo só é
+        // registrado quando o pool confirma o envio dos fundos.
+        // Até lá as garantias dos fiadores ficam travadas
+        let token_id = token_id.unwrap_esdt();
+        if guarantee_request_id > 0 {
+            self.guarantee_request_opening(guarantee_request_id).set(true);
+        }
 
-    //================================================
+        self.liquidity_pool_proxy(pool_address.clone())
+            .provide_funds_for_loan(amount.clone(), token_id.clone())
+            .with_callback(self.callbacks().loan_funding_callback(
+                caller,
+                amount,
+                token_id,
+                term_adjusted_rate,
+                term,
+                installment_period,
+                guarantee_request_id,
+                pool_address,
+            ))
+            .call_and_exit();
+    }
 
     // Callbacks para processamento assíncrono
+
+    // Retorno de getCreditDecision quando o ReputationScore está em outro shard
     #[callback]
-    fn check_eligibility_callback(
+    fn credit_decision_callback(
         &self,
-        #[call_result] result: ManagedAsyncCallResult<bool>,
+        #[call_result] result: ManagedAsyncCallResult<CreditDecision<Self::Api>>,
         caller: ManagedAddress,
         amount: BigUint,
+        token_id: EgldOrEsdtTokenIdentifier,
         term: LoanTerm,
+        installment_period: Option<InstallmentPeriod>,
+        guarantee_request_id: u64,
     ) {
         match result {
-            ManagedAsyncCallResult::Ok(is_eligible) => {
-                if !is_eligible {
-                    require!(false, "Pontuação do usuário muito baixa para empréstimo");
-                    return;
-                }
-                
-                // Continua com a verificação do valor máximo
-                let rs_address = self.reputation_score_address().get();
-                let base_amount = self.base_loan_amount().get();
-                
-                self.reputation_score_proxy(rs_address.clone())
-                    .calculate_max_loan_amount(caller.clone(), base_amount)
-                    .with_callback(self.callbacks().check_amount_callback(
-                        caller,
-                        amount,
-                        term
-                    ))
-                    .call_and_exit();
+            ManagedAsyncCallResult::Ok(decision) => {
+                self.apply_credit_decision(caller, amount, token_id, term, installment_period, guarantee_request_id, decision);
             },
             ManagedAsyncCallResult::Err(_) => {
-                require!(false, "Erro ao verificar elegibilidade do usuário");
+                require!(false, "Erro ao obter decisão de crédito do usuário");
             }
         }
     }
-    
+
     #[callback]
-    fn check_amount_callback(
+    fn loan_funding_callback(
         &self,
-        #[call_result] result: ManagedAsyncCallResult<BigUint>,
+        #[call_result] result: ManagedAsyncCallResult<()>,
         caller: ManagedAddress,
         amount: BigUint,
+        token_id: TokenIdentifier,
+        term_adjusted_rate: u64,
         term: LoanTerm,
+        installment_period: Option<InstallmentPeriod>,
+        guarantee_request_id: u64,
+        pool_address: ManagedAddress,
     ) {
         match result {
-            ManagedAsyncCallResult::Ok(max_amount) => {
-                if amount > max_amount {
-                    require!(false, "Valor solicitado excede o limite permitido");
+            ManagedAsyncCallResult::Ok(()) => {
+                let loan_id = self.register_loan(
+                    &caller,
+                    &amount,
+                    &EgldOrEsdtTokenIdentifier::esdt(token_id.clone()),
+                    term_adjusted_rate,
+                    term,
+                    installment_period,
+                    guarantee_request_id,
+                );
+                self.set_loan_funding_pool(loan_id, &token_id, &pool_address);
+
+                // Repassa ao tomador os fundos recebidos do pool
+                self.send().direct_esdt(&caller, &token_id, 0, &amount);
+            },
+            ManagedAsyncCallResult::Err(_) => {
+                // Sem os fundos o empréstimo não abre; as garantias voltam a ficar livres
+                if guarantee_request_id > 0 {
+                    self.guarantee_request_opening(guarantee_request_id).clear();
+                    self.guaranteed_loan_open_failed_event(guarantee_request_id);
                     return;
                 }
-                
-                // Continua com a obtenção da pontuação do usuário
-                let rs_address = self.reputation_score_address().get();
-                
-                self.reputation_score_proxy(rs_address)
-                    .get_user_score(caller.clone())
-                    .with_callback(self.callbacks().process_loan_callback(
-                        caller,
-                        amount,
-                        term
-                    ))
-                    .call_and_exit();
+                require!(false, "Erro ao obter fundos do pool de liquidez");
+            }
+        }
+    }
+
+    #[callback]
+    fn application_funding_callback(
+        &self,
+        #[call_result] result: ManagedAsyncCallResult<()>,
+        application_id: u64,
+        pool_address: ManagedAddress,
+    ) {
+        match result {
+            ManagedAsyncCallResult::Ok(()) => {
+                let loan_id = self.disburse_loan_application(application_id);
+                let token_id = self.loan_applications(application_id).get().token_id.unwrap_esdt();
+                self.set_loan_funding_pool(loan_id, &token_id, &pool_address);
+            },
+            ManagedAsyncCallResult::Err(_) => {
+                // O pool não liberou os fundos: o pedido volta para a fila
+                let mut application = self.loan_applications(application_id).get();
+                application.status = ApplicationStatus::Pending;
+                self.loan_applications(application_id).set(&application);
+                self.pending_applications().insert(application_id);
+            }
+        }
+    }
+
+    #[callback]
+    fn credit_limit_callback(
+        &self,
+        #[call_result] result: ManagedAsyncCallResult<BigUint>,
+        borrower: ManagedAddress,
+        token_id: OptionalValue<EgldOrEsdtTokenIdentifier>,
+    ) {
+        match result {
+            ManagedAsyncCallResult::Ok(limit) => {
+                match token_id.into_option() {
+                    Some(token_id) => {
+                        require!(self.credit_lines(&borrower).is_empty(), "Linha de crédito já existe");
+                        require!(limit > 0u64, "Pontuação insuficiente para linha de crédito");
+                        let line = CreditLine {
+                            interest_rate: self.credit_line_rate(&token_id),
+                            token_id,
+                            limit: limit.clone(),
+                            principal: BigUint::zero(),
+                            accrued_interest: BigUint::zero(),
+                            last_accrual_timestamp: self.current_timestamp(),
+                        };
+                        self.credit_lines(&borrower).set(&line);
+                        self.credit_line_paid_through(&borrower).set(line.last_accrual_timestamp);
+                        self.credit_line_opened_event(&borrower, &line.token_id, &limit);
+                    },
+                    None => {
+                        // A linha pode ter sido encerrada enquanto o limite era consultado
+                        if self.credit_lines(&borrower).is_empty() {
+                            return;
+                        }
+                        let mut line = self.accrued_credit_line(&borrower);
+                        line.limit = limit.clone();
+                        self.credit_lines(&borrower).set(&line);
+                        self.credit_limit_updated_event(&borrower, &limit);
+                    },
+                }
             },
             ManagedAsyncCallResult::Err(_) => {
                 require!(false, "Erro ao calcular valor máximo do empréstimo");
             }
         }
     }
-    
+
     #[callback]
-    fn process_loan_callback(
+    fn refinance_callback(
         &self,
         #[call_result] result: ManagedAsyncCallResult<u64>,
         caller: ManagedAddress,
-        amount: BigUint,
-        term: LoanTerm,
+        loan_ids: ManagedVec<u64>,
+        new_term: LoanTerm,
     ) {
         match result {
             ManagedAsyncCallResult::Ok(user_score) => {
-                let base_rate = self.calculate_interest_rate(user_score);
-                let term_adjusted_rate = self.calculate_interest_rate_for_term(base_rate, term);
-                
-                // Calcula o valor total a ser pago
-                let interest_amount = &amount * &BigUint::from(term_adjusted_rate) / &BigUint::from(10000u32);
-                let repayment_amount = &amount + &interest_amount;
-
-                // faça duas cópias: uma para o struct, outra para o contador
-                let repayment_for_total = repayment_amount.clone();
-
-                // 2) atualiza o total usando um clone
-                self.total_repayment_amount()
-                    .update(|current| *current += repayment_amount.clone());
-
-                let loan_id = self.loan_counter().get();
-                self.loan_counter().set(loan_id + 1);
-                
-                let current_timestamp = self.blockchain().get_block_timestamp();
-                let due_timestamp = self.calculate_due_date(term);
-                
-                // 3) grava o Loan (aqui sim o repayment_amount é movido para dentro do struct)
-                self.loans(loan_id).set(Loan {
-                    borrower: caller.clone(),
-                    amount: amount.clone(),
-                    repayment_amount,
-                    interest_rate: term_adjusted_rate,
-                    creation_timestamp: current_timestamp,
-                    due_timestamp,
-                    status: LoanStatus::Active,
-                });
-                
-                self.user_loans(caller.clone()).push(&loan_id);
-                // após gravar o loan
-                self.total_loan_amount().update(|current| *current += amount.clone());
-
-                // agora use a segunda cópia para incrementar o acumulador
-                self.total_repayment_amount()
-                .update(|current| *current += repayment_for_total);
-                
-                // Transfere os fundos
-                let token_id = self.call_value().egld_or_single_esdt().token_identifier;
-                self.send().direct(&caller, &token_id, 0, &amount);
+                // Os empréstimos podem ter mudado enquanto a pontuação era consultada
+                self.require_refinanceable_loans(&caller, &loan_ids);
+                self.consolidate_loans(&caller, &loan_ids, new_term, user_score);
             },
             ManagedAsyncCallResult::Err(_) => {
                 require!(false, "Erro ao obter pontuação do usuário");
@@ -1308,10 +4778,127 @@ pub trait LoanController {
         }
     }
 
+    // Registra um empréstimo já financiado, no token em que foi concedido,
+    // com o parcelamento pedido e os fiadores do pedido (zero = sem fiadores)
+    fn register_loan(
+        &self,
+        caller: &ManagedAddress,
+        amount: &BigUint,
+        token_id: &EgldOrEsdtTokenIdentifier,
+        term_adjusted_rate: u64,
+        term: LoanTerm,
+        installment_period: Option<InstallmentPeriod>,
+        guarantee_request_id: u64,
+    ) -> u64 {
+        if guarantee_request_id > 0 {
+            self.guarantee_request_opening(guarantee_request_id).clear();
+        }
+        self.record_loan(caller, amount, token_id, term_adjusted_rate, term, installment_period, guarantee_request_id)
+    }
+
+    fn record_loan(
+        &self,
+        caller: &ManagedAddress,
+        amount: &BigUint,
+        token_id: &EgldOrEsdtTokenIdentifier,
+        term_adjusted_rate: u64,
+        term: LoanTerm,
+        installment_period: Option<InstallmentPeriod>,
+        guarantee_request_id: u64,
+    ) -> u64 {
+        // Calcula o valor total a ser pago
+        let repayment_amount = self.calculate_repayment_amount(amount, term_adjusted_rate);
+
+        // faça duas cópias: uma para o struct, outra para o contador
+        let repayment_for_total = repayment_amount.clone();
+
+        // 2) atualiza o total usando um clone
+        self.total_repayment_amount()
+            .update(|current| *current += repayment_amount.clone());
+
+        // 3) grava o Loan (aqui sim o repayment_amount é movido para dentro do struct)
+        let loan_id = self.store_new_loan(caller, amount, repayment_amount, token_id, term_adjusted_rate, term);
+
+        // após gravar o loan
+        self.total_loan_amount().update(|current| *current += amount.clone());
+
+        // agora use a segunda cópia para incrementar o acumulador
+        self.total_repayment_amount()
+        .update(|current| *current += repayment_for_total);
+
+        // Aplica o plano de parcelamento pedido em requestInstallmentLoan
+        if let Some(period) = installment_period {
+            self.create_installment_schedule(loan_id, period);
+        }
+
+        // Vincula as garantias dos fiadores pedidas em openGuaranteedLoan
+        if guarantee_request_id > 0 {
+            self.attach_guarantee_request(loan_id, guarantee_request_id);
+        }
+
+        self.on_loan_created(loan_id);
+
+        loan_id
+    }
+
+    // Principal mais os juros do prazo (taxa em pontos base)
+    fn calculate_repayment_amount(&self, amount: &BigUint, interest_rate: u64) -> BigUint {
+        let interest_amount = amount * &BigUint::from(interest_rate) / &BigUint::from(10000u32);
+        amount + &interest_amount
+    }
+
+    // Grava um empréstimo Active com o próximo id e o associa ao tomador
+    fn store_new_loan(
+        &self,
+        borrower: &ManagedAddress,
+        amount: &BigUint,
+        repayment_amount: BigUint,
+        token_id: &EgldOrEsdtTokenIdentifier,
+        interest_rate: u64,
+        term: LoanTerm,
+    ) -> u64 {
+        let loan_id = self.next_loan_id();
+
+        self.loans(loan_id).set(Loan {
+            borrower: borrower.clone(),
+            amount: amount.clone(),
+            repayment_amount,
+            interest_rate,
+            creation_timestamp: self.current_timestamp(),
+            due_timestamp: self.calculate_due_date(term),
+            status: LoanStatus::Active,
+        });
+        self.loan_token(loan_id).set(token_id);
+        self.user_loans(borrower.clone()).push(&loan_id);
+
+        loan_id
+    }
+
+    // Reserva o próximo id de empréstimo. Todos os caminhos de criação usam
+    // este contador, com ids a partir de 1; o id 0 só existe em empréstimos
+    // gravados antes da unificação
+    fn next_loan_id(&self) -> u64 {
+        let loan_id = self.loan_counter().get() + 1;
+        self.loan_counter().set(loan_id);
+        loan_id
+    }
+
+    // Taxa de juros pela pontuação, partindo da taxa base do token
+    fn calculate_token_interest_rate(&self, token_id: &EgldOrEsdtTokenIdentifier, user_score: u64) -> u64 {
+        if self.loan_token_config(token_id).is_empty() {
+            return self.calculate_interest_rate(user_score);
+        }
+        let base_rate = self.loan_token_config(token_id).get().interest_rate_base;
+        self.interest_rate_from_score(base_rate, user_score)
+    }
+
     // Calcula a taxa de juros com base na pontuação do usuário
     fn calculate_interest_rate(&self, user_score: u64) -> u64 {
-        let base_rate = self.interest_rate_base().get();
-        let max_score = 1000u64;
+        self.interest_rate_from_score(self.interest_rate_base().get(), user_score)
+    }
+
+    fn interest_rate_from_score(&self, base_rate: u64, user_score: u64) -> u64 {
+        let max_score = self.max_score_or_default();
 
         let score_factor = (user_score * 80) / max_score;
         if score_factor >= 100 {
@@ -1320,14 +4907,296 @@ pub trait LoanController {
         base_rate * (100 - score_factor) / 100
     }
 
+    fn max_score_or_default(&self) -> u64 {
+        if self.max_score().get() == 0 {
+            DEFAULT_MAX_SCORE
+        } else {
+            self.max_score().get()
+        }
+    }
+
+    // Eventos
+    #[event("loan_application_created")]
+    fn loan_application_created_event(
+        &self,
+        #[indexed] application_id: u64,
+        #[indexed] borrower: &ManagedAddress,
+        #[indexed] expires_at: u64,
+        amount: &BigUint,
+    );
+
+    #[event("loan_application_approved")]
+    fn loan_application_approved_event(
+        &self,
+        #[indexed] application_id: u64,
+        #[indexed] underwriter: &ManagedAddress,
+        #[indexed] interest_rate: u64,
+        amount: &BigUint,
+    );
+
+    #[event("loan_application_rejected")]
+    fn loan_application_rejected_event(
+        &self,
+        #[indexed] application_id: u64,
+        #[indexed] underwriter: &ManagedAddress,
+        #[indexed] reason_code: u32,
+    );
+
+    #[event("loan_application_expired")]
+    fn loan_application_expired_event(&self, #[indexed] application_id: u64);
+
+    #[event("exposure_tier_set")]
+    fn exposure_tier_set_event(
+        &self,
+        #[indexed] min_score: u64,
+        #[indexed] max_active_loans: u64,
+        max_outstanding_principal: &BigUint,
+    );
+
+    #[event("exposure_tier_removed")]
+    fn exposure_tier_removed_event(&self, #[indexed] min_score: u64);
+
+    // Ciclo de vida dos empréstimos (esquema documentado em docs/technical.md)
+    #[event("loan_created")]
+    fn loan_created_event(
+        &self,
+        #[indexed] loan_id: u64,
+        #[indexed] borrower: &ManagedAddress,
+        #[indexed] amount: &BigUint,
+        #[indexed] repayment_amount: &BigUint,
+        #[indexed] interest_rate: u64,
+        #[indexed] due_timestamp: u64,
+        timestamp: u64,
+    );
+
+    #[event("loan_status_changed")]
+    fn loan_status_changed_event(
+        &self,
+        #[indexed] loan_id: u64,
+        #[indexed] borrower: &ManagedAddress,
+        #[indexed] old_status: &LoanStatus,
+        #[indexed] new_status: &LoanStatus,
+        timestamp: u64,
+    );
+
+    #[event("loan_repaid")]
+    fn loan_repaid_event(
+        &self,
+        #[indexed] loan_id: u64,
+        #[indexed] borrower: &ManagedAddress,
+        #[indexed] amount: &BigUint,
+        #[indexed] late_fee: &BigUint,
+        timestamp: u64,
+    );
+
+    #[event("loan_partially_repaid")]
+    fn loan_partially_repaid_event(
+        &self,
+        #[indexed] loan_id: u64,
+        #[indexed] borrower: &ManagedAddress,
+        #[indexed] amount: &BigUint,
+        #[indexed] remaining: &BigUint,
+        timestamp: u64,
+    );
+
+    #[event("loan_extended")]
+    fn loan_extended_event(
+        &self,
+        #[indexed] loan_id: u64,
+        #[indexed] borrower: &ManagedAddress,
+        #[indexed] old_due_timestamp: u64,
+        #[indexed] new_due_timestamp: u64,
+        #[indexed] fee: &BigUint,
+        timestamp: u64,
+    );
+
+    #[event("collateral_deposited")]
+    fn collateral_deposited_event(
+        &self,
+        #[indexed] loan_id: u64,
+        #[indexed] borrower: &ManagedAddress,
+        #[indexed] token_id: &EgldOrEsdtTokenIdentifier,
+        #[indexed] amount: &BigUint,
+        #[indexed] total_collateral: &BigUint,
+        timestamp: u64,
+    );
+
+    #[event("collateral_withdrawn")]
+    fn collateral_withdrawn_event(
+        &self,
+        #[indexed] loan_id: u64,
+        #[indexed] borrower: &ManagedAddress,
+        #[indexed] token_id: &EgldOrEsdtTokenIdentifier,
+        #[indexed] amount: &BigUint,
+        timestamp: u64,
+    );
+
+    #[event("collateral_forfeited")]
+    fn collateral_forfeited_event(
+        &self,
+        #[indexed] loan_id: u64,
+        #[indexed] borrower: &ManagedAddress,
+        #[indexed] token_id: &EgldOrEsdtTokenIdentifier,
+        #[indexed] amount: &BigUint,
+        timestamp: u64,
+    );
+
+    #[event("guaranteed_loan_requested")]
+    fn guaranteed_loan_requested_event(
+        &self,
+        #[indexed] request_id: u64,
+        #[indexed] borrower: &ManagedAddress,
+        #[indexed] amount: &BigUint,
+        required_stake: &BigUint,
+    );
+
+    #[event("guarantee_pledged")]
+    fn guarantee_pledged_event(&self, #[indexed] request_id: u64, #[indexed] guarantor: &ManagedAddress, amount: &BigUint);
+
+    #[event("guarantee_released")]
+    fn guarantee_released_event(&self, #[indexed] request_id: u64, #[indexed] guarantor: &ManagedAddress, amount: &BigUint);
+
+    #[event("guarantee_seized")]
+    fn guarantee_seized_event(&self, #[indexed] request_id: u64, #[indexed] guarantor: &ManagedAddress, amount: &BigUint);
+
+    #[event("guaranteed_loan_opened")]
+    fn guaranteed_loan_opened_event(&self, #[indexed] request_id: u64, #[indexed] loan_id: u64, pledged_stake: &BigUint);
+
+    #[event("guaranteed_loan_cancelled")]
+    fn guaranteed_loan_cancelled_event(&self, #[indexed] request_id: u64);
+
+    #[event("guaranteed_loan_open_failed")]
+    fn guaranteed_loan_open_failed_event(&self, #[indexed] request_id: u64);
+
+    #[event("credit_line_opened")]
+    fn credit_line_opened_event(
+        &self,
+        #[indexed] borrower: &ManagedAddress,
+        #[indexed] token_id: &EgldOrEsdtTokenIdentifier,
+        limit: &BigUint,
+    );
+
+    #[event("credit_limit_updated")]
+    fn credit_limit_updated_event(&self, #[indexed] borrower: &ManagedAddress, limit: &BigUint);
+
+    #[event("credit_drawn")]
+    fn credit_drawn_event(&self, #[indexed] borrower: &ManagedAddress, #[indexed] amount: &BigUint, principal: &BigUint);
+
+    #[event("credit_repaid")]
+    fn credit_repaid_event(
+        &self,
+        #[indexed] borrower: &ManagedAddress,
+        #[indexed] principal_paid: &BigUint,
+        interest_paid: &BigUint,
+    );
+
+    #[event("credit_line_closed")]
+    fn credit_line_closed_event(&self, #[indexed] borrower: &ManagedAddress);
+
+    #[event("credit_line_defaulted")]
+    fn credit_line_defaulted_event(
+        &self,
+        #[indexed] borrower: &ManagedAddress,
+        #[indexed] principal: &BigUint,
+        accrued_interest: &BigUint,
+    );
+
+    #[event("loans_refinanced")]
+    fn loans_refinanced_event(
+        &self,
+        #[indexed] new_loan_id: u64,
+        #[indexed] borrower: &ManagedAddress,
+        #[indexed] old_loan_ids: &ManagedVec<u64>,
+        #[indexed] principal: &BigUint,
+        interest_rate: u64,
+    );
+
+    #[event("profits_claimed")]
+    fn profits_claimed_event(&self, #[indexed] investor: &ManagedAddress, amount: &BigUint);
+
+    #[event("loan_defaulted")]
+    fn loan_defaulted_event(
+        &self,
+        #[indexed] loan_id: u64,
+        #[indexed] borrower: &ManagedAddress,
+        due_timestamp: u64,
+    );
+
+    #[event("auction_started")]
+    fn auction_started_event(
+        &self,
+        #[indexed] loan_id: u64,
+        #[indexed] auction_type: AuctionType,
+        #[indexed] start_price: &BigUint,
+        #[indexed] floor_price: &BigUint,
+        end_timestamp: u64,
+    );
+
+    #[event("auction_bid")]
+    fn auction_bid_event(
+        &self,
+        #[indexed] loan_id: u64,
+        #[indexed] bidder: &ManagedAddress,
+        amount: &BigUint,
+    );
+
+    #[event("auction_settled")]
+    fn auction_settled_event(
+        &self,
+        #[indexed] loan_id: u64,
+        #[indexed] winner: &ManagedAddress,
+        #[indexed] proceeds: &BigUint,
+        #[indexed] surplus: &BigUint,
+        shortfall: &BigUint,
+    );
+
+    #[event("auction_cancelled")]
+    fn auction_cancelled_event(&self, #[indexed] loan_id: u64);
+
+    #[event("parameter_change_proposed")]
+    fn parameter_change_proposed_event(
+        &self,
+        #[indexed] param: ParamType,
+        #[indexed] value: u64,
+        executable_at: u64,
+    );
+
+    #[event("parameter_change_executed")]
+    fn parameter_change_executed_event(&self, #[indexed] param: ParamType, value: u64);
+
+    #[event("parameter_change_cancelled")]
+    fn parameter_change_cancelled_event(&self, #[indexed] param: ParamType);
+
+    #[event("config_change_proposed")]
+    fn config_change_proposed_event(&self, #[indexed] change_id: u64, executable_at: u64);
+
+    #[event("config_change_executed")]
+    fn config_change_executed_event(&self, #[indexed] change_id: u64);
+
+    #[event("config_change_cancelled")]
+    fn config_change_cancelled_event(&self, #[indexed] change_id: u64);
+
+    #[event("loan_liquidated")]
+    fn loan_liquidated_event(
+        &self,
+        #[indexed] loan_id: u64,
+        #[indexed] liquidator: &ManagedAddress,
+        health_factor: &BigUint,
+    );
+
+    #[event("overdue_sweep")]
+    fn overdue_sweep_event(
+        &self,
+        #[indexed] keeper: &ManagedAddress,
+        #[indexed] defaulted_count: u64,
+        bounty: &BigUint,
+    );
+
     // Storage mappers
     #[storage_mapper("reputation_score_address")]
     fn reputation_score_address(&self) -> SingleValueMapper<ManagedAddress>;
     #[storage_mapper("min_required_score")]
     fn min_required_score(&self) -> SingleValueMapper<u64>;
-    
-    //#[storage_mapper("interest_rate_base")]
-    //fn interest_rate_base(&self) -> SingleValueMapper<u64>;
 
     #[storage_mapper("base_loan_amount")]
     fn base_loan_amount(&self) -> SingleValueMapper<BigUint>;
@@ -1340,6 +5209,15 @@ pub trait LoanController {
     #[storage_mapper("on_time_payments")]
     fn on_time_payments(&self, user: ManagedAddress) -> SingleValueMapper<u64>;
 
+    // Exposição do tomador: empréstimos ativos e principal ainda não devolvido
+    #[storage_mapper("borrower_active_loans")]
+    fn borrower_active_loans(&self, borrower: &ManagedAddress) -> SingleValueMapper<u64>;
+    #[storage_mapper("borrower_outstanding_principal")]
+    fn borrower_outstanding_principal(&self, borrower: &ManagedAddress) -> SingleValueMapper<BigUint>;
+    // Contadores já inicializados a partir dos empréstimos existentes
+    #[storage_mapper("borrower_exposure_synced")]
+    fn borrower_exposure_synced(&self, borrower: &ManagedAddress) -> SingleValueMapper<bool>;
+
     //=====================================================================
     // Adicione o mapper de armazenamento para a taxa de extensão
     #[storage_mapper("extension_fee_percent")]
@@ -1363,6 +5241,7 @@ pub trait LoanController {
     #[storage_mapper("total_investor_shares")]
     fn total_investor_shares(&self) -> SingleValueMapper<u64>;
 
+    // Chaves legadas dos prazos: lidas apenas pela migração v1 (ver upgrade)
     #[storage_mapper("standard_loan_term_days")]
     fn standard_loan_term_days(&self) -> SingleValueMapper<u64>;
     
@@ -1409,9 +5288,25 @@ pub trait LoanController {
     #[storage_mapper("pending_parameter_changes")]
     fn pending_parameter_changes(&self, param_type: ParamType) -> SingleValueMapper<ParameterChange>;
 
-    #[storage_mapper("pending_parameter_changes_by_key")]
-    fn pending_parameter_changes_by_key(&self, param_key: BigUint) -> SingleValueMapper<ParameterChange>;
-    
+    // Layout anterior à v2: (valor, proposta em), sem executable_at
+    #[storage_mapper("pending_parameter_changes")]
+    fn legacy_pending_parameter_changes(&self, param_type: ParamType) -> SingleValueMapper<(u64, u64)>;
+
+    // Parâmetros com alteração pendente (para a view de pendências)
+    #[storage_mapper("pending_parameter_types")]
+    fn pending_parameter_types(&self) -> UnorderedSetMapper<ParamType>;
+
+    #[storage_mapper("config_change_counter")]
+    fn config_change_counter(&self) -> SingleValueMapper<u64>;
+
+    // Alterações de configuração propostas, por id
+    #[storage_mapper("pending_config_changes")]
+    fn pending_config_changes(&self) -> MapMapper<u64, PendingConfigChange<Self::Api>>;
+
+    // Escala de pontuação usada no cálculo de juros (zero = DEFAULT_MAX_SCORE)
+    #[storage_mapper("max_score")]
+    fn max_score(&self) -> SingleValueMapper<u64>;
+
     #[storage_mapper("standard_term_days")]
     fn standard_term_days(&self) -> SingleValueMapper<u64>;
 
@@ -1421,17 +5316,9 @@ pub trait LoanController {
     #[storage_mapper("short_term_days")]
     fn short_term_days(&self) -> SingleValueMapper<u64>;
 
-    // Add a storage mapper for the destruction timelock
-    #[storage_mapper("destruction_timelock")]
-    fn destruction_timelock(&self) -> SingleValueMapper<u64>;
-
-    // Renomear de destruction_pending para destruction_pending_v2
-    #[storage_mapper("destruction_pending_v2")]
-    fn destruction_pending_v2(&self) -> SingleValueMapper<bool>;
-
-    // Add storage mapper for destruction confirmation count
-    #[storage_mapper("destruction_confirmation_count")]
-    fn destruction_confirmation_count(&self) -> SingleValueMapper<u32>;
+    // Marcado quando o multisig aprova a destruição; impede despausar
+    #[storage_mapper("contract_destroyed")]
+    fn contract_destroyed(&self) -> SingleValueMapper<bool>;
 
     #[storage_mapper("repaid_loans_count")]
     fn repaid_loans_count(&self) -> SingleValueMapper<u64>;
@@ -1461,10 +5348,6 @@ pub trait LoanController {
     #[storage_mapper("blacklist")]
     fn blacklist(&self, user: ManagedAddress) -> SingleValueMapper<bool>;
 
-    // Modo de emergência
-    #[storage_mapper("emergency_mode")]
-    fn emergency_mode(&self) -> SingleValueMapper<bool>;
-
     // Limite de empréstimos por usuário
     #[storage_mapper("max_loans_per_user")]
     fn max_loans_per_user(&self) -> SingleValueMapper<u64>;
@@ -1489,9 +5372,227 @@ pub trait LoanController {
         fn liquidation_penalty(&self) -> SingleValueMapper<u64>;
     
         
+    // Pool de liquidez que financia os empréstimos
+    #[storage_mapper("liquidity_pool_address")]
+    fn liquidity_pool_address(&self) -> SingleValueMapper<ManagedAddress>;
+
+    // Token ESDT emprestado pelo pool
+    #[storage_mapper("lending_token_id")]
+    fn lending_token_id(&self) -> SingleValueMapper<TokenIdentifier>;
+
+    // Token com que o pool financiou o empréstimo (vazio se não veio do pool)
+    #[storage_mapper("loan_pool_token")]
+    fn loan_pool_token(&self, loan_id: u64) -> SingleValueMapper<TokenIdentifier>;
+
+    // Principal já devolvido por empréstimo (ao pool ou ao saldo emprestável)
+    #[storage_mapper("pool_principal_returned")]
+    fn pool_principal_returned(&self, loan_id: u64) -> SingleValueMapper<BigUint>;
+
+    // Pool que financiou cada empréstimo (vazio se não veio do pool)
+    #[storage_mapper("loan_pool_address")]
+    fn loan_pool_address(&self, loan_id: u64) -> SingleValueMapper<ManagedAddress>;
+
+    // Fundos disponíveis para empréstimos de cada token sem pool
+    #[storage_mapper("lendable_balance")]
+    fn lendable_balance(&self, token_id: &EgldOrEsdtTokenIdentifier) -> SingleValueMapper<BigUint>;
+
+    // Taxas do protocolo retidas no contrato, sacáveis pelo Admin
+    #[storage_mapper("protocol_fees")]
+    fn protocol_fees(&self, token_id: &EgldOrEsdtTokenIdentifier) -> SingleValueMapper<BigUint>;
+
+    // Parcelas de empréstimos parcelados (vazio para empréstimos com pagamento único)
+    #[storage_mapper("loan_installments")]
+    fn loan_installments(&self, loan_id: u64) -> VecMapper<Installment<Self::Api>>;
+
+    // Taxas de atraso acumuladas e ainda não pagas por empréstimo
+    #[storage_mapper("loan_late_fee_balance")]
+    fn loan_late_fee_balance(&self, loan_id: u64) -> SingleValueMapper<BigUint>;
+
+    // Último instante em que as taxas de atraso foram incorporadas ao saldo
+    #[storage_mapper("late_fee_checkpoint")]
+    fn late_fee_checkpoint(&self, loan_id: u64) -> SingleValueMapper<u64>;
+
+    // Taxas de atraso pagas por empréstimo
+    #[storage_mapper("loan_late_fees_paid")]
+    fn loan_late_fees_paid(&self, loan_id: u64) -> SingleValueMapper<BigUint>;
+
+    // Total de taxas de atraso recebidas
+    #[storage_mapper("total_late_fees_earned")]
+    fn total_late_fees_earned(&self) -> SingleValueMapper<BigUint>;
+
+    // Índice de empréstimos ativos por dia de vencimento (timestamp / 86400)
+    #[storage_mapper("due_day_loans")]
+    fn due_day_loans(&self, day: u64) -> UnorderedSetMapper<u64>;
+
+    // Primeiro dia do índice ainda não varrido por processOverdueLoans
+    #[storage_mapper("overdue_cursor_day")]
+    fn overdue_cursor_day(&self) -> SingleValueMapper<u64>;
+
+    // Dia do índice de vencimentos em que cada empréstimo ativo está
+    #[storage_mapper("loan_index_day")]
+    fn loan_index_day(&self, loan_id: u64) -> SingleValueMapper<u64>;
+
+    // Bônus do keeper por empréstimo inadimplente processado
+    #[storage_mapper("keeper_bounty")]
+    fn keeper_bounty(&self) -> SingleValueMapper<BigUint>;
+
+    // EGLD reservado para os bônus dos keepers (depositado em fundKeeperBounty)
+    #[storage_mapper("keeper_bounty_reserve")]
+    fn keeper_bounty_reserve(&self) -> SingleValueMapper<BigUint>;
+
+    // Leilão em andamento da garantia de cada empréstimo inadimplente
+    #[storage_mapper("collateral_auction")]
+    fn collateral_auction(&self, loan_id: u64) -> SingleValueMapper<CollateralAuction<Self::Api>>;
+
+    // Lances superados aguardando saque, por licitante e token
+    #[storage_mapper("auction_refunds")]
+    fn auction_refunds(&self, bidder: &ManagedAddress, token_id: &EgldOrEsdtTokenIdentifier) -> SingleValueMapper<BigUint>;
+
+    // Duração dos leilões de garantia, em segundos
+    #[storage_mapper("auction_duration")]
+    fn auction_duration(&self) -> SingleValueMapper<u64>;
+
+    // Penalidades de liquidação arrecadadas
+    #[storage_mapper("total_liquidation_penalties")]
+    fn total_liquidation_penalties(&self) -> SingleValueMapper<BigUint>;
+
+    // Tokens aceitos para novos empréstimos
+    #[storage_mapper("loan_tokens")]
+    fn loan_tokens(&self) -> UnorderedSetMapper<EgldOrEsdtTokenIdentifier>;
+
+    // Taxa base, limite e pool de cada token aceito
+    #[storage_mapper("loan_token_config")]
+    fn loan_token_config(&self, token_id: &EgldOrEsdtTokenIdentifier) -> SingleValueMapper<LoanTokenConfig<Self::Api>>;
+
+    // Token em que cada empréstimo foi concedido
+    #[storage_mapper("loan_token")]
+    fn loan_token(&self, loan_id: u64) -> SingleValueMapper<EgldOrEsdtTokenIdentifier>;
+
+    // Token da garantia de cada empréstimo (vazio = EGLD)
+    #[storage_mapper("loan_collateral_token")]
+    fn loan_collateral_token(&self, loan_id: u64) -> SingleValueMapper<EgldOrEsdtTokenIdentifier>;
+
+    // Token da garantia pendente de cada usuário (vazio = EGLD)
+    #[storage_mapper("pending_collateral_token")]
+    fn pending_collateral_token(&self, user: &ManagedAddress) -> SingleValueMapper<EgldOrEsdtTokenIdentifier>;
+
+    // Contrato que fornece os preços das garantias
+    #[storage_mapper("price_source_address")]
+    fn price_source_address(&self) -> SingleValueMapper<ManagedAddress>;
+
+    // Preços fixos por par (base, quote), escala PRICE_PRECISION
+    #[storage_mapper("collateral_price")]
+    fn collateral_price(
+        &self,
+        base_token: &EgldOrEsdtTokenIdentifier,
+        quote_token: &EgldOrEsdtTokenIdentifier,
+    ) -> SingleValueMapper<BigUint>;
+
+    // Linha de crédito rotativo de cada tomador
+    #[storage_mapper("credit_lines")]
+    fn credit_lines(&self, borrower: &ManagedAddress) -> SingleValueMapper<CreditLine<Self::Api>>;
+
+    // Último instante em que a linha estava com os juros quitados
+    #[storage_mapper("credit_line_paid_through")]
+    fn credit_line_paid_through(&self, borrower: &ManagedAddress) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("credit_line_defaulted")]
+    fn credit_line_defaulted(&self, borrower: &ManagedAddress) -> SingleValueMapper<bool>;
+
+    // Trilha de auditoria dos refinanciamentos: antigo -> novo e novo -> antigos
+    #[storage_mapper("loan_refinanced_into")]
+    fn loan_refinanced_into(&self, loan_id: u64) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("loan_refinanced_from")]
+    fn loan_refinanced_from(&self, loan_id: u64) -> VecMapper<u64>;
+
+    // Pedidos de empréstimo com fiadores (id sequencial a partir de 1)
+    #[storage_mapper("guarantee_request_counter")]
+    fn guarantee_request_counter(&self) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("guarantee_requests")]
+    fn guarantee_requests(&self, request_id: u64) -> SingleValueMapper<GuaranteedLoanRequest<Self::Api>>;
+
+    #[storage_mapper("guarantee_request_guarantors")]
+    fn guarantee_request_guarantors(&self, request_id: u64) -> UnorderedSetMapper<ManagedAddress>;
+
+    // Garantia bloqueada por fiador em cada pedido
+    #[storage_mapper("guarantor_stake")]
+    fn guarantor_stake(&self, request_id: u64, guarantor: &ManagedAddress) -> SingleValueMapper<BigUint>;
+
+    // Pedido com fiadores aguardando os fundos do pool para abrir o empréstimo
+    #[storage_mapper("guarantee_request_opening")]
+    fn guarantee_request_opening(&self, request_id: u64) -> SingleValueMapper<bool>;
+
+    // Pedido com fiadores que originou o empréstimo
+    #[storage_mapper("loan_guarantee_request")]
+    fn loan_guarantee_request(&self, loan_id: u64) -> SingleValueMapper<u64>;
+
+    // Garantia exigida dos fiadores em pontos base sobre o principal
+    #[storage_mapper("guarantor_stake_ratio")]
+    fn guarantor_stake_ratio(&self) -> SingleValueMapper<u64>;
+
+    // Taxa anual (pontos base) de cada empréstimo, para os juros por segundo
+    #[storage_mapper("loan_apr")]
+    fn loan_apr(&self, loan_id: u64) -> SingleValueMapper<u64>;
+
+    // Juros mínimos cobrados na quitação antecipada (pontos base dos juros contratados)
+    #[storage_mapper("min_interest_floor")]
+    fn min_interest_floor(&self) -> SingleValueMapper<u64>;
+
+    // Limites de exposição por faixa de pontuação (chave = pontuação mínima da faixa)
+    #[storage_mapper("exposure_tiers")]
+    fn exposure_tiers(&self) -> MapMapper<u64, ExposureLimit<Self::Api>>;
+
+    // Valor a partir do qual o pedido aguarda aprovação (zero = desativado)
+    #[storage_mapper("underwriting_threshold")]
+    fn underwriting_threshold(&self) -> SingleValueMapper<BigUint>;
+
+    // Prazo (segundos) para decidir um pedido antes que ele expire
+    #[storage_mapper("application_lifetime")]
+    fn application_lifetime(&self) -> SingleValueMapper<u64>;
+
+    // Pedidos na fila de aprovação (id sequencial a partir de 1)
+    #[storage_mapper("loan_application_counter")]
+    fn loan_application_counter(&self) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("loan_applications")]
+    fn loan_applications(&self, application_id: u64) -> SingleValueMapper<LoanApplication<Self::Api>>;
+
+    #[storage_mapper("pending_applications")]
+    fn pending_applications(&self) -> UnorderedSetMapper<u64>;
+
+    // Contrato DebtToken que emite os NFTs de dívida
+    #[storage_mapper("debt_token_address")]
+    fn debt_token_address(&self) -> SingleValueMapper<ManagedAddress>;
+
+    // Token (SFT) dos NFTs de dívida
+    #[storage_mapper("debt_nft_token_id")]
+    fn debt_nft_token_id(&self) -> SingleValueMapper<TokenIdentifier>;
+
+    // Nonce do NFT de dívida de cada empréstimo
+    #[storage_mapper("loan_debt_nft_nonce")]
+    fn loan_debt_nft_nonce(&self, loan_id: u64) -> SingleValueMapper<u64>;
+
+    // Empréstimo associado a cada nonce de NFT de dívida
+    #[storage_mapper("debt_nft_loan_id")]
+    fn debt_nft_loan_id(&self, nft_nonce: u64) -> SingleValueMapper<u64>;
+
     //=====================================================================
 
     // Proxy para o contrato ReputationScore
     #[proxy]
     fn reputation_score_proxy(&self, address: ManagedAddress) -> reputation_score_proxy::Proxy<Self::Api>;
+
+    // Proxy para o contrato LiquidityPool
+    #[proxy]
+    fn liquidity_pool_proxy(&self, address: ManagedAddress) -> liquidity_pool_proxy::Proxy<Self::Api>;
+
+    // Proxy para o contrato DebtToken
+    #[proxy]
+    fn debt_token_proxy(&self, address: ManagedAddress) -> debt_token_proxy::Proxy<Self::Api>;
+
+    // Proxy para a fonte de preços das garantias
+    #[proxy]
+    fn price_source_proxy(&self, address: ManagedAddress) -> price_source_proxy::Proxy<Self::Api>;
 }
\ No newline at end of file
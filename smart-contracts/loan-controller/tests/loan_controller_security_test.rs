@@ -4,13 +4,15 @@
 // ==========================================================================
 
 use multiversx_sc::contract_base::ContractBase;
-use multiversx_sc::types::{Address, BigUint};
+use multiversx_sc::types::{Address, BigUint, EgldOrEsdtTokenIdentifier, MultiValueEncoded};
 use multiversx_sc_scenario::{
     managed_address, managed_biguint, rust_biguint,
     testing_framework::{BlockchainStateWrapper, ContractObjWrapper},
     DebugApi,
 };
 use loan_controller::*;
+use common_types::access_control::{AccessControlModule, Role};
+use common_types::multisig::{MultisigAction, MultisigModule};
 
 const WASM_PATH: &str = "output/loan-controller.wasm";
 
@@ -257,7 +259,7 @@ fn test_timestamp_manipulation() {
             sc.blockchain().get_block_timestamp();
             
             // Marcar empréstimos vencidos
-            sc.mark_expired_loans();
+            let _ = sc.mark_expired_loans(0u64, 100u64);
             
             // Verificar que o empréstimo foi marcado como inadimplente
             let loan = sc.loans(1u64).get();
@@ -303,8 +305,11 @@ fn test_mark_expired_loans() {
     // Marcar empréstimos vencidos
     setup.blockchain_wrapper
         .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
-            sc.mark_expired_loans();
+            // A varredura é limitada e devolve o id de onde continuar
+            assert_eq!(sc.mark_expired_loans(0u64, 1u64).into_option(), Some(1u64));
+            assert_eq!(sc.loans(1u64).get().status, LoanStatus::Active);
 
+            assert_eq!(sc.mark_expired_loans(1u64, 100u64).into_option(), None);
             let loan = sc.loans(1u64).get();
             assert_eq!(loan.status, LoanStatus::Defaulted);
         })
@@ -350,7 +355,7 @@ fn test_access_control() {
             // Tentar funções de administrador
             sc.set_min_required_score(600u64);
         })
-        .assert_user_error("Only owner can call this function");
+        .assert_user_error("Caller does not have the required role");
     
     // Verificar que o proprietário pode chamar funções restritas
     setup.blockchain_wrapper
@@ -534,6 +539,8 @@ fn test_contract_upgrade_integrity() {
     // Simular upgrade do contrato
     setup.blockchain_wrapper
         .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.upgrade();
+
             // Verificar que os parâmetros foram preservados
             assert_eq!(sc.min_required_score().get(), 600u64);
             assert_eq!(sc.interest_rate_base().get(), 1200u64);
@@ -617,18 +624,13 @@ fn test_timecode_security() {
     // Simular uma solicitação com timelock
         setup.blockchain_wrapper
             .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
-                let param_key = match ParamType::MinScore {
-                    ParamType::MinScore => managed_biguint!(1), // Replace with appropriate BigUint value
-                    ParamType::MaxScore => managed_biguint!(2), // Replace with an actual variant of ParamType
-                    ParamType::InterestRate => managed_biguint!(3), // Handle InterestRate variant
-                };
-                let param_key_clone = param_key.clone();
-                sc.pending_parameter_changes_by_key(param_key).set(ParameterChange {
+                sc.pending_parameter_changes(ParamType::MinScore).set(ParameterChange {
                     value: 600u64,
                     timestamp: 10000u64,
+                    executable_at: 10300u64,
                 });
 
-                let request = sc.pending_parameter_changes_by_key(param_key_clone).get();
+                let request = sc.pending_parameter_changes(ParamType::MinScore).get();
                 assert_eq!(request.value, 600u64);
                 assert_eq!(request.timestamp, 10000u64);
             })
@@ -678,58 +680,401 @@ fn test_timecode_security() {
 #[test]
 fn test_self_destruct_security() {
     let mut setup = setup_contract(loan_controller::contract_obj);
-    
-    // Verificar que apenas o proprietário pode iniciar auto-destruição
+    let third_signer = setup.blockchain_wrapper.create_user_account(&rust_biguint!(0));
+    let signers = [
+        setup.owner_address.clone(),
+        setup.borrower_address.clone(),
+        third_signer.clone(),
+    ];
+    setup.blockchain_wrapper.set_block_timestamp(1_000);
+    // 10_000 emprestáveis do protocolo e 2_500 de garantia de um tomador
+    setup.blockchain_wrapper.set_egld_balance(setup.contract_wrapper.address_ref(), &rust_biguint!(12_500));
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.lendable_balance(&EgldOrEsdtTokenIdentifier::egld()).set(BigUint::from(10_000u64));
+        })
+        .assert_ok();
+
+    // Apenas o owner configura o conjunto de signatários (2 de 3)
     setup.blockchain_wrapper
         .execute_tx(&setup.attacker_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
-            sc.initiate_contract_destruction();
+            sc.setup_multisig(1u32, MultiValueEncoded::from_iter([managed_address!(&setup.attacker_address)]));
         })
         .assert_user_error("Only owner can call this function");
-    
-    // Verificar que a auto-destruição exige confirmação dupla
     setup.blockchain_wrapper
         .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
-            // Primeira etapa - inicia contador
-            sc.initiate_contract_destruction();
-            
-            // Verificar que foi iniciado
-            assert!(sc.destruction_pending_v2().get());
-            assert_eq!(sc.destruction_confirmation_count().get(), 1u32);
-            
-            // Na vida real, seria necessária uma segunda transação para confirmação
+            sc.setup_multisig(2u32, signers.iter().map(|s| managed_address!(s)).collect());
+            assert_eq!(sc.get_multisig_quorum(), 2u32);
         })
         .assert_ok();
-    
-    // Verificar que a destruição só ocorre após todas as confirmações
+    setup.blockchain_wrapper
+        .execute_tx(&setup.attacker_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.propose_action(MultisigAction::ContractDestruction);
+        })
+        .assert_user_error("Apenas signatários do multisig");
+
+    // Uma única aprovação não basta, e o mesmo signatário não conta duas vezes
     setup.blockchain_wrapper
         .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
-            // Verificar estado atual
-            assert!(sc.destruction_pending_v2().get());
-            
-            // Confirmar destruição
-            sc.confirm_contract_destruction_v2();
-            
-            // Verificar contador
-            assert_eq!(sc.destruction_confirmation_count().get(), 2u32);
-            
-            // Em uma implementação real, verificaria se atingiu o limite necessário
-            let required_confirmations = 3u32;
-            
-            if sc.destruction_confirmation_count().get() >= required_confirmations {
-                // Destruir o contrato - em teste, apenas simulamos
-                assert!(false); // Não deve chegar aqui ainda
-            }
+            assert_eq!(sc.propose_action(MultisigAction::ContractDestruction), 1u64);
         })
         .assert_ok();
-    
-    // Verificar que é possível cancelar a destruição
     setup.blockchain_wrapper
         .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
-            sc.cancel_contract_destruction_v2();
-            
-            // Verificar que foi cancelado
-            assert!(!sc.destruction_pending_v2().get());
-            assert_eq!(sc.destruction_confirmation_count().get(), 0u32);
+            sc.perform_action(1u64);
+        })
+        .assert_user_error("Quórum de aprovações não atingido");
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.approve_action(1u64);
+        })
+        .assert_user_error("Signatário já aprovou esta proposta");
+
+    // Setters sensíveis deixam de ser diretos
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_price_source(managed_address!(&setup.attacker_address));
+        })
+        .assert_user_error("Operação exige aprovação multisig: use proposeAction");
+
+    // Com a segunda aprovação, qualquer signatário executa
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.approve_action(1u64);
+            assert!(sc.is_quorum_reached(1u64));
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&third_signer, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.perform_action(1u64);
+            assert!(sc.paused().get());
+            assert!(sc.get_proposal(1u64).into_option().is_none());
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.check_egld_balance(setup.contract_wrapper.address_ref(), &rust_biguint!(2_500));
+    setup.blockchain_wrapper.check_egld_balance(&setup.owner_address, &rust_biguint!(10_000));
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.unpause_contract();
+        })
+        .assert_user_error("Contrato destruído");
+
+    // Propostas expiram e podem ser descartadas
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.propose_action(MultisigAction::EmergencyWithdraw);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.set_block_timestamp(1_000 + 3 * 24 * 60 * 60 + 1);
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.approve_action(2u64);
+        })
+        .assert_user_error("Proposta expirou");
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.discard_action(2u64);
+            assert!(sc.get_proposal(2u64).into_option().is_none());
+        })
+        .assert_ok();
+}
+
+#[test]
+fn test_timelocked_parameter_governance() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let grace_period = 7u64 * 24 * 60 * 60;
+    setup.blockchain_wrapper.set_block_timestamp(10_000);
+
+    // Sem timelock, a configuração inicial é aplicada na hora
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_operation_timelock(300u64);
+        })
+        .assert_ok();
+
+    // Com timelock, setters diretos são recusados
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_interest_rate_base(1500u64);
+        })
+        .assert_user_error("Parâmetro sob timelock: use proposeParameterChange");
+    setup.blockchain_wrapper
+        .execute_tx(&setup.attacker_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.propose_parameter_change(ParamType::InterestRate, 1500u64);
+        })
+        .assert_user_error("Caller does not have the required role");
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.propose_parameter_change(ParamType::LiquidationPenalty, 20_000u64);
+        })
+        .assert_user_error("A penalidade de liquidação não pode exceder 100%");
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.propose_parameter_change(ParamType::InterestRate, 1500u64);
+
+            let pending: Vec<_> = sc.get_pending_parameter_changes().into_iter().collect();
+            assert_eq!(pending.len(), 1);
+            let (param, value, proposed_at, executable_at) = pending[0].clone().into_tuple();
+            assert_eq!(param, ParamType::InterestRate);
+            assert_eq!(value, 1500u64);
+            assert_eq!(proposed_at, 10_000u64);
+            assert_eq!(executable_at, 10_300u64);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.propose_parameter_change(ParamType::InterestRate, 1800u64);
+        })
+        .assert_user_error("Já existe alteração pendente para este parâmetro");
+
+    // Execução antes do fim do timelock é rejeitada
+    setup.blockchain_wrapper.set_block_timestamp(10_100);
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.execute_parameter_change(ParamType::InterestRate);
+        })
+        .assert_user_error("Timelock ainda não expirou");
+
+    setup.blockchain_wrapper.set_block_timestamp(10_300);
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.execute_parameter_change(ParamType::InterestRate);
+            assert_eq!(sc.interest_rate_base().get(), 1500u64);
+            assert!(sc.get_pending_parameter_changes().is_empty());
+
+            // Cancelamento descarta a proposta
+            sc.propose_parameter_change(ParamType::MinScore, 600u64);
+            sc.cancel_parameter_change(ParamType::MinScore);
+            assert_eq!(sc.min_required_score().get(), 500u64);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.execute_parameter_change(ParamType::MinScore);
+        })
+        .assert_user_error("Nenhuma alteração pendente para este parâmetro");
+
+    // Alterações não executadas dentro do prazo expiram
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.propose_parameter_change(ParamType::MaxActiveLoans, 3u64);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.set_block_timestamp(10_300 + 300 + grace_period + 1);
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.execute_parameter_change(ParamType::MaxActiveLoans);
+        })
+        .assert_user_error("Alteração pendente expirou");
+
+    // Zerar o timelock não antecipa alterações já propostas
+    let now = 10_300 + 300 + grace_period + 1;
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.cancel_parameter_change(ParamType::MaxActiveLoans);
+            sc.propose_parameter_change(ParamType::OperationTimelock, 0u64);
         })
         .assert_ok();
+    setup.blockchain_wrapper.set_block_timestamp(now + 299);
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.propose_parameter_change(ParamType::MinScore, 700u64);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.set_block_timestamp(now + 300);
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.execute_parameter_change(ParamType::OperationTimelock);
+            assert_eq!(sc.operation_timelock().get(), 0u64);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.execute_parameter_change(ParamType::MinScore);
+        })
+        .assert_user_error("Timelock ainda não expirou");
+}
+
+#[test]
+fn test_timelocked_config_changes() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    setup.blockchain_wrapper.set_block_timestamp(10_000);
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_keeper_bounty(managed_biguint!(5));
+            sc.set_operation_timelock(300u64);
+        })
+        .assert_ok();
+
+    // Com timelock, os setters de configuração de risco também são recusados
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_keeper_bounty(managed_biguint!(1_000));
+        })
+        .assert_user_error("Parâmetro sob timelock: use proposeConfigChange");
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_exposure_tier(0u64, 1u64, managed_biguint!(100));
+        })
+        .assert_user_error("Parâmetro sob timelock: use proposeConfigChange");
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_application_lifetime(60u64);
+        })
+        .assert_user_error("Parâmetro sob timelock: use proposeParameterChange");
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_max_loan_term_days(365u64);
+        })
+        .assert_user_error("Parâmetro sob timelock: use proposeParameterChange");
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_loan_terms(20u64, 60u64, 10u64);
+        })
+        .assert_user_error("Parâmetro sob timelock: use proposeConfigChange");
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_standard_loan_term_days(45u64);
+        })
+        .assert_user_error("Parâmetro sob timelock: use proposeConfigChange");
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_min_collateral_amount(managed_biguint!(1));
+        })
+        .assert_user_error("Parâmetro sob timelock: use proposeConfigChange");
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.propose_config_change(ConfigChange::LoanTerms { standard: 30u64, extended: 20u64, short: 15u64 });
+        })
+        .assert_user_error("Termos devem seguir a hierarquia");
+    setup.blockchain_wrapper
+        .execute_tx(&setup.attacker_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.propose_config_change(ConfigChange::KeeperBounty { bounty: managed_biguint!(1_000) });
+        })
+        .assert_user_error("Caller does not have the required role");
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.propose_config_change(ConfigChange::RemoveExposureTier { min_score: 0u64 });
+        })
+        .assert_user_error("Faixa de exposição não existe");
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            let bounty_id = sc.propose_config_change(ConfigChange::KeeperBounty { bounty: managed_biguint!(1_000) });
+            assert_eq!(bounty_id, 1u64);
+            let threshold_id = sc.propose_config_change(ConfigChange::UnderwritingThreshold {
+                threshold: managed_biguint!(500),
+            });
+            assert_eq!(threshold_id, 2u64);
+            let terms_id = sc.propose_config_change(ConfigChange::LoanTerms {
+                standard: 45u64,
+                extended: 120u64,
+                short: 20u64,
+            });
+            assert_eq!(terms_id, 3u64);
+
+            let pending: Vec<_> = sc.get_pending_config_changes().into_iter().collect();
+            assert_eq!(pending.len(), 3);
+            let (change_id, change, proposed_at, executable_at) = pending[0].clone().into_tuple();
+            assert_eq!(change_id, 1u64);
+            assert_eq!(change, ConfigChange::KeeperBounty { bounty: managed_biguint!(1_000) });
+            assert_eq!(proposed_at, 10_000u64);
+            assert_eq!(executable_at, 10_300u64);
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper.set_block_timestamp(10_299);
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.execute_config_change(1u64);
+        })
+        .assert_user_error("Timelock ainda não expirou");
+
+    setup.blockchain_wrapper.set_block_timestamp(10_300);
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.execute_config_change(1u64);
+            assert_eq!(sc.get_keeper_bounty(), managed_biguint!(1_000));
+            sc.execute_config_change(3u64);
+            assert_eq!(sc.get_standard_loan_term_days(), 45u64);
+            assert_eq!(sc.get_extended_loan_term_days(), 120u64);
+            assert_eq!(sc.short_term_days().get(), 20u64);
+
+            sc.cancel_config_change(2u64);
+            assert!(sc.get_pending_config_changes().is_empty());
+            assert_eq!(sc.underwriting_threshold().get(), managed_biguint!(0));
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.execute_config_change(2u64);
+        })
+        .assert_user_error("Nenhuma alteração de configuração pendente com este id");
+}
+
+#[test]
+fn test_role_based_access_control() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let risk_manager = setup.borrower_address.clone();
+
+    // Apenas owner ou Admin concedem papéis
+    setup.blockchain_wrapper
+        .execute_tx(&setup.attacker_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.grant_role(Role::Admin, managed_address!(&setup.attacker_address));
+        })
+        .assert_user_error("Only owner or admin can manage roles");
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.grant_role(Role::RiskManager, managed_address!(&risk_manager));
+            assert!(sc.has_role(Role::RiskManager, managed_address!(&risk_manager)));
+            assert!(!sc.has_role(Role::Pauser, managed_address!(&risk_manager)));
+            assert_eq!(sc.get_role_members(Role::RiskManager).len(), 1);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.grant_role(Role::RiskManager, managed_address!(&risk_manager));
+        })
+        .assert_user_error("Endereço já possui este papel");
+
+    // O RiskManager ajusta parâmetros de risco, mas não pausa nem mexe em fundos
+    setup.blockchain_wrapper
+        .execute_tx(&risk_manager, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_collateral_ratio(8_000u64);
+            sc.set_min_required_score(600u64);
+            assert_eq!(sc.collateral_ratio().get(), 8_000u64);
+            assert_eq!(sc.min_required_score().get(), 600u64);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&risk_manager, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.pause_contract();
+        })
+        .assert_user_error("Caller does not have the required role");
+    setup.blockchain_wrapper
+        .execute_tx(&risk_manager, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_operation_timelock(300u64);
+        })
+        .assert_user_error("Caller does not have the required role");
+
+    // Revogado, o endereço perde o acesso
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.revoke_role(Role::RiskManager, managed_address!(&risk_manager));
+            assert!(!sc.has_role(Role::RiskManager, managed_address!(&risk_manager)));
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&risk_manager, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_collateral_ratio(7_000u64);
+        })
+        .assert_user_error("Caller does not have the required role");
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.revoke_role(Role::RiskManager, managed_address!(&risk_manager));
+        })
+        .assert_user_error("Endereço não possui este papel");
 }
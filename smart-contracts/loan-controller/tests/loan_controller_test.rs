@@ -14,6 +14,11 @@ use multiversx_sc_scenario::{
 };
 
 use loan_controller::*;
+use common_types::access_control::{AccessControlModule, Role};
+use common_types::clock::ClockModule;
+use common_types::storage_version::StorageVersionModule;
+use common_types::CreditDecision;
+use common_types::multisig::{MultisigAction, MultisigModule};
 
 const WASM_PATH: &str = "output/loan-controller.wasm";
 
@@ -73,6 +78,29 @@ where
     }
 }
 
+// Deposita EGLD no saldo emprestável do contrato (depositLendingFunds)
+fn fund_lendable_egld<ContractObjBuilder>(setup: &mut ContractSetup<ContractObjBuilder>, amount: u64)
+where
+    ContractObjBuilder: 'static + Copy + Fn() -> loan_controller::ContractObj<DebugApi>,
+{
+    setup.blockchain_wrapper.set_egld_balance(&setup.owner_address, &rust_biguint!(amount));
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(amount), |sc| {
+            sc.deposit_lending_funds();
+        })
+        .assert_ok();
+}
+
+// Endereço de contrato no mesmo shard de `contract` ou em outro
+// (o VM de testes usa o último byte do endereço)
+fn address_in_shard(contract: &Address, same_shard: bool) -> Address {
+    let shard = contract.as_bytes()[31] % 3;
+    let mut bytes = [7u8; 32];
+    bytes[..8].copy_from_slice(&[0u8; 8]);
+    bytes[31] = if same_shard { shard } else { (shard + 1) % 3 };
+    Address::from(bytes)
+}
+
 // Teste de inicialização do contrato
 #[test]
 fn test_init() {
@@ -193,7 +221,7 @@ fn test_request_loan_success() {
     setup.blockchain_wrapper
         .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
             // Definir timestamp atual
-            sc.get_block_timestamp();
+            sc.current_timestamp();
             
             // Solicitar empréstimo
             let _loan_id = sc.request_loan(
@@ -370,7 +398,7 @@ fn test_repay_loan_success() {
     // Efetuar o pagamento do empréstimo
     setup.blockchain_wrapper
         .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(5500), |sc| {
-            sc.get_block_timestamp(); // Antes do vencimento
+            sc.current_timestamp(); // Antes do vencimento
             
             sc.repay_loan(1u64);
             
@@ -560,7 +588,7 @@ fn test_owner_only_functions() {
         .execute_tx(&non_owner, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
             sc.set_min_required_score(600u64);
         })
-        .assert_user_error("Only owner can call this function");
+        .assert_user_error("Caller does not have the required role");
     
     // Proprietário atualiza parâmetros com sucesso
     setup.blockchain_wrapper
@@ -750,25 +778,37 @@ fn test_loan_statistics() {
 fn test_withdraw_funds() {
     let mut setup = setup_contract(loan_controller::contract_obj);
 
-    // 1. Adiciona fundos ao contrato
+    // 1. Contrato com 10000 de taxas apuradas e 5000 de garantia de um tomador
     setup.blockchain_wrapper.set_egld_balance(
         &setup.contract_wrapper.address_ref(),
-        &rust_biguint!(10000),
+        &rust_biguint!(15000),
     );
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.protocol_fees(&EgldOrEsdtTokenIdentifier::egld()).set(managed_biguint!(10000));
+            sc.loan_collateral(1u64).set(managed_biguint!(5000));
+        })
+        .assert_ok();
 
     // 2. Salva saldo inicial do owner
     let initial_owner_balance = setup.blockchain_wrapper.get_egld_balance(&setup.owner_address);
 
-    // 3. Owner saca os fundos
+    // 3. Só as taxas podem ser sacadas
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.withdraw_funds(rust_biguint!(15000).into(), OptionalValue::None);
+        })
+        .assert_user_error("Saldo de taxas insuficiente");
     setup.blockchain_wrapper
         .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
-            sc.withdraw_funds(rust_biguint!(10000).into());
+            sc.withdraw_funds(rust_biguint!(10000).into(), OptionalValue::None);
+            assert_eq!(sc.get_protocol_fees(EgldOrEsdtTokenIdentifier::egld()), managed_biguint!(0));
         })
         .assert_ok();
 
-    // 4. Verifica saldo final do contrato (deve ser 0)
+    // 4. A garantia continua no contrato
     let contract_balance = setup.blockchain_wrapper.get_egld_balance(&setup.contract_wrapper.address_ref());
-    assert_eq!(contract_balance, rust_biguint!(0));
+    assert_eq!(contract_balance, rust_biguint!(5000));
 
     // 5. Verifica que o owner recebeu os fundos
     let final_owner_balance = setup.blockchain_wrapper.get_egld_balance(&setup.owner_address);
@@ -781,9 +821,21 @@ fn test_withdraw_funds() {
     let non_owner = setup.blockchain_wrapper.create_user_account(&rust_biguint!(0));
     setup.blockchain_wrapper
         .execute_tx(&non_owner, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
-            sc.withdraw_funds(rust_biguint!(10000).into());
+            sc.withdraw_funds(rust_biguint!(10000).into(), OptionalValue::None);
+        })
+        .assert_user_error("Caller does not have the required role");
+
+    // 7. Com multisig configurado, o saque direto é recusado
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.setup_multisig(1u32, MultiValueEncoded::from_iter([managed_address!(&setup.owner_address)]));
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.withdraw_funds(rust_biguint!(0).into(), OptionalValue::None);
         })
-        .assert_user_error("Only owner can call this function");
+        .assert_user_error("Operação exige aprovação multisig: use proposeAction");
 }
 
 // Teste para pausar/despausar o contrato
@@ -979,7 +1031,7 @@ fn test_handle_expired_loans() {
     // Executa a lógica de expiração
     setup.blockchain_wrapper
         .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
-            sc.mark_expired_loans();
+            let _ = sc.mark_expired_loans(0u64, 100u64);
 
             assert_eq!(sc.loans(1u64).get().status, LoanStatus::Defaulted);
             assert_eq!(sc.loans(2u64).get().status, LoanStatus::Active);
@@ -1230,6 +1282,39 @@ fn test_collateral_forfeiture() {
         .assert_ok();
 }
 
+// A garantia apreendida entra nas taxas do protocolo e pode ser sacada
+#[test]
+fn test_forfeited_collateral_can_be_withdrawn() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let owner = setup.owner_address.clone();
+    setup.blockchain_wrapper.set_block_timestamp(10_000);
+    fund_lendable_egld(&mut setup, 1_000);
+    setup.blockchain_wrapper
+        .execute_tx(&owner, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.collateral_ratio().set(7000u64);
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(700), |sc| {
+            sc.provide_collateral_for_new_loan();
+            assert_eq!(sc.request_loan_with_collateral(), 1u64);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&owner, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.mark_loan_defaulted(1u64);
+            sc.forfeit_collateral(1u64);
+            assert_eq!(sc.loan_collateral(1u64).get(), managed_biguint!(0));
+            assert_eq!(sc.get_protocol_fees(EgldOrEsdtTokenIdentifier::egld()), managed_biguint!(700));
+
+            sc.withdraw_funds(managed_biguint!(700), OptionalValue::None);
+            assert_eq!(sc.get_protocol_fees(EgldOrEsdtTokenIdentifier::egld()), managed_biguint!(0));
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.check_egld_balance(&owner, &rust_biguint!(700));
+}
+
 // Teste para tentativa não autorizada de executar a garantia
 #[test]
 fn test_unauthorized_collateral_forfeiture() {
@@ -1261,7 +1346,7 @@ fn test_unauthorized_collateral_forfeiture() {
         .execute_tx(&non_owner, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
             sc.forfeit_collateral(1u64);
         })
-        .assert_user_error("Only owner can call this function");
+        .assert_user_error("Caller does not have the required role");
 }
 
 // Teste para fornecer garantia para um empréstimo
@@ -1685,69 +1770,84 @@ fn test_distribute_profits() {
     let mut setup = setup_contract(loan_controller::contract_obj);
     let investor1 = setup.blockchain_wrapper.create_user_account(&rust_biguint!(0));
     let investor2 = setup.blockchain_wrapper.create_user_account(&rust_biguint!(0));
-    
-    // Configurar e pagar um empréstimo para gerar juros
+    let investor3 = setup.blockchain_wrapper.create_user_account(&rust_biguint!(0));
+    setup.blockchain_wrapper.set_egld_balance(&setup.borrower_address, &rust_biguint!(22_000));
+
+    let borrower = setup.borrower_address.clone();
+    let set_loan = |sc: &loan_controller::ContractObj<DebugApi>, loan_id: u64| {
+        sc.loan_counter().set(loan_id);
+        sc.loans(loan_id).set(Loan {
+            borrower: managed_address!(&borrower),
+            amount: managed_biguint!(10_000),
+            repayment_amount: managed_biguint!(11_000), // 1000 de juros
+            interest_rate: 1000u64,
+            creation_timestamp: 10000u64,
+            due_timestamp: 20000u64,
+            status: LoanStatus::Active,
+        });
+        sc.user_loans(managed_address!(&borrower)).push(&loan_id);
+    };
+
+    // Primeiro empréstimo pago com investidores 60% / 40%
     setup.blockchain_wrapper
         .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
-            // Configurar empréstimo
-            sc.loan_counter().set(1u64);
-            let loan = Loan {
-                borrower: managed_address!(&setup.borrower_address),
-                amount: managed_biguint!(10_000),
-                repayment_amount: managed_biguint!(11_000), // 1000 de juros
-                interest_rate: 1000u64,
-                creation_timestamp: 10000u64,
-                due_timestamp: 20000u64,
-                status: LoanStatus::Active,
-            };
-            sc.loans(1u64).set(loan);
-            sc.user_loans(managed_address!(&setup.borrower_address)).push(&1u64);
-            
-            // Adicionar investidores
+            set_loan(&sc, 1u64);
             sc.add_investor(managed_address!(&investor1), 6000u64); // 60%
             sc.add_investor(managed_address!(&investor2), 4000u64); // 40%
         })
         .assert_ok();
-    
-    // Pagar o empréstimo para gerar juros
     setup.blockchain_wrapper
         .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(11_000), |sc| {
             sc.repay_loan(1u64);
         })
         .assert_ok();
-    
-    // Distribuir lucros dos juros
+    setup.blockchain_wrapper
+        .execute_query(&setup.contract_wrapper, |sc| {
+            assert_eq!(sc.pending_profits(managed_address!(&investor1)), managed_biguint!(600));
+            assert_eq!(sc.pending_profits(managed_address!(&investor2)), managed_biguint!(400));
+        })
+        .assert_ok();
+
+    // As participações mudam antes do segundo pagamento: o investidor 1 sai
+    // e o investidor 3 entra com 60%; o que já foi apurado não muda
     setup.blockchain_wrapper
         .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
-            // Verificar saldo antes da distribuição
-            assert_eq!(
-                sc.blockchain().get_balance(&sc.blockchain().get_sc_address()),
-                managed_biguint!(11_000)
-            );
-            
-            // Distribuir apenas os juros (1000)
-            sc.distribute_profits();
-            
-            // Verificar saldo após distribuição (deve manter o principal)
-            assert_eq!(
-                sc.blockchain().get_balance(&sc.blockchain().get_sc_address()),
-                managed_biguint!(10_000) // Principal mantido
-            );
-            
-            // Verificar que os investidores receberam suas partes dos juros
-            assert_eq!(
-                sc.blockchain().get_balance(&managed_address!(&investor1)),
-                managed_biguint!(600) // 60% de 1000
-            );
-            assert_eq!(
-                sc.blockchain().get_balance(&managed_address!(&investor2)),
-                managed_biguint!(400) // 40% de 1000
-            );
-            
-            // Verificar que o contador de juros foi zerado
-            assert_eq!(sc.total_interest_earned().get(), managed_biguint!(0));
+            sc.remove_investor(managed_address!(&investor1));
+            sc.add_investor(managed_address!(&investor3), 6000u64);
+            set_loan(&sc, 2u64);
+            assert_eq!(sc.get_investors().len(), 2);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(11_000), |sc| {
+            sc.repay_loan(2u64);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_query(&setup.contract_wrapper, |sc| {
+            assert_eq!(sc.pending_profits(managed_address!(&investor1)), managed_biguint!(600));
+            assert_eq!(sc.pending_profits(managed_address!(&investor2)), managed_biguint!(800));
+            assert_eq!(sc.pending_profits(managed_address!(&investor3)), managed_biguint!(600));
         })
         .assert_ok();
+
+    // Cada investidor saca sua parte
+    for (investor, expected) in [(&investor1, 600u64), (&investor2, 800u64), (&investor3, 600u64)] {
+        setup.blockchain_wrapper
+            .execute_tx(investor, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+                assert_eq!(sc.claim_profits(), managed_biguint!(expected));
+            })
+            .assert_ok();
+        setup.blockchain_wrapper.check_egld_balance(investor, &rust_biguint!(expected));
+    }
+    setup.blockchain_wrapper
+        .execute_tx(&investor2, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.claim_profits();
+        })
+        .assert_user_error("Não há lucros a receber");
+
+    // O principal permanece no contrato
+    setup.blockchain_wrapper.check_egld_balance(setup.contract_wrapper.address_ref(), &rust_biguint!(20_000));
 }
 
 // Teste para verificar a validação de endereços ao adicionar investidores
@@ -2052,9 +2152,27 @@ fn test_liquidate_collateral_via_auction() {
             sc.loan_collateral(1u64).set(managed_biguint!(10_000));
         })
         .assert_ok();
+    // A garantia está em posse do contrato
+    setup.blockchain_wrapper.set_egld_balance(setup.contract_wrapper.address_ref(), &rust_biguint!(10_000));
     
     // Atualizar o timestamp simulado
     setup.current_timestamp = 25000;
+    setup.blockchain_wrapper.set_block_timestamp(setup.current_timestamp);
+
+    // Sem leilão aberto não há lance
+    setup.blockchain_wrapper
+        .execute_tx(&winning_bidder, &setup.contract_wrapper, &rust_biguint!(8_000), |sc| {
+            sc.liquidate_collateral_via_auction(1u64);
+        })
+        .assert_user_error("Leilão não iniciado");
+
+    // Leilão holandês começa no valor da dívida (7700)
+    setup.blockchain_wrapper
+        .execute_tx(&winning_bidder, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.start_collateral_auction(1u64, AuctionType::Dutch);
+            assert_eq!(sc.get_auction_price(1u64), managed_biguint!(7_700));
+        })
+        .assert_ok();
 
         // Simular leilão com lance vencedor
     setup.blockchain_wrapper
@@ -2067,10 +2185,10 @@ fn test_liquidate_collateral_via_auction() {
             // Verificar que a garantia foi transferida ao licitante vencedor
             assert_eq!(sc.loan_collateral(1u64).get(), managed_biguint!(0));
             
-            // Verificar que o valor do lance foi recebido pelo contrato
+            // Verificar que o contrato ficou com o preço do leilão (o excedente do lance é devolvido)
             assert_eq!(
                 sc.blockchain().get_balance(&sc.blockchain().get_sc_address()),
-                managed_biguint!(8_000)
+                managed_biguint!(7_700)
             );
             
             // Verificar que o empréstimo foi marcado como liquidado
@@ -2078,6 +2196,8 @@ fn test_liquidate_collateral_via_auction() {
             assert_eq!(loan.status, LoanStatus::Liquidated);
         })
         .assert_ok();
+
+    setup.blockchain_wrapper.check_egld_balance(&winning_bidder, &rust_biguint!(10_300));
 }
 
 // Teste para pagamento parcial de empréstimo
@@ -2195,7 +2315,7 @@ fn test_partial_loan_repayment() {
 //             sc.blockchain().get_block_timestamp();
             
 //             // Tentar marcar empréstimos vencidos
-//             sc.mark_expired_loans();
+//             sc.mark_expired_loans(0u64, 100u64);
             
 //             // Verificar que o empréstimo ainda está ativo
 //             let loan = sc.loans(1u64).get();
@@ -2213,7 +2333,7 @@ fn test_partial_loan_repayment() {
 //             sc.blockchain().get_block_timestamp();
             
 //             // Marcar empréstimos vencidos
-//             sc.mark_expired_loans();
+//             sc.mark_expired_loans(0u64, 100u64);
             
 //             // Verificar que o empréstimo foi marcado como inadimplente
 //             let loan = sc.loans(1u64).get();
@@ -2474,37 +2594,45 @@ fn test_liquidation_discount() {
 fn test_emergency_funds_recovery() {
     let mut setup = setup_contract(loan_controller::contract_obj);
 
-    // ✅ Adicionar fundos ao contrato (forma correta)
+    // Saldo emprestável, reserva dos keepers e taxas são do protocolo;
+    // os 4000 restantes são garantias de tomadores
+    fund_lendable_egld(&mut setup, 7000);
     setup.blockchain_wrapper.set_egld_balance(
         &setup.contract_wrapper.address_ref(),
-        &rust_biguint!(10000),
+        &rust_biguint!(14000),
     );
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.keeper_bounty_reserve().set(managed_biguint!(2000));
+            sc.protocol_fees(&EgldOrEsdtTokenIdentifier::egld()).set(managed_biguint!(1000));
+        })
+        .assert_ok();
 
-    // Ativar modo de emergência
+    // Multisig com o owner como único signatário
     setup.blockchain_wrapper
         .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
-            sc.emergency_mode().set(true);
+            sc.setup_multisig(1u32, MultiValueEncoded::from_iter([managed_address!(&setup.owner_address)]));
         })
         .assert_ok();
 
     // Recuperar fundos
     setup.blockchain_wrapper
         .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
-            let initial_owner_balance = sc.blockchain().get_balance(&managed_address!(&setup.owner_address));
-            
-            sc.emergency_withdraw();
-            
-            // Verificar que o saldo do contrato foi para zero
+            let action_id = sc.propose_action(MultisigAction::EmergencyWithdraw);
+            sc.perform_action(action_id);
+
+            // As garantias ficam no contrato
             assert_eq!(
                 sc.blockchain().get_balance(&sc.blockchain().get_sc_address()),
-                managed_biguint!(0)
+                managed_biguint!(4000)
             );
-            
-            // Verificar que o proprietário recebeu os fundos
-            let final_owner_balance = sc.blockchain().get_balance(&managed_address!(&setup.owner_address));
-            assert_eq!(final_owner_balance, initial_owner_balance + managed_biguint!(10000));
+            assert_eq!(sc.get_lendable_balance(EgldOrEsdtTokenIdentifier::egld()), managed_biguint!(0));
+            assert_eq!(sc.keeper_bounty_reserve().get(), managed_biguint!(0));
         })
         .assert_ok();
+
+    // O proprietário recebe só o EGLD do protocolo
+    setup.blockchain_wrapper.check_egld_balance(&setup.owner_address, &rust_biguint!(10000));
 }
 
 
@@ -2631,7 +2759,7 @@ fn test_blacklist_functionality() {
     // (a) executa a chamada ao endpoint, retornando unit
     setup.blockchain_wrapper
         .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
-            sc.get_block_timestamp();     // unit
+            sc.current_timestamp();   // unit
             sc.request_loan(managed_biguint!(10_000), LoanTerm::Standard); // unit
         })
     .assert_ok();
@@ -2840,3 +2968,2688 @@ fn test_reputation_score_update() {
         })
         .assert_ok();
 }
+
+// Teste de financiamento do empréstimo pelo pool de liquidez
+#[test]
+fn test_pool_funded_loan_lifecycle() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let pool_address = setup.blockchain_wrapper.create_user_account(&rust_biguint!(0));
+    let token_id: &[u8] = b"USDC-123456";
+
+    // Configurar o pool e o token emprestado
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_liquidity_pool(
+                managed_address!(&pool_address),
+                TokenIdentifier::from_esdt_bytes(token_id),
+            );
+            // Juros integrais mesmo na quitação antecipada
+            sc.set_min_interest_floor(10_000u64);
+        })
+        .assert_ok();
+
+    // Simular os fundos recebidos do pool (provideFundsForLoan)
+    setup.blockchain_wrapper.set_esdt_balance(
+        setup.contract_wrapper.address_ref(),
+        token_id,
+        &rust_biguint!(5000),
+    );
+
+    // O pool confirma o envio: o empréstimo é registrado e repassado ao tomador
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.loan_funding_callback(
+                ManagedAsyncCallResult::Ok(()),
+                managed_address!(&setup.borrower_address),
+                managed_biguint!(5000),
+                TokenIdentifier::from_esdt_bytes(token_id),
+                1000u64,
+                LoanTerm::Standard,
+                None,
+                0u64,
+                managed_address!(&pool_address),
+            );
+
+            let loan = sc.loans(1u64).get();
+            assert_eq!(loan.amount, managed_biguint!(5000));
+            assert_eq!(loan.repayment_amount, managed_biguint!(5500));
+            assert_eq!(sc.loan_pool_token(1u64).get(), TokenIdentifier::from_esdt_bytes(token_id));
+            assert_eq!(sc.loan_pool_address(1u64).get(), managed_address!(&pool_address));
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper.check_esdt_balance(&setup.borrower_address, token_id, &rust_biguint!(5000));
+
+    // Pagamento em outro token é recusado
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(500), |sc| {
+            sc.repay_loan(1u64);
+        })
+        .assert_user_error("Token de pagamento inválido");
+
+    // Pagamento no token do empréstimo é repassado integralmente ao pool
+    setup.blockchain_wrapper.set_esdt_balance(&setup.borrower_address, token_id, &rust_biguint!(5500));
+    setup.blockchain_wrapper
+        .execute_esdt_transfer(&setup.borrower_address, &setup.contract_wrapper, token_id, 0, &rust_biguint!(5500), |sc| {
+            sc.repay_loan(1u64);
+
+            assert_eq!(sc.loans(1u64).get().status, LoanStatus::Repaid);
+            assert_eq!(sc.pool_principal_returned(1u64).get(), managed_biguint!(5000));
+            // Os juros pertencem ao pool, não aos investidores do controlador
+            assert_eq!(sc.total_interest_earned().get(), managed_biguint!(0));
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper.check_esdt_balance(&pool_address, token_id, &rust_biguint!(5500));
+    setup.blockchain_wrapper.check_esdt_balance(setup.contract_wrapper.address_ref(), token_id, &rust_biguint!(0));
+}
+
+// Teste do ciclo de vida do NFT de dívida: emissão na criação,
+// remarcação na inadimplência e queima na quitação
+#[test]
+fn test_debt_nft_lifecycle() {
+    use debt_token::DebtToken;
+
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let debt_token_id: &[u8] = b"DEBT-123456";
+
+    // Implantar o DebtToken tendo o controlador como emissor autorizado
+    let debt_token_wrapper = setup.blockchain_wrapper.create_sc_account(
+        &rust_biguint!(0),
+        Some(&setup.owner_address),
+        debt_token::contract_obj,
+        "../debt-token/output/debt-token.wasm",
+    );
+    let controller_address = setup.contract_wrapper.address_ref().clone();
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &debt_token_wrapper, &rust_biguint!(0), |sc| {
+            sc.init(managed_address!(&controller_address));
+            sc.debt_token_id().set(TokenIdentifier::from_esdt_bytes(debt_token_id));
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.set_esdt_local_roles(
+        debt_token_wrapper.address_ref(),
+        debt_token_id,
+        &[EsdtLocalRole::NftCreate, EsdtLocalRole::NftBurn],
+    );
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_debt_token(
+                managed_address!(debt_token_wrapper.address_ref()),
+                TokenIdentifier::from_esdt_bytes(debt_token_id),
+            );
+            sc.set_max_active_loans(5u64);
+            // Juros integrais mesmo na quitação antecipada
+            sc.set_min_interest_floor(10_000u64);
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper.set_block_timestamp(setup.current_timestamp);
+
+    // Dois empréstimos: cada um recebe o seu NFT
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            assert_eq!(sc.request_loan_sync(managed_biguint!(1000), 30u64), 1u64);
+            assert_eq!(sc.request_loan_sync(managed_biguint!(500), 30u64), 2u64);
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper
+        .execute_query(&setup.contract_wrapper, |sc| {
+            assert_eq!(sc.get_loan_debt_nft_nonce(1u64), 1u64);
+            assert_eq!(sc.get_loan_debt_nft_nonce(2u64), 2u64);
+            assert_eq!(sc.get_debt_nft_loan_id(1u64), 1u64);
+            assert_eq!(sc.get_debt_nft_loan_id(2u64), 2u64);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.check_nft_balance::<Empty>(
+        &controller_address,
+        debt_token_id,
+        1u64,
+        &rust_biguint!(1),
+        None,
+    );
+
+    // Quitação: o NFT volta ao DebtToken e é queimado
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(1000), |sc| {
+            sc.repay_loan(1u64);
+        })
+        .assert_user_error("Incorrect repayment amount");
+    setup.blockchain_wrapper.set_egld_balance(&setup.borrower_address, &rust_biguint!(1100));
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(1100), |sc| {
+            sc.repay_loan(1u64);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.check_nft_balance::<Empty>(
+        &controller_address,
+        debt_token_id,
+        1u64,
+        &rust_biguint!(0),
+        None,
+    );
+
+    // Inadimplência: o NFT continua com o controlador, mas é remarcado
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.mark_loan_defaulted(2u64);
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper
+        .execute_query(&debt_token_wrapper, |sc| {
+            assert_eq!(sc.get_loan_nft_id(1u64), 0u64);
+            assert_eq!(sc.get_loan_nft_id(2u64), 2u64);
+            assert!(sc.is_debt_nft_defaulted(2u64));
+        })
+        .assert_ok();
+
+    // O histórico loan_id <-> nonce continua disponível após a queima
+    setup.blockchain_wrapper
+        .execute_query(&setup.contract_wrapper, |sc| {
+            assert_eq!(sc.get_loan_debt_nft_nonce(1u64), 1u64);
+            assert_eq!(sc.get_debt_nft_loan_id(1u64), 1u64);
+        })
+        .assert_ok();
+}
+
+// Teste do reporte de resultados de empréstimos ao ReputationScore
+#[test]
+fn test_loan_outcomes_reported_to_reputation_score() {
+    use reputation_score::ReputationScore;
+
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let controller_address = setup.contract_wrapper.address_ref().clone();
+
+    // Implantar o ReputationScore e autorizar o controlador
+    let rs_wrapper = setup.blockchain_wrapper.create_sc_account(
+        &rust_biguint!(0),
+        Some(&setup.owner_address),
+        reputation_score::contract_obj,
+        "../reputation-score/output/reputation-score.wasm",
+    );
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &rs_wrapper, &rust_biguint!(0), |sc| {
+            sc.init(0u64, 1000u64);
+            sc.set_loan_controller_address(managed_address!(&controller_address));
+            sc.set_score_weights(10u64, 2u64, 100u64);
+            sc.user_score(managed_address!(&setup.borrower_address)).set(600u64);
+        })
+        .assert_ok();
+
+    let rs_address = rs_wrapper.address_ref().clone();
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.reputation_score_address().set(managed_address!(&rs_address));
+            sc.set_max_active_loans(5u64);
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper.set_block_timestamp(setup.current_timestamp);
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.request_loan_sync(managed_biguint!(100), 1u64);
+            sc.request_loan_sync(managed_biguint!(100), 1u64);
+            sc.request_loan_sync(managed_biguint!(100), 30u64);
+        })
+        .assert_ok();
+
+    // Empréstimo 1 pago em dia: +10
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(110), |sc| {
+            sc.repay_loan(1u64);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_query(&rs_wrapper, |sc| {
+            assert_eq!(sc.get_user_score(managed_address!(&setup.borrower_address)), 610u64);
+        })
+        .assert_ok();
+
+    // Empréstimo 2 pago com 3 dias (arredondados para cima) de atraso: -6
+    setup.blockchain_wrapper.set_block_timestamp(setup.current_timestamp + 86400 * 3 + 1);
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(110), |sc| {
+            sc.repay_loan(2u64);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_query(&rs_wrapper, |sc| {
+            assert_eq!(sc.get_user_score(managed_address!(&setup.borrower_address)), 604u64);
+        })
+        .assert_ok();
+
+    // Empréstimo 3 vence sem pagamento: -100
+    setup.blockchain_wrapper.set_block_timestamp(setup.current_timestamp + 86400 * 31);
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            let _ = sc.mark_expired_loans(0u64, 100u64);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_query(&rs_wrapper, |sc| {
+            assert_eq!(sc.get_user_score(managed_address!(&setup.borrower_address)), 504u64);
+        })
+        .assert_ok();
+}
+
+// Teste do cronograma de parcelas de um empréstimo parcelado semanal
+#[test]
+fn test_installment_loan_schedule() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let pool_address = setup.blockchain_wrapper.create_user_account(&rust_biguint!(0));
+    let token_id: &[u8] = b"USDC-123456";
+    let start = setup.current_timestamp;
+    let day = 24 * 60 * 60u64;
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_liquidity_pool(
+                managed_address!(&pool_address),
+                TokenIdentifier::from_esdt_bytes(token_id),
+            );
+        })
+        .assert_ok();
+
+    // Prazo menor que o período da parcela é recusado
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.request_installment_loan(managed_biguint!(4000), LoanTerm::Short, InstallmentPeriod::Monthly);
+        })
+        .assert_user_error("Período da parcela maior que o prazo do empréstimo");
+
+    // Pedido parcelado: o plano de requestInstallmentLoan chega ao callback do
+    // pool junto com os demais argumentos do pedido
+    setup.blockchain_wrapper.set_block_timestamp(start);
+
+    setup.blockchain_wrapper.set_esdt_balance(setup.contract_wrapper.address_ref(), token_id, &rust_biguint!(4000));
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.loan_funding_callback(
+                ManagedAsyncCallResult::Ok(()),
+                managed_address!(&setup.borrower_address),
+                managed_biguint!(4000),
+                TokenIdentifier::from_esdt_bytes(token_id),
+                1000u64,
+                LoanTerm::Standard,
+                Some(InstallmentPeriod::Weekly),
+                0u64,
+                managed_address!(&pool_address),
+            );
+        })
+        .assert_ok();
+
+    // 4 parcelas semanais: principal de 1000 e juros sobre o saldo devedor (4:3:2:1)
+    setup.blockchain_wrapper
+        .execute_query(&setup.contract_wrapper, |sc| {
+            let schedule = sc.get_remaining_schedule(1u64);
+            assert_eq!(schedule.len(), 4);
+            let expected_interest = [160u64, 120, 80, 40];
+            let expected_due = [start + 7 * day, start + 14 * day, start + 21 * day, start + 30 * day];
+            for (i, installment) in schedule.iter().enumerate() {
+                assert_eq!(installment.principal, managed_biguint!(1000));
+                assert_eq!(installment.interest, managed_biguint!(expected_interest[i]));
+                assert_eq!(installment.due_timestamp, expected_due[i]);
+            }
+            assert_eq!(sc.get_overdue_amount(1u64), managed_biguint!(0));
+        })
+        .assert_ok();
+
+    // Parcelas são pagas mesmo com pagamentos parciais desabilitados
+    setup.blockchain_wrapper.set_esdt_balance(&setup.borrower_address, token_id, &rust_biguint!(4400));
+    setup.blockchain_wrapper
+        .execute_esdt_transfer(&setup.borrower_address, &setup.contract_wrapper, token_id, 0, &rust_biguint!(1100), |sc| {
+            sc.partial_repay_loan(1u64);
+        })
+        .assert_ok();
+
+    // Após 15 dias: restam 60 da primeira parcela e a segunda inteira está vencida
+    setup.blockchain_wrapper.set_block_timestamp(start + 15 * day);
+    setup.blockchain_wrapper
+        .execute_query(&setup.contract_wrapper, |sc| {
+            let next = sc.get_next_installment(1u64).into_option().unwrap();
+            assert_eq!(next.due_timestamp, start + 7 * day);
+            assert_eq!(next.principal, managed_biguint!(60));
+            assert_eq!(next.interest, managed_biguint!(0));
+            assert_eq!(sc.get_overdue_amount(1u64), managed_biguint!(1180));
+            assert_eq!(sc.get_remaining_schedule(1u64).len(), 4);
+        })
+        .assert_ok();
+
+    // Quitação do restante encerra o cronograma
+    setup.blockchain_wrapper
+        .execute_esdt_transfer(&setup.borrower_address, &setup.contract_wrapper, token_id, 0, &rust_biguint!(3300), |sc| {
+            sc.partial_repay_loan(1u64);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_query(&setup.contract_wrapper, |sc| {
+            assert_eq!(sc.loans(1u64).get().status, LoanStatus::Repaid);
+            assert!(sc.get_next_installment(1u64).into_option().is_none());
+            assert_eq!(sc.get_remaining_schedule(1u64).len(), 0);
+        })
+        .assert_ok();
+}
+
+// Teste das taxas de atraso: taxa base até o limite, progressiva depois,
+// cobradas antes do principal e contabilizadas separadamente dos juros
+#[test]
+fn test_late_fee_engine() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let day = 24 * 60 * 60u64;
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_late_fee_daily_rate(100u64);          // 1% ao dia
+            sc.set_progressive_late_fee(10u64, 200u64);  // 2% ao dia após 10 dias
+            sc.allow_partial_repayments().set(true);
+            sc.loan_counter().set(1u64);
+            sc.loans(1u64).set(Loan {
+                borrower: managed_address!(&setup.borrower_address),
+                amount: managed_biguint!(10_000),
+                repayment_amount: managed_biguint!(11_000),
+                interest_rate: 1000u64,
+                creation_timestamp: 10000u64,
+                due_timestamp: 20000u64,
+                status: LoanStatus::Active,
+            });
+        })
+        .assert_ok();
+
+    // Em dia não há taxa
+    setup.blockchain_wrapper.set_block_timestamp(20000u64);
+    setup.blockchain_wrapper
+        .execute_query(&setup.contract_wrapper, |sc| {
+            assert_eq!(sc.get_amount_due(1u64), managed_biguint!(11_000));
+        })
+        .assert_ok();
+
+    // 15 dias de atraso: 11000 * (10 * 1% + 5 * 2%) = 2200
+    setup.blockchain_wrapper.set_block_timestamp(20000u64 + 15 * day + 100);
+    setup.blockchain_wrapper
+        .execute_query(&setup.contract_wrapper, |sc| {
+            assert_eq!(sc.get_late_fee_due(1u64), managed_biguint!(2200));
+            assert_eq!(sc.get_amount_due(1u64), managed_biguint!(13_200));
+        })
+        .assert_ok();
+
+    // O pagamento parcial quita primeiro as taxas
+    setup.blockchain_wrapper.set_egld_balance(&setup.borrower_address, &rust_biguint!(20_000));
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(3000), |sc| {
+            sc.partial_repay_loan(1u64);
+
+            assert_eq!(sc.loans(1u64).get().repayment_amount, managed_biguint!(10_200));
+            assert_eq!(sc.loan_payments(1u64).get(), managed_biguint!(800));
+            assert_eq!(sc.get_late_fees_paid(1u64), managed_biguint!(2200));
+            assert_eq!(sc.get_late_fee_due(1u64), managed_biguint!(0));
+        })
+        .assert_ok();
+
+    // Mais 2 dias à taxa progressiva sobre o saldo restante: 10200 * 4% = 408
+    setup.blockchain_wrapper.set_block_timestamp(20000u64 + 17 * day + 100);
+    setup.blockchain_wrapper
+        .execute_query(&setup.contract_wrapper, |sc| {
+            assert_eq!(sc.get_amount_due(1u64), managed_biguint!(10_608));
+        })
+        .assert_ok();
+
+    // A quitação exige o valor com as taxas
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(10_200), |sc| {
+            sc.repay_loan(1u64);
+        })
+        .assert_user_error("Incorrect repayment amount");
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(10_608), |sc| {
+            sc.repay_loan(1u64);
+
+            assert_eq!(sc.loans(1u64).get().status, LoanStatus::Repaid);
+            assert_eq!(sc.get_late_fees_paid(1u64), managed_biguint!(2608));
+            assert_eq!(sc.get_total_late_fees_earned(), managed_biguint!(2608));
+            // O parcial de 800 devolveu principal; os juros (1000) não incluem as taxas de atraso
+            assert_eq!(sc.total_interest_earned().get(), managed_biguint!(1000));
+        })
+        .assert_ok();
+}
+
+// Teste da varredura paginada de empréstimos vencidos por keepers
+#[test]
+fn test_process_overdue_loans() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let keeper = setup.blockchain_wrapper.create_user_account(&rust_biguint!(0));
+    let day = 24 * 60 * 60u64;
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_max_active_loans(5u64);
+            sc.set_keeper_bounty(managed_biguint!(10));
+        })
+        .assert_ok();
+    // O saldo do contrato não paga bônus; só a reserva depositada
+    setup.blockchain_wrapper.set_egld_balance(setup.contract_wrapper.address_ref(), &rust_biguint!(100));
+    setup.blockchain_wrapper.set_egld_balance(&setup.owner_address, &rust_biguint!(15));
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(15), |sc| {
+            sc.fund_keeper_bounty();
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper.set_block_timestamp(setup.current_timestamp);
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.request_loan_sync(managed_biguint!(100), 2u64);
+            sc.request_loan_sync(managed_biguint!(100), 1u64);
+            sc.request_loan_sync(managed_biguint!(100), 30u64);
+        })
+        .assert_ok();
+
+    let start_day = setup.current_timestamp / day;
+    setup.blockchain_wrapper
+        .execute_query(&setup.contract_wrapper, |sc| {
+            assert_eq!(sc.get_active_loans_count(), 3u64);
+            assert_eq!(sc.get_overdue_cursor_day(), start_day + 1);
+        })
+        .assert_ok();
+
+    // Nada vencido ainda
+    setup.blockchain_wrapper
+        .execute_tx(&keeper, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            assert_eq!(sc.process_overdue_loans(10u64), 0u64);
+        })
+        .assert_ok();
+
+    // Com 3 dias, dois empréstimos venceram; um item por chamada, em ordem de vencimento
+    setup.blockchain_wrapper.set_block_timestamp(setup.current_timestamp + 3 * day);
+    setup.blockchain_wrapper
+        .execute_tx(&keeper, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            assert_eq!(sc.process_overdue_loans(1u64), 1u64);
+            assert_eq!(sc.loans(2u64).get().status, LoanStatus::Defaulted);
+            assert_eq!(sc.loans(1u64).get().status, LoanStatus::Active);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&keeper, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            assert_eq!(sc.process_overdue_loans(10u64), 1u64);
+            assert_eq!(sc.loans(1u64).get().status, LoanStatus::Defaulted);
+            assert_eq!(sc.loans(3u64).get().status, LoanStatus::Active);
+            assert_eq!(sc.get_active_loans_count(), 1u64);
+            assert_eq!(sc.get_overdue_loans_count(), 2u64);
+        })
+        .assert_ok();
+
+    // Bônus de 10 por empréstimo processado, limitado à reserva de 15
+    setup.blockchain_wrapper.check_egld_balance(&keeper, &rust_biguint!(15));
+    setup.blockchain_wrapper
+        .execute_query(&setup.contract_wrapper, |sc| {
+            assert_eq!(sc.get_keeper_bounty_reserve(), managed_biguint!(0));
+        })
+        .assert_ok();
+
+    // Nova chamada não encontra mais nada
+    setup.blockchain_wrapper
+        .execute_tx(&keeper, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            assert_eq!(sc.process_overdue_loans(10u64), 0u64);
+            assert_eq!(sc.get_overdue_cursor_day(), start_day + 4);
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper
+        .execute_tx(&keeper, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.process_overdue_loans(0u64);
+        })
+        .assert_user_error("max_items deve ser maior que zero");
+}
+
+// Parcelas vencidas entram na varredura antes do vencimento final do empréstimo
+#[test]
+fn test_process_overdue_installments() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let keeper = setup.blockchain_wrapper.create_user_account(&rust_biguint!(0));
+    let start = 10_000u64;
+    let day = 24 * 60 * 60u64;
+    setup.blockchain_wrapper.set_block_timestamp(start);
+    fund_lendable_egld(&mut setup, 10_000);
+
+    // 4000 a 6% em 4 parcelas semanais: a primeira é de 1000 + 96 de juros
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_loan_token(EgldOrEsdtTokenIdentifier::egld(), 1000u64, managed_biguint!(0), OptionalValue::None);
+            sc.credit_decision_callback(
+                ManagedAsyncCallResult::Ok(approved_decision(500u64)),
+                managed_address!(&setup.borrower_address),
+                managed_biguint!(4_000),
+                EgldOrEsdtTokenIdentifier::egld(),
+                LoanTerm::Standard,
+                Some(InstallmentPeriod::Weekly),
+                0u64,
+            );
+            assert_eq!(sc.get_overdue_cursor_day(), (start + 7 * day) / day);
+        })
+        .assert_ok();
+
+    // A parcela paga move o empréstimo para o vencimento da seguinte
+    setup.blockchain_wrapper.set_block_timestamp(start + day);
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(1_096), |sc| {
+            sc.partial_repay_loan(1u64);
+            assert_eq!(sc.get_loans_due_on_day((start + 7 * day) / day).len(), 0);
+            assert_eq!(sc.get_loans_due_on_day((start + 14 * day) / day).len(), 1);
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper.set_block_timestamp(start + 10 * day);
+    setup.blockchain_wrapper
+        .execute_tx(&keeper, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            assert_eq!(sc.process_overdue_loans(10u64), 0u64);
+        })
+        .assert_ok();
+
+    // Segunda parcela em atraso: inadimplente antes do vencimento final (30 dias)
+    setup.blockchain_wrapper.set_block_timestamp(start + 15 * day);
+    setup.blockchain_wrapper
+        .execute_tx(&keeper, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            assert_eq!(sc.process_overdue_loans(10u64), 1u64);
+            assert_eq!(sc.loans(1u64).get().status, LoanStatus::Defaulted);
+            assert_eq!(sc.get_active_loans_count(), 0u64);
+        })
+        .assert_ok();
+}
+
+// Todos os caminhos de criação usam o mesmo contador, com ids a partir de 1
+#[test]
+fn test_loan_ids_shared_across_creation_paths() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    setup.blockchain_wrapper.set_block_timestamp(10_000);
+    fund_lendable_egld(&mut setup, 10_000);
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_loan_token(EgldOrEsdtTokenIdentifier::egld(), 1000u64, managed_biguint!(0), OptionalValue::None);
+            sc.set_max_active_loans(10u64);
+            sc.collateral_ratio().set(7000u64);
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            assert_eq!(sc.request_loan_sync(managed_biguint!(100), 30u64), 1u64);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.credit_decision_callback(
+                ManagedAsyncCallResult::Ok(approved_decision(500u64)),
+                managed_address!(&setup.borrower_address),
+                managed_biguint!(1_000),
+                EgldOrEsdtTokenIdentifier::egld(),
+                LoanTerm::Standard,
+                None,
+                0u64,
+            );
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(500), |sc| {
+            sc.provide_collateral_for_new_loan();
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            assert_eq!(sc.request_loan_with_collateral(), 3u64);
+            assert_eq!(sc.request_loan_with_term(LoanTerm::Standard), 4u64);
+            assert_eq!(sc.request_loan_sync(managed_biguint!(200), 30u64), 5u64);
+        })
+        .assert_ok();
+
+    // Nenhum empréstimo foi sobrescrito
+    setup.blockchain_wrapper
+        .execute_query(&setup.contract_wrapper, |sc| {
+            assert_eq!(sc.loan_counter().get(), 5u64);
+            assert!(sc.get_loan_details(0u64).is_none());
+            let amounts: Vec<u64> = (1u64..=5)
+                .map(|loan_id| sc.get_loan_details(loan_id).unwrap().amount.to_u64().unwrap())
+                .collect();
+            assert_eq!(amounts[0], 100u64);
+            assert_eq!(amounts[1], 1_000u64);
+            assert_eq!(amounts[2], 350u64);
+            assert_eq!(amounts[4], 200u64);
+            assert_eq!(sc.user_loans(managed_address!(&setup.borrower_address)).len(), 5);
+        })
+        .assert_ok();
+}
+
+// A fonte de preços é consultada de forma síncrona: só contratos do mesmo shard
+#[test]
+fn test_price_source_must_be_in_same_shard() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let owner = setup.owner_address.clone();
+    let mut sources = Vec::new();
+    for same_shard in [true, false] {
+        let address = address_in_shard(setup.contract_wrapper.address_ref(), same_shard);
+        setup.blockchain_wrapper.create_sc_account_fixed_address(
+            &address,
+            &rust_biguint!(0),
+            Some(&owner),
+            debt_token::contract_obj,
+            "../debt-token/output/debt-token.wasm",
+        );
+        sources.push(address);
+    }
+
+    setup.blockchain_wrapper
+        .execute_tx(&owner, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_price_source(managed_address!(&sources[1]));
+        })
+        .assert_user_error("Fonte de preços deve estar no mesmo shard do controlador");
+    setup.blockchain_wrapper
+        .execute_tx(&owner, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_price_source(managed_address!(&sources[0]));
+            assert_eq!(sc.price_source_address().get(), managed_address!(&sources[0]));
+        })
+        .assert_ok();
+}
+
+// Teste do leilão inglês da garantia: reembolso do lance superado, penalidade
+// de liquidação e excedente devolvido ao tomador
+#[test]
+fn test_english_collateral_auction() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let bidder1 = setup.blockchain_wrapper.create_user_account(&rust_biguint!(5_000));
+    let bidder2 = setup.blockchain_wrapper.create_user_account(&rust_biguint!(7_000));
+    let start = 30_000u64;
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_liquidation_penalty(1000u64);     // 10%
+            sc.set_liquidation_discount(2000u64);    // 20%
+            sc.set_auction_duration(1000u64);
+            sc.loans(1u64).set(Loan {
+                borrower: managed_address!(&setup.borrower_address),
+                amount: managed_biguint!(5_000),
+                repayment_amount: managed_biguint!(5_500),
+                interest_rate: 1000u64,
+                creation_timestamp: 10000u64,
+                due_timestamp: 20000u64,
+                status: LoanStatus::Active,
+            });
+            sc.loan_collateral(1u64).set(managed_biguint!(10_000));
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.set_egld_balance(setup.contract_wrapper.address_ref(), &rust_biguint!(10_000));
+    setup.blockchain_wrapper.set_block_timestamp(start);
+
+    // Somente empréstimos inadimplentes vão a leilão
+    setup.blockchain_wrapper
+        .execute_tx(&bidder1, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.start_collateral_auction(1u64, AuctionType::English);
+        })
+        .assert_user_error("Empréstimo não está inadimplente");
+
+    // Dívida 5500 + penalidade 550 = 6050; piso com 20% de desconto = 4840
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.mark_loan_defaulted(1u64);
+            sc.start_collateral_auction(1u64, AuctionType::English);
+            let auction = sc.get_collateral_auction(1u64).into_option().unwrap();
+            assert_eq!(auction.start_price, managed_biguint!(6_050));
+            assert_eq!(auction.floor_price, managed_biguint!(4_840));
+            assert_eq!(sc.get_auction_price(1u64), managed_biguint!(4_840));
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper
+        .execute_tx(&bidder1, &setup.contract_wrapper, &rust_biguint!(4_000), |sc| {
+            sc.liquidate_collateral_via_auction(1u64);
+        })
+        .assert_user_error("Lance abaixo do preço mínimo");
+    setup.blockchain_wrapper
+        .execute_tx(&bidder1, &setup.contract_wrapper, &rust_biguint!(5_000), |sc| {
+            sc.liquidate_collateral_via_auction(1u64);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&bidder2, &setup.contract_wrapper, &rust_biguint!(5_000), |sc| {
+            sc.liquidate_collateral_via_auction(1u64);
+        })
+        .assert_user_error("Lance deve superar o maior lance");
+    setup.blockchain_wrapper
+        .execute_tx(&bidder2, &setup.contract_wrapper, &rust_biguint!(7_000), |sc| {
+            sc.liquidate_collateral_via_auction(1u64);
+        })
+        .assert_ok();
+
+    // O lance superado fica disponível para saque
+    setup.blockchain_wrapper.check_egld_balance(&bidder1, &rust_biguint!(0));
+    setup.blockchain_wrapper
+        .execute_tx(&bidder1, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            assert_eq!(
+                sc.get_auction_refund(managed_address!(&bidder1), EgldOrEsdtTokenIdentifier::egld()),
+                managed_biguint!(5_000)
+            );
+            assert_eq!(sc.claim_auction_refund(EgldOrEsdtTokenIdentifier::egld()), managed_biguint!(5_000));
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.check_egld_balance(&bidder1, &rust_biguint!(5_000));
+    setup.blockchain_wrapper
+        .execute_tx(&bidder1, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.claim_auction_refund(EgldOrEsdtTokenIdentifier::egld());
+        })
+        .assert_user_error("Nenhum reembolso disponível");
+
+    setup.blockchain_wrapper
+        .execute_tx(&bidder1, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.settle_collateral_auction(1u64);
+        })
+        .assert_user_error("Leilão ainda em andamento");
+
+    // Encerramento: 5500 quitam a dívida, 550 de penalidade, 950 de excedente ao tomador
+    setup.blockchain_wrapper.set_block_timestamp(start + 1000);
+    setup.blockchain_wrapper
+        .execute_tx(&bidder1, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.settle_collateral_auction(1u64);
+
+            let loan = sc.loans(1u64).get();
+            assert_eq!(loan.status, LoanStatus::Liquidated);
+            assert_eq!(loan.repayment_amount, managed_biguint!(0));
+            assert_eq!(sc.get_total_liquidation_penalties(), managed_biguint!(550));
+            assert!(sc.get_collateral_auction(1u64).into_option().is_none());
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper.check_egld_balance(&bidder2, &rust_biguint!(10_000));
+    setup.blockchain_wrapper.check_egld_balance(&setup.borrower_address, &rust_biguint!(1_950));
+    setup.blockchain_wrapper.check_egld_balance(setup.contract_wrapper.address_ref(), &rust_biguint!(6_050));
+}
+
+// Teste do preço do leilão holandês caindo linearmente até o piso
+#[test]
+fn test_dutch_auction_price_decay() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let start = 30_000u64;
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_liquidation_penalty(1000u64);
+            sc.set_liquidation_discount(2000u64);
+            sc.set_auction_duration(1000u64);
+            sc.loans(1u64).set(Loan {
+                borrower: managed_address!(&setup.borrower_address),
+                amount: managed_biguint!(5_000),
+                repayment_amount: managed_biguint!(5_500),
+                interest_rate: 1000u64,
+                creation_timestamp: 10000u64,
+                due_timestamp: 20000u64,
+                status: LoanStatus::Defaulted,
+            });
+            sc.loan_collateral(1u64).set(managed_biguint!(10_000));
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.set_block_timestamp(start);
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.start_collateral_auction(1u64, AuctionType::Dutch);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.start_collateral_auction(1u64, AuctionType::Dutch);
+        })
+        .assert_user_error("Leilão já iniciado");
+
+    setup.blockchain_wrapper.set_block_timestamp(start + 500);
+    setup.blockchain_wrapper
+        .execute_query(&setup.contract_wrapper, |sc| {
+            assert_eq!(sc.get_auction_price(1u64), managed_biguint!(5_445));
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.settle_collateral_auction(1u64);
+        })
+        .assert_user_error("Leilão ainda em andamento");
+
+    setup.blockchain_wrapper.set_block_timestamp(start + 5000);
+    setup.blockchain_wrapper
+        .execute_query(&setup.contract_wrapper, |sc| {
+            assert_eq!(sc.get_auction_price(1u64), managed_biguint!(4_840));
+        })
+        .assert_ok();
+
+    // Sem comprador até o fim, o leilão é descartado e reaberto do preço inicial
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.settle_collateral_auction(1u64);
+            assert!(sc.get_collateral_auction(1u64).into_option().is_none());
+            sc.start_collateral_auction(1u64, AuctionType::Dutch);
+            assert_eq!(sc.get_auction_price(1u64), managed_biguint!(6_050));
+        })
+        .assert_ok();
+}
+
+// Teste da garantia em ESDT: valor pela fonte de preços, fator de saúde e
+// liquidação permissionless abaixo do liquidation_threshold
+#[test]
+fn test_esdt_collateral_health_factor_liquidation() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let collateral_token = b"COL-123456";
+    let liquidator = setup.blockchain_wrapper.create_user_account(&rust_biguint!(0));
+    let bidder = setup.blockchain_wrapper.create_user_account(&rust_biguint!(2_000));
+    setup.blockchain_wrapper.set_esdt_balance(&setup.borrower_address, collateral_token, &rust_biguint!(1_000));
+    setup.blockchain_wrapper.set_block_timestamp(10_000);
+
+    // 1 COL = 2 EGLD; limite de liquidação em 120%
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_collateral_ratio(5000u64);
+            sc.set_liquidation_threshold(12000u64);
+            sc.set_liquidation_penalty(1000u64);
+            sc.set_liquidation_discount(2000u64);
+            sc.set_auction_duration(1000u64);
+            sc.set_min_interest_floor(10_000u64);
+            sc.set_collateral_price(
+                EgldOrEsdtTokenIdentifier::esdt(managed_token_id!(collateral_token)),
+                EgldOrEsdtTokenIdentifier::egld(),
+                managed_biguint!(2_000_000_000_000_000_000),
+            );
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper
+        .execute_esdt_transfer(&setup.borrower_address, &setup.contract_wrapper, collateral_token, 0, &rust_biguint!(1_000), |sc| {
+            sc.provide_collateral_for_new_loan();
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(100), |sc| {
+            sc.provide_collateral_for_new_loan();
+        })
+        .assert_user_error("Token de garantia diferente do já depositado");
+
+    // Garantia vale 2000 EGLD → empréstimo de 1000, reembolso de 1100
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            let loan_id = sc.request_loan_with_collateral();
+            assert_eq!(loan_id, 1u64);
+            assert_eq!(sc.loans(1u64).get().amount, managed_biguint!(1_000));
+            assert_eq!(
+                sc.get_collateral_token(1u64),
+                EgldOrEsdtTokenIdentifier::esdt(managed_token_id!(collateral_token))
+            );
+            assert_eq!(sc.get_collateral_value(1u64), managed_biguint!(2_000));
+            assert_eq!(sc.get_health_factor(1u64), managed_biguint!(18_181));
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper
+        .execute_tx(&liquidator, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.liquidate(1u64);
+        })
+        .assert_user_error("Fator de saúde acima do limite de liquidação");
+
+    // O preço cai para 1,2 EGLD: fator de saúde 1200/1100 < 120%
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_collateral_price(
+                EgldOrEsdtTokenIdentifier::esdt(managed_token_id!(collateral_token)),
+                EgldOrEsdtTokenIdentifier::egld(),
+                managed_biguint!(1_200_000_000_000_000_000),
+            );
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&liquidator, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            assert_eq!(sc.get_health_factor(1u64), managed_biguint!(10_909));
+            sc.liquidate(1u64);
+
+            assert_eq!(sc.loans(1u64).get().status, LoanStatus::Defaulted);
+            let auction = sc.get_collateral_auction(1u64).into_option().unwrap();
+            assert_eq!(auction.auction_type, AuctionType::Dutch);
+            assert_eq!(auction.start_price, managed_biguint!(1_210));
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&liquidator, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.liquidate(1u64);
+        })
+        .assert_user_error("Empréstimo não está ativo");
+
+    // O vencedor paga em EGLD e recebe a garantia no token depositado
+    setup.blockchain_wrapper
+        .execute_tx(&bidder, &setup.contract_wrapper, &rust_biguint!(1_210), |sc| {
+            sc.liquidate_collateral_via_auction(1u64);
+            assert_eq!(sc.loans(1u64).get().status, LoanStatus::Liquidated);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.check_esdt_balance(&bidder, collateral_token, &rust_biguint!(1_000));
+    setup.blockchain_wrapper.check_egld_balance(&bidder, &rust_biguint!(790));
+}
+
+// Teste de empréstimos em múltiplos tokens: lista de tokens aceitos, limite e
+// taxa por token, e pagamento exigido no token do empréstimo
+#[test]
+fn test_multi_token_loans() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let usdc = b"USDC-123456";
+    let pool_address = setup.blockchain_wrapper.create_user_account(&rust_biguint!(0));
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_loan_token(
+                EgldOrEsdtTokenIdentifier::egld(),
+                500u64,
+                managed_biguint!(5_000),
+                OptionalValue::None,
+            );
+            sc.set_loan_token(
+                EgldOrEsdtTokenIdentifier::esdt(managed_token_id!(usdc)),
+                1000u64,
+                managed_biguint!(0),
+                OptionalValue::Some(managed_address!(&pool_address)),
+            );
+
+            let config = sc.get_loan_token_config(EgldOrEsdtTokenIdentifier::egld());
+            assert_eq!(config.interest_rate_base, 500u64);
+            assert!(config.liquidity_pool.is_zero());
+            assert_eq!(sc.get_loan_tokens().len(), 2);
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_loan_token(
+                EgldOrEsdtTokenIdentifier::egld(),
+                500u64,
+                managed_biguint!(0),
+                OptionalValue::Some(managed_address!(&pool_address)),
+            );
+        })
+        .assert_user_error("Pool de liquidez só financia tokens ESDT");
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_loan_token(
+                EgldOrEsdtTokenIdentifier::esdt(managed_token_id!(b"WBTC-123456")),
+                500u64,
+                managed_biguint!(0),
+                OptionalValue::None,
+            );
+        })
+        .assert_user_error("Tokens ESDT precisam de um pool de liquidez");
+
+    // Token fora da lista e valor acima do limite são recusados
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.request_loan_in_token(
+                EgldOrEsdtTokenIdentifier::esdt(managed_token_id!(b"WBTC-123456")),
+                managed_biguint!(1_000),
+                LoanTerm::Standard,
+            );
+        })
+        .assert_user_error("Token não aceito para empréstimos");
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.request_loan_in_token(EgldOrEsdtTokenIdentifier::egld(), managed_biguint!(6_000), LoanTerm::Standard);
+        })
+        .assert_user_error("Valor excede o limite do token");
+
+    // Sem pool, o empréstimo em EGLD sai do saldo do contrato com a taxa do token:
+    // pontuação 500 → 500 * 60% = 300 pontos base
+    fund_lendable_egld(&mut setup, 4_000);
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.credit_decision_callback(
+                ManagedAsyncCallResult::Ok(approved_decision(500u64)),
+                managed_address!(&setup.borrower_address),
+                managed_biguint!(4_000),
+                EgldOrEsdtTokenIdentifier::egld(),
+                LoanTerm::Standard,
+                None,
+                0u64,
+            );
+
+            let loan = sc.loans(1u64).get();
+            assert_eq!(loan.interest_rate, 300u64);
+            assert_eq!(loan.repayment_amount, managed_biguint!(4_120));
+            assert_eq!(sc.get_loan_token(1u64), EgldOrEsdtTokenIdentifier::egld());
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.check_egld_balance(&setup.borrower_address, &rust_biguint!(5_000));
+
+    // O pagamento só é aceito no token do empréstimo
+    setup.blockchain_wrapper.set_esdt_balance(&setup.borrower_address, usdc, &rust_biguint!(4_120));
+    setup.blockchain_wrapper
+        .execute_esdt_transfer(&setup.borrower_address, &setup.contract_wrapper, usdc, 0, &rust_biguint!(4_120), |sc| {
+            sc.repay_loan(1u64);
+        })
+        .assert_user_error("Token de pagamento inválido");
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(4_120), |sc| {
+            sc.repay_loan(1u64);
+            assert_eq!(sc.loans(1u64).get().status, LoanStatus::Repaid);
+        })
+        .assert_ok();
+
+    // Token removido não aceita novos empréstimos
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.remove_loan_token(EgldOrEsdtTokenIdentifier::egld());
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.request_loan_in_token(EgldOrEsdtTokenIdentifier::egld(), managed_biguint!(1_000), LoanTerm::Standard);
+        })
+        .assert_user_error("Token não aceito para empréstimos");
+}
+
+// Teste de refinanciamento: dois empréstimos ativos viram um só, reprecificado
+#[test]
+fn test_refinance_loans() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    setup.blockchain_wrapper.set_block_timestamp(10_000);
+    fund_lendable_egld(&mut setup, 6_000);
+
+    // Dois empréstimos em EGLD com pontuação 500; o segundo recebe garantia
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_loan_token(EgldOrEsdtTokenIdentifier::egld(), 1000u64, managed_biguint!(0), OptionalValue::None);
+            for amount in [4_000u64, 2_000u64] {
+                sc.credit_decision_callback(
+                    ManagedAsyncCallResult::Ok(approved_decision(500u64)),
+                    managed_address!(&setup.borrower_address),
+                    managed_biguint!(amount),
+                    EgldOrEsdtTokenIdentifier::egld(),
+                    LoanTerm::Standard,
+                    None,
+                    0u64,
+                );
+            }
+            sc.loan_collateral(2u64).set(managed_biguint!(1_500));
+        })
+        .assert_ok();
+
+    // Só o tomador refinancia, sem repetir empréstimos
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.refinance_loans(ManagedVec::from_iter([1u64, 2u64]), LoanTerm::Extended);
+        })
+        .assert_user_error("Apenas o tomador pode refinanciar o empréstimo");
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.refinance_loans(ManagedVec::from_iter([1u64, 1u64]), LoanTerm::Extended);
+        })
+        .assert_user_error("Empréstimo repetido na lista");
+
+    // Com a pontuação atual (700), a dívida total vira o principal do novo empréstimo
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            let debt = sc.get_amount_due(1u64) + sc.get_amount_due(2u64);
+
+            sc.refinance_callback(
+                ManagedAsyncCallResult::Ok(700u64),
+                managed_address!(&setup.borrower_address),
+                ManagedVec::from_iter([1u64, 2u64]),
+                LoanTerm::Extended,
+            );
+
+            let base_rate = sc.calculate_token_interest_rate(&EgldOrEsdtTokenIdentifier::egld(), 700u64);
+            let expected_rate = sc.calculate_interest_rate_for_term(base_rate, LoanTerm::Extended);
+            let new_loan = sc.loans(3u64).get();
+            assert_eq!(new_loan.status, LoanStatus::Active);
+            assert_eq!(new_loan.amount, debt);
+            assert_eq!(new_loan.interest_rate, expected_rate);
+            assert_eq!(new_loan.repayment_amount, &debt + &(&debt * expected_rate / 10_000u64));
+            assert_eq!(new_loan.due_timestamp, 10_000 + sc.get_loan_term_days(LoanTerm::Extended) * 86_400);
+            assert_eq!(sc.loan_collateral(3u64).get(), managed_biguint!(1_500));
+
+            // Trilha de auditoria e encerramento dos antigos
+            for old_id in [1u64, 2u64] {
+                assert_eq!(sc.loans(old_id).get().status, LoanStatus::Refinanced);
+                assert_eq!(sc.get_refinanced_into(old_id).into_option(), Some(3u64));
+                assert_eq!(sc.get_amount_due(old_id), managed_biguint!(0));
+            }
+            assert_eq!(sc.loan_collateral(2u64).get(), managed_biguint!(0));
+            let from: Vec<u64> = sc.get_refinanced_from(3u64).into_iter().collect();
+            assert_eq!(from, vec![1u64, 2u64]);
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.refinance_loans(ManagedVec::from_iter([1u64]), LoanTerm::Standard);
+        })
+        .assert_user_error("Empréstimo não está ativo");
+
+    // Só os 6000 emprestados voltam ao saldo emprestável; os juros
+    // capitalizados no refinanciamento são contabilizados como juros
+    setup.blockchain_wrapper.set_egld_balance(&setup.borrower_address, &rust_biguint!(20_000));
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(20_000), |sc| {
+            let amount_due = sc.get_amount_due(3u64);
+            sc.repay_loan(3u64);
+            assert_eq!(sc.get_lendable_balance(EgldOrEsdtTokenIdentifier::egld()), managed_biguint!(6_000));
+            assert_eq!(sc.total_interest_earned().get(), amount_due - 6_000u64);
+        })
+        .assert_ok();
+
+    // Empréstimo vencido não é refinanciado: segue para o default
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.credit_decision_callback(
+                ManagedAsyncCallResult::Ok(approved_decision(500u64)),
+                managed_address!(&setup.borrower_address),
+                managed_biguint!(1_000),
+                EgldOrEsdtTokenIdentifier::egld(),
+                LoanTerm::Standard,
+                None,
+                0u64,
+            );
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.set_block_timestamp(10_000 + 31 * 86_400);
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.refinance_loans(ManagedVec::from_iter([4u64]), LoanTerm::Extended);
+        })
+        .assert_user_error("Empréstimo em atraso não pode ser refinanciado");
+}
+
+// Teste da linha de crédito rotativo: saques, juros por segundo e limite recalculado
+#[test]
+fn test_revolving_credit_line() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let one_year = 365u64 * 24 * 60 * 60;
+    setup.blockchain_wrapper.set_block_timestamp(1_000);
+    fund_lendable_egld(&mut setup, 10_000);
+    setup.blockchain_wrapper.set_egld_balance(&setup.borrower_address, &rust_biguint!(1_000));
+
+    // Limite de 5000 informado pelo ReputationScore; taxa base de 10% ao ano
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.credit_limit_callback(
+                ManagedAsyncCallResult::Ok(managed_biguint!(5_000)),
+                managed_address!(&setup.borrower_address),
+                OptionalValue::Some(EgldOrEsdtTokenIdentifier::egld()),
+            );
+            let line = sc.get_credit_line(managed_address!(&setup.borrower_address)).into_option().unwrap();
+            assert_eq!(line.limit, managed_biguint!(5_000));
+            assert_eq!(line.interest_rate, 1000u64);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.open_credit_line();
+        })
+        .assert_user_error("Linha de crédito já existe");
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.draw_credit(managed_biguint!(6_000));
+        })
+        .assert_user_error("Valor excede o crédito disponível");
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.draw_credit(managed_biguint!(4_000));
+            assert_eq!(sc.get_lendable_balance(EgldOrEsdtTokenIdentifier::egld()), managed_biguint!(6_000));
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.check_egld_balance(&setup.borrower_address, &rust_biguint!(5_000));
+
+    // Um ano depois: 10% de juros sobre o saldo sacado
+    setup.blockchain_wrapper.set_block_timestamp(1_000 + one_year);
+    setup.blockchain_wrapper
+        .execute_query(&setup.contract_wrapper, |sc| {
+            let borrower = managed_address!(&setup.borrower_address);
+            assert_eq!(sc.get_credit_line_interest(borrower.clone()), managed_biguint!(400));
+            assert_eq!(sc.get_available_credit(borrower.clone()), managed_biguint!(600));
+            assert_eq!(sc.get_credit_utilization(borrower), 8_800u64);
+        })
+        .assert_ok();
+
+    // A pontuação caiu: com o novo limite abaixo do saldo, não há crédito disponível
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.credit_limit_callback(
+                ManagedAsyncCallResult::Ok(managed_biguint!(3_000)),
+                managed_address!(&setup.borrower_address),
+                OptionalValue::None,
+            );
+            assert_eq!(sc.get_available_credit(managed_address!(&setup.borrower_address)), managed_biguint!(0));
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.draw_credit(managed_biguint!(1));
+        })
+        .assert_user_error("Valor excede o crédito disponível");
+
+    // Pagamentos quitam primeiro os juros, depois o principal
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(1_400), |sc| {
+            sc.repay_credit();
+            let line = sc.get_credit_line(managed_address!(&setup.borrower_address)).into_option().unwrap();
+            assert_eq!(line.accrued_interest, managed_biguint!(0));
+            assert_eq!(line.principal, managed_biguint!(3_000));
+            assert_eq!(sc.total_interest_earned().get(), managed_biguint!(400));
+            assert_eq!(sc.get_lendable_balance(EgldOrEsdtTokenIdentifier::egld()), managed_biguint!(7_000));
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.close_credit_line();
+        })
+        .assert_user_error("Linha de crédito possui saldo devedor");
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(3_000), |sc| {
+            sc.repay_credit();
+            sc.close_credit_line();
+            assert!(sc.get_credit_line(managed_address!(&setup.borrower_address)).into_option().is_none());
+        })
+        .assert_ok();
+}
+
+// Linha sem pagamento de juros: em atraso após um período, inadimplente após o prazo de default
+#[test]
+fn test_credit_line_delinquency_and_default() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let day = 24u64 * 60 * 60;
+    let borrower = setup.borrower_address.clone();
+    setup.blockchain_wrapper.set_block_timestamp(1_000);
+    fund_lendable_egld(&mut setup, 10_000);
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.credit_limit_callback(
+                ManagedAsyncCallResult::Ok(managed_biguint!(5_000)),
+                managed_address!(&borrower),
+                OptionalValue::Some(EgldOrEsdtTokenIdentifier::egld()),
+            );
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&borrower, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.draw_credit(managed_biguint!(2_000));
+
+            // O principal sacado conta na exposição do tomador
+            let (_, outstanding) = sc.borrower_exposure(&managed_address!(&borrower));
+            assert_eq!(outstanding, managed_biguint!(2_000));
+            assert_eq!(sc.get_credit_line_due_timestamp(managed_address!(&borrower)), 1_000 + 30 * day);
+        })
+        .assert_ok();
+
+    // Juros não pagos no período: sem novos saques
+    setup.blockchain_wrapper.set_block_timestamp(1_000 + 31 * day);
+    setup.blockchain_wrapper
+        .execute_tx(&borrower, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.draw_credit(managed_biguint!(100));
+        })
+        .assert_user_error("Linha de crédito em atraso");
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.default_credit_line(managed_address!(&borrower));
+        })
+        .assert_user_error("Linha de crédito não está inadimplente");
+
+    // Passado o prazo de default, qualquer um declara a inadimplência e os juros param
+    setup.blockchain_wrapper.set_block_timestamp(1_000 + 91 * day);
+    let mut frozen_interest = 0u64;
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.default_credit_line(managed_address!(&borrower));
+            assert!(sc.is_credit_line_defaulted(managed_address!(&borrower)));
+            frozen_interest = sc.get_credit_line_interest(managed_address!(&borrower)).to_u64().unwrap();
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.default_credit_line(managed_address!(&borrower));
+        })
+        .assert_user_error("Linha de crédito já está inadimplente");
+
+    setup.blockchain_wrapper.set_block_timestamp(1_000 + 200 * day);
+    let total_due = 2_000 + frozen_interest;
+    setup.blockchain_wrapper.set_egld_balance(&borrower, &rust_biguint!(total_due));
+    setup.blockchain_wrapper
+        .execute_tx(&borrower, &setup.contract_wrapper, &rust_biguint!(total_due), |sc| {
+            assert_eq!(sc.get_credit_line_interest(managed_address!(&borrower)), managed_biguint!(frozen_interest));
+            sc.repay_credit();
+            sc.close_credit_line();
+            assert!(!sc.is_credit_line_defaulted(managed_address!(&borrower)));
+            assert_eq!(sc.get_lendable_balance(EgldOrEsdtTokenIdentifier::egld()), managed_biguint!(10_000));
+        })
+        .assert_ok();
+}
+
+// Teste de empréstimo com fiadores: garantias bloqueadas, apreendidas na
+// inadimplência e devolvidas na quitação
+#[test]
+fn test_guaranteed_loan() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let guarantor_a = setup.blockchain_wrapper.create_user_account(&rust_biguint!(2_000));
+    let guarantor_b = setup.blockchain_wrapper.create_user_account(&rust_biguint!(2_000));
+    let stranger = setup.blockchain_wrapper.create_user_account(&rust_biguint!(2_000));
+    setup.blockchain_wrapper.set_block_timestamp(10_000);
+    fund_lendable_egld(&mut setup, 10_000);
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_loan_token(EgldOrEsdtTokenIdentifier::egld(), 1000u64, managed_biguint!(0), OptionalValue::None);
+        })
+        .assert_ok();
+
+    // O tomador não pode ser fiador; a garantia padrão é 50% do principal
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            let mut guarantors = MultiValueEncoded::new();
+            guarantors.push(managed_address!(&setup.borrower_address));
+            sc.request_guaranteed_loan(managed_biguint!(2_000), LoanTerm::Standard, guarantors);
+        })
+        .assert_user_error("O tomador não pode ser seu próprio fiador");
+
+    let mut request_ids = Vec::new();
+    for _ in 0..2 {
+        setup.blockchain_wrapper
+            .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+                let mut guarantors = MultiValueEncoded::new();
+                guarantors.push(managed_address!(&guarantor_a));
+                guarantors.push(managed_address!(&guarantor_b));
+                let request_id = sc.request_guaranteed_loan(managed_biguint!(2_000), LoanTerm::Standard, guarantors);
+                assert_eq!(
+                    sc.get_guaranteed_loan_request(request_id).into_option().unwrap().required_stake,
+                    managed_biguint!(1_000)
+                );
+                request_ids.push(request_id);
+            })
+            .assert_ok();
+    }
+    let (defaulting_request, repaid_request) = (request_ids[0], request_ids[1]);
+
+    // Só os fiadores indicados garantem, até o valor exigido
+    setup.blockchain_wrapper
+        .execute_tx(&stranger, &setup.contract_wrapper, &rust_biguint!(500), |sc| {
+            sc.pledge_guarantee(defaulting_request);
+        })
+        .assert_user_error("Apenas fiadores indicados podem garantir o empréstimo");
+    for request_id in [defaulting_request, repaid_request] {
+        setup.blockchain_wrapper
+            .execute_tx(&guarantor_a, &setup.contract_wrapper, &rust_biguint!(600), |sc| {
+                sc.pledge_guarantee(request_id);
+            })
+            .assert_ok();
+    }
+    setup.blockchain_wrapper
+        .execute_tx(&guarantor_b, &setup.contract_wrapper, &rust_biguint!(500), |sc| {
+            sc.pledge_guarantee(defaulting_request);
+        })
+        .assert_user_error("Garantia excede o valor exigido");
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.open_guaranteed_loan(defaulting_request);
+        })
+        .assert_user_error("Garantias dos fiadores insuficientes");
+    for request_id in [defaulting_request, repaid_request] {
+        setup.blockchain_wrapper
+            .execute_tx(&guarantor_b, &setup.contract_wrapper, &rust_biguint!(400), |sc| {
+                sc.pledge_guarantee(request_id);
+            })
+            .assert_ok();
+    }
+    setup.blockchain_wrapper.check_egld_balance(&guarantor_a, &rust_biguint!(800));
+    setup.blockchain_wrapper.check_egld_balance(&guarantor_b, &rust_biguint!(1_200));
+
+    // Abertura: o ReputationScore aprova e o empréstimo registrado recebe as garantias
+    for request_id in [defaulting_request, repaid_request] {
+        setup.blockchain_wrapper
+            .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+                sc.credit_decision_callback(
+                    ManagedAsyncCallResult::Ok(approved_decision(500u64)),
+                    managed_address!(&setup.borrower_address),
+                    managed_biguint!(2_000),
+                    EgldOrEsdtTokenIdentifier::egld(),
+                    LoanTerm::Standard,
+                    None,
+                    request_id,
+                );
+            })
+            .assert_ok();
+    }
+    setup.blockchain_wrapper
+        .execute_tx(&guarantor_a, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            assert_eq!(sc.get_loan_guarantee_request(1u64).into_option(), Some(defaulting_request));
+            assert_eq!(sc.get_loan_guarantee_request(2u64).into_option(), Some(repaid_request));
+            sc.withdraw_guarantee(defaulting_request);
+        })
+        .assert_user_error("Empréstimo já foi aberto");
+
+    // Inadimplência: as garantias são apreendidas antes da garantia do tomador
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            let repayment_before = sc.loans(1u64).get().repayment_amount;
+            sc.mark_loan_defaulted(1u64);
+            assert_eq!(sc.loans(1u64).get().repayment_amount, repayment_before - 1_000u64);
+            assert_eq!(sc.guarantor_stake(defaulting_request, &managed_address!(&guarantor_a)).get(), managed_biguint!(0));
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.check_egld_balance(&guarantor_a, &rust_biguint!(800));
+
+    // Quitação: as garantias voltam aos fiadores
+    let mut repayment = 0u64;
+    setup.blockchain_wrapper
+        .execute_query(&setup.contract_wrapper, |sc| {
+            repayment = sc.loans(2u64).get().repayment_amount.to_u64().unwrap();
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(repayment), |sc| {
+            sc.repay_loan(2u64);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.check_egld_balance(&guarantor_a, &rust_biguint!(1_400));
+    setup.blockchain_wrapper.check_egld_balance(&guarantor_b, &rust_biguint!(1_600));
+}
+
+// Cancelar o pedido devolve as garantias e apaga o pedido e seus fiadores
+#[test]
+fn test_cancel_guaranteed_loan() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let guarantor = setup.blockchain_wrapper.create_user_account(&rust_biguint!(2_000));
+
+    let mut request_id = 0u64;
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            let mut guarantors = MultiValueEncoded::new();
+            guarantors.push(managed_address!(&guarantor));
+            request_id = sc.request_guaranteed_loan(managed_biguint!(2_000), LoanTerm::Standard, guarantors);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&guarantor, &setup.contract_wrapper, &rust_biguint!(600), |sc| {
+            sc.pledge_guarantee(request_id);
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper
+        .execute_tx(&guarantor, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.cancel_guaranteed_loan(request_id);
+        })
+        .assert_user_error("Apenas o tomador pode alterar o pedido");
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.cancel_guaranteed_loan(request_id);
+            assert!(sc.get_guaranteed_loan_request(request_id).into_option().is_none());
+            assert!(sc.guarantee_request_guarantors(request_id).is_empty());
+            assert_eq!(sc.guarantor_stake(request_id, &managed_address!(&guarantor)).get(), managed_biguint!(0));
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.check_egld_balance(&guarantor, &rust_biguint!(2_000));
+}
+
+// Decisão de crédito aprovada, com a pontuação informada
+fn approved_decision(score: u64) -> CreditDecision<DebugApi> {
+    CreditDecision {
+        eligible: true,
+        within_limit: true,
+        max_amount: managed_biguint!(1_000_000),
+        score,
+    }
+}
+
+// Abertura com fiadores que falha não prende as garantias; na liquidação por
+// fator de saúde, as garantias são apreendidas antes do leilão da garantia do tomador
+#[test]
+fn test_guaranteed_loan_opening_and_liquidation() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let guarantor = setup.blockchain_wrapper.create_user_account(&rust_biguint!(2_000));
+    let pool_address = setup.blockchain_wrapper.create_user_account(&rust_biguint!(0));
+    setup.blockchain_wrapper.set_block_timestamp(10_000);
+    setup.blockchain_wrapper.set_egld_balance(&setup.borrower_address, &rust_biguint!(300));
+    fund_lendable_egld(&mut setup, 10_000);
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_loan_token(EgldOrEsdtTokenIdentifier::egld(), 1000u64, managed_biguint!(0), OptionalValue::None);
+        })
+        .assert_ok();
+
+    let mut request_id = 0u64;
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            let mut guarantors = MultiValueEncoded::new();
+            guarantors.push(managed_address!(&guarantor));
+            request_id = sc.request_guaranteed_loan(managed_biguint!(2_000), LoanTerm::Standard, guarantors);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&guarantor, &setup.contract_wrapper, &rust_biguint!(1_000), |sc| {
+            sc.pledge_guarantee(request_id);
+        })
+        .assert_ok();
+
+    // Enquanto o pool envia os fundos as garantias ficam travadas; se o pool
+    // falhar, a trava é desfeita no callback
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.guarantee_request_opening(request_id).set(true);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&guarantor, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.withdraw_guarantee(request_id);
+        })
+        .assert_user_error("Empréstimo em abertura");
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.loan_funding_callback(
+                ManagedAsyncCallResult::Err(ManagedAsyncCallError {
+                    err_code: 4,
+                    err_msg: managed_buffer!(b"sem liquidez"),
+                }),
+                managed_address!(&setup.borrower_address),
+                managed_biguint!(2_000),
+                TokenIdentifier::from_esdt_bytes(b"LEND-123456"),
+                600u64,
+                LoanTerm::Standard,
+                None,
+                request_id,
+                managed_address!(&pool_address),
+            );
+            assert!(!sc.guarantee_request_opening(request_id).get());
+            assert_eq!(sc.loan_counter().get(), 0u64);
+        })
+        .assert_ok();
+
+    // A decisão de crédito revalida as garantias no desembolso
+    setup.blockchain_wrapper
+        .execute_tx(&guarantor, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.withdraw_guarantee(request_id);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.credit_decision_callback(
+                ManagedAsyncCallResult::Ok(approved_decision(500u64)),
+                managed_address!(&setup.borrower_address),
+                managed_biguint!(2_000),
+                EgldOrEsdtTokenIdentifier::egld(),
+                LoanTerm::Standard,
+                None,
+                request_id,
+            );
+        })
+        .assert_user_error("Garantias dos fiadores insuficientes");
+
+    setup.blockchain_wrapper
+        .execute_tx(&guarantor, &setup.contract_wrapper, &rust_biguint!(1_000), |sc| {
+            sc.pledge_guarantee(request_id);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.credit_decision_callback(
+                ManagedAsyncCallResult::Ok(approved_decision(500u64)),
+                managed_address!(&setup.borrower_address),
+                managed_biguint!(2_000),
+                EgldOrEsdtTokenIdentifier::egld(),
+                LoanTerm::Standard,
+                None,
+                request_id,
+            );
+            assert_eq!(sc.get_loan_guarantee_request(1u64).into_option(), Some(request_id));
+            sc.set_liquidation_threshold(10_000u64);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(300), |sc| {
+            sc.provide_collateral(1u64);
+        })
+        .assert_ok();
+
+    // Liquidação: a garantia dos fiadores quita parte da dívida antes do leilão,
+    // que parte da dívida restante; a garantia do tomador continua no contrato
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            let repayment_before = sc.loans(1u64).get().repayment_amount;
+            sc.liquidate(1u64);
+
+            let loan = sc.loans(1u64).get();
+            assert_eq!(loan.status, LoanStatus::Defaulted);
+            assert_eq!(loan.repayment_amount, repayment_before - 1_000u64);
+            assert_eq!(sc.guarantor_stake(request_id, &managed_address!(&guarantor)).get(), managed_biguint!(0));
+            assert_eq!(sc.loan_collateral(1u64).get(), managed_biguint!(300));
+            let auction = sc.get_collateral_auction(1u64).into_option().unwrap();
+            assert_eq!(auction.start_price, loan.repayment_amount);
+
+            // O valor apreendido volta ao saldo emprestável como principal
+            assert_eq!(sc.get_lendable_balance(EgldOrEsdtTokenIdentifier::egld()), managed_biguint!(9_000));
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.check_egld_balance(&guarantor, &rust_biguint!(1_000));
+}
+
+// Identificadores dos eventos emitidos por uma transação (e tópicos das transferências)
+fn event_names(logs: &[multiversx_sc_scenario::multiversx_chain_vm::tx_mock::TxLog]) -> Vec<String> {
+    logs.iter()
+        .map(|log| String::from_utf8_lossy(&log.topics[0]).into_owned())
+        .collect()
+}
+
+// Teste dos eventos de ciclo de vida: cada transição do empréstimo é emitida
+#[test]
+fn test_loan_lifecycle_events() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    setup.blockchain_wrapper.set_block_timestamp(10_000);
+    fund_lendable_egld(&mut setup, 10_000);
+
+    let result = setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_loan_token(EgldOrEsdtTokenIdentifier::egld(), 1000u64, managed_biguint!(0), OptionalValue::None);
+            sc.allow_partial_repayments().set(true);
+            sc.credit_decision_callback(
+                ManagedAsyncCallResult::Ok(approved_decision(500u64)),
+                managed_address!(&setup.borrower_address),
+                managed_biguint!(1_000),
+                EgldOrEsdtTokenIdentifier::egld(),
+                LoanTerm::Standard,
+                None,
+                0u64,
+            );
+        });
+    result.assert_ok();
+    assert!(event_names(&result.result_logs).contains(&"loan_created".to_string()));
+
+    let result = setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(300), |sc| {
+            sc.provide_collateral(1u64);
+        });
+    result.assert_ok();
+    assert!(event_names(&result.result_logs).contains(&"collateral_deposited".to_string()));
+
+    let result = setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.extend_loan_deadline(1u64, 10u64);
+        });
+    result.assert_ok();
+    assert!(event_names(&result.result_logs).contains(&"loan_extended".to_string()));
+
+    let result = setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(100), |sc| {
+            sc.partial_repay_loan(1u64);
+        });
+    result.assert_ok();
+    assert!(event_names(&result.result_logs).contains(&"loan_partially_repaid".to_string()));
+
+    // Quitação: pagamento e mudança de status Active -> Repaid
+    let mut remaining = 0u64;
+    setup.blockchain_wrapper
+        .execute_query(&setup.contract_wrapper, |sc| {
+            remaining = sc.loans(1u64).get().repayment_amount.to_u64().unwrap();
+        })
+        .assert_ok();
+    let result = setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(remaining), |sc| {
+            sc.repay_loan(1u64);
+        });
+    result.assert_ok();
+    let names = event_names(&result.result_logs);
+    assert!(names.contains(&"loan_status_changed".to_string()));
+    assert!(names.contains(&"loan_repaid".to_string()));
+    let status_log = result.result_logs.iter()
+        .find(|log| log.topics[0] == b"loan_status_changed".to_vec())
+        .unwrap();
+    assert_eq!(LoanStatus::top_decode(status_log.topics[3].clone()).unwrap(), LoanStatus::Active);
+    assert_eq!(LoanStatus::top_decode(status_log.topics[4].clone()).unwrap(), LoanStatus::Repaid);
+
+    let result = setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.withdraw_collateral(1u64);
+        });
+    result.assert_ok();
+    assert!(event_names(&result.result_logs).contains(&"collateral_withdrawn".to_string()));
+}
+
+// Teste das listagens paginadas com os valores calculados de cada empréstimo
+#[test]
+fn test_paginated_loan_views() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    setup.blockchain_wrapper.set_block_timestamp(10_000);
+    fund_lendable_egld(&mut setup, 10_000);
+
+    // Três empréstimos de 1000 a 6% (pontuação 500, prazo padrão)
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_loan_token(EgldOrEsdtTokenIdentifier::egld(), 1000u64, managed_biguint!(0), OptionalValue::None);
+            for _ in 0..3 {
+                sc.credit_decision_callback(
+                    ManagedAsyncCallResult::Ok(approved_decision(500u64)),
+                    managed_address!(&setup.borrower_address),
+                    managed_biguint!(1_000),
+                    EgldOrEsdtTokenIdentifier::egld(),
+                    LoanTerm::Standard,
+                    None,
+                    0u64,
+                );
+            }
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(1_060), |sc| {
+            sc.repay_loan(2u64);
+        })
+        .assert_ok();
+
+    // Metade do prazo: metade dos juros contratados
+    setup.blockchain_wrapper.set_block_timestamp(10_000 + 15 * 86_400);
+    setup.blockchain_wrapper
+        .execute_query(&setup.contract_wrapper, |sc| {
+            let active: Vec<LoanView<DebugApi>> =
+                sc.get_loans(0u64, 10u64, OptionalValue::Some(LoanStatus::Active)).into_tuple().1.into_iter().collect();
+            assert_eq!(active.iter().map(|view| view.loan_id).collect::<Vec<_>>(), vec![1u64, 3u64]);
+            assert_eq!(active[0].accrued_interest, managed_biguint!(30));
+            assert_eq!(active[0].amount_due, managed_biguint!(1_030));
+            assert_eq!(active[0].token_id, EgldOrEsdtTokenIdentifier::egld());
+
+            let (next_id, page) = sc.get_loans(2u64, 1u64, OptionalValue::None).into_tuple();
+            let page: Vec<LoanView<DebugApi>> = page.into_iter().collect();
+            assert_eq!(next_id, Some(3u64));
+            assert_eq!(page.len(), 1);
+            assert_eq!(page[0].loan.status, LoanStatus::Repaid);
+            assert_eq!(page[0].amount_due, managed_biguint!(0));
+
+            let borrower = managed_address!(&setup.borrower_address);
+            assert_eq!(sc.get_user_loan_count(borrower.clone()), 3u64);
+            let user_page: Vec<LoanView<DebugApi>> =
+                sc.get_user_loans_detailed(borrower.clone(), 1u64, 5u64).into_iter().collect();
+            assert_eq!(user_page.iter().map(|view| view.loan_id).collect::<Vec<_>>(), vec![2u64, 3u64]);
+            assert_eq!(sc.get_user_loans_detailed(borrower, 3u64, 5u64).len(), 0);
+
+            let (next_id, _) = sc.get_loans(0u64, 10u64, OptionalValue::None).into_tuple();
+            assert_eq!(next_id, None);
+        })
+        .assert_ok();
+
+    // O filtro por status examina um número limitado de ids por chamada e
+    // devolve o cursor de onde continuar
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.loan_counter().set(2_500u64);
+
+            let (next_id, active) = sc.get_loans(0u64, 10u64, OptionalValue::Some(LoanStatus::Active)).into_tuple();
+            assert_eq!(active.len(), 2);
+            assert_eq!(next_id, Some(1_000u64));
+
+            let (next_id, active) = sc.get_loans(2_000u64, 10u64, OptionalValue::Some(LoanStatus::Active)).into_tuple();
+            assert_eq!(active.len(), 0);
+            assert_eq!(next_id, None);
+        })
+        .assert_ok();
+}
+
+// Teste dos juros por segundo: a quitação antecipada paga só os juros
+// decorridos, respeitando o piso de juros mínimos
+#[test]
+fn test_early_repayment_rebate() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let day = 86_400u64;
+    setup.blockchain_wrapper.set_block_timestamp(10_000);
+    fund_lendable_egld(&mut setup, 10_000);
+    setup.blockchain_wrapper.set_egld_balance(&setup.borrower_address, &rust_biguint!(100));
+
+    // 1000 a 6% em 30 dias = 73% ao ano
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_loan_token(EgldOrEsdtTokenIdentifier::egld(), 1000u64, managed_biguint!(0), OptionalValue::None);
+            sc.credit_decision_callback(
+                ManagedAsyncCallResult::Ok(approved_decision(500u64)),
+                managed_address!(&setup.borrower_address),
+                managed_biguint!(1_000),
+                EgldOrEsdtTokenIdentifier::egld(),
+                LoanTerm::Standard,
+                None,
+                0u64,
+            );
+            assert_eq!(sc.get_loan_apr(1u64), 7_300u64);
+            assert_eq!(sc.quote_repayment(1u64, 10_000 + 10 * day), managed_biguint!(1_020));
+            assert_eq!(sc.quote_repayment(1u64, 10_000 + 30 * day), managed_biguint!(1_060));
+            // Depois do vencimento os juros não passam dos contratados
+            assert_eq!(sc.quote_repayment(1u64, 10_000 + 31 * day), managed_biguint!(1_060));
+
+            // Piso de 50% dos juros contratados
+            sc.set_min_interest_floor(5_000u64);
+            assert_eq!(sc.quote_repayment(1u64, 10_000 + 10 * day), managed_biguint!(1_030));
+            assert_eq!(sc.quote_repayment(1u64, 10_000 + 20 * day), managed_biguint!(1_040));
+        })
+        .assert_ok();
+
+    // Quitação no 10º dia: o excedente enviado é devolvido
+    setup.blockchain_wrapper.set_block_timestamp(10_000 + 10 * day);
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(1_029), |sc| {
+            sc.repay_loan(1u64);
+        })
+        .assert_user_error("Incorrect repayment amount");
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(1_060), |sc| {
+            assert_eq!(sc.get_accrued_interest(1u64), managed_biguint!(20));
+            sc.repay_loan(1u64);
+            assert_eq!(sc.loans(1u64).get().status, LoanStatus::Repaid);
+            assert_eq!(sc.total_interest_earned().get(), managed_biguint!(30));
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.check_egld_balance(&setup.borrower_address, &rust_biguint!(70));
+}
+
+// Pagamento parcial que cobre a quitação recebe o mesmo desconto de juros não
+// decorridos de repayLoan; os demais abatem o saldo pelo valor pago
+#[test]
+fn test_partial_repayment_rebate() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let day = 86_400u64;
+    setup.blockchain_wrapper.set_block_timestamp(10_000);
+    fund_lendable_egld(&mut setup, 10_000);
+    setup.blockchain_wrapper.set_egld_balance(&setup.borrower_address, &rust_biguint!(100));
+
+    // 1000 a 6% em 30 dias
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.allow_partial_repayments().set(true);
+            sc.set_loan_token(EgldOrEsdtTokenIdentifier::egld(), 1000u64, managed_biguint!(0), OptionalValue::None);
+            sc.credit_decision_callback(
+                ManagedAsyncCallResult::Ok(approved_decision(500u64)),
+                managed_address!(&setup.borrower_address),
+                managed_biguint!(1_000),
+                EgldOrEsdtTokenIdentifier::egld(),
+                LoanTerm::Standard,
+                None,
+                0u64,
+            );
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper.set_block_timestamp(10_000 + 5 * day);
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(500), |sc| {
+            sc.partial_repay_loan(1u64);
+            assert_eq!(sc.loans(1u64).get().repayment_amount, managed_biguint!(560));
+        })
+        .assert_ok();
+
+    // No 10º dia a quitação custa 520 (20 de juros decorridos); o excedente volta
+    setup.blockchain_wrapper.set_block_timestamp(10_000 + 10 * day);
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(600), |sc| {
+            assert_eq!(sc.get_amount_due(1u64), managed_biguint!(520));
+            sc.partial_repay_loan(1u64);
+
+            let loan = sc.loans(1u64).get();
+            assert_eq!(loan.status, LoanStatus::Repaid);
+            assert_eq!(loan.repayment_amount, managed_biguint!(0));
+            assert_eq!(sc.loan_payments(1u64).get(), managed_biguint!(1_020));
+            assert_eq!(sc.total_interest_earned().get(), managed_biguint!(20));
+            assert_eq!(sc.get_lendable_balance(EgldOrEsdtTokenIdentifier::egld()), managed_biguint!(10_000));
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.check_egld_balance(&setup.borrower_address, &rust_biguint!(80));
+}
+
+#[test]
+fn test_exposure_limits_by_tier() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let borrower = setup.borrower_address.clone();
+
+    // Faixa base: 1 empréstimo e 1000 de principal; a partir de 700: 3 empréstimos e 5000
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_exposure_tier(0u64, 1u64, managed_biguint!(1_000));
+            sc.set_exposure_tier(700u64, 3u64, managed_biguint!(5_000));
+            assert_eq!(sc.get_exposure_tiers().len(), 2);
+        })
+        .assert_ok();
+
+    // Sem pontuação conhecida vale a faixa base
+    setup.blockchain_wrapper
+        .execute_tx(&borrower, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            assert_eq!(sc.request_loan_sync(managed_biguint!(800), 30u64), 1u64);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&borrower, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.request_loan_sync(managed_biguint!(100), 30u64);
+        })
+        .assert_user_error("User has reached maximum allowed loans");
+
+    // Com pontuação 750 o tomador sobe de faixa, até o limite de principal
+    setup.blockchain_wrapper
+        .execute_tx(&setup.reputation_score_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.reputation_check_callback(managed_address!(&borrower), 750u64);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&borrower, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            assert_eq!(sc.request_loan_sync(managed_biguint!(3_000), 30u64), 2u64);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&borrower, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.request_loan_sync(managed_biguint!(2_000), 30u64);
+        })
+        .assert_user_error("User has reached maximum outstanding principal");
+
+    // O desembolso assíncrono confere a pontuação atual: de volta à faixa base
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.credit_decision_callback(
+                ManagedAsyncCallResult::Ok(approved_decision(500u64)),
+                managed_address!(&borrower),
+                managed_biguint!(100),
+                EgldOrEsdtTokenIdentifier::egld(),
+                LoanTerm::Standard,
+                None,
+                0u64,
+            );
+        })
+        .assert_user_error("User has reached maximum allowed loans");
+
+    // maxLoansPerUser continua sendo o teto de quantidade de todas as faixas
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_max_loans_per_user(2u64);
+            let limit = sc.get_exposure_limit(750u64);
+            assert_eq!(limit.max_active_loans, 2u64);
+            assert_eq!(limit.max_outstanding_principal, managed_biguint!(5_000));
+
+            let (active_loans, outstanding_principal) =
+                sc.get_borrower_exposure(managed_address!(&borrower)).into_tuple();
+            assert_eq!(active_loans, 2u64);
+            assert_eq!(outstanding_principal, managed_biguint!(3_800));
+
+            sc.remove_exposure_tier(700u64);
+            assert_eq!(sc.get_exposure_tiers().len(), 1);
+        })
+        .assert_ok();
+
+    // Endereços na blacklist são recusados antes de qualquer outro limite
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.remove_exposure_tier(0u64);
+            sc.set_max_loans_per_user(0u64);
+            sc.add_to_blacklist(managed_address!(&borrower));
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&borrower, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.request_loan_sync(managed_biguint!(100), 30u64);
+        })
+        .assert_user_error("User is blacklisted");
+}
+
+// Pedidos assíncronos não são barrados pela pontuação registrada: a faixa é
+// decidida com a pontuação da decisão de crédito, que passa a ser a registrada
+#[test]
+fn test_exposure_high_tier_borrower() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let borrower = setup.borrower_address.clone();
+    fund_lendable_egld(&mut setup, 10_000);
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_exposure_tier(0u64, 1u64, managed_biguint!(1_000));
+            sc.set_exposure_tier(700u64, 3u64, managed_biguint!(5_000));
+            sc.set_loan_token(EgldOrEsdtTokenIdentifier::egld(), 1000u64, managed_biguint!(0), OptionalValue::None);
+            // ReputationScore em outro shard: a decisão chega pelo callback
+            let rs_address = address_in_shard(setup.contract_wrapper.address_ref(), false);
+            sc.reputation_score_address().set(managed_address!(&rs_address));
+        })
+        .assert_ok();
+
+    // Sem pontuação registrada, o pedido acima da faixa base segue para a decisão
+    setup.blockchain_wrapper
+        .execute_tx(&borrower, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.request_loan_in_token(EgldOrEsdtTokenIdentifier::egld(), managed_biguint!(3_000), LoanTerm::Standard);
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.credit_decision_callback(
+                ManagedAsyncCallResult::Ok(approved_decision(750u64)),
+                managed_address!(&borrower),
+                managed_biguint!(3_000),
+                EgldOrEsdtTokenIdentifier::egld(),
+                LoanTerm::Standard,
+                None,
+                0u64,
+            );
+            assert_eq!(sc.user_reputation_scores(&managed_address!(&borrower)).get(), 750u64);
+        })
+        .assert_ok();
+
+    // Os caminhos síncronos usam a pontuação da última decisão
+    setup.blockchain_wrapper
+        .execute_tx(&borrower, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            assert_eq!(sc.request_loan_sync(managed_biguint!(1_500), 30u64), 2u64);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&borrower, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.request_loan_sync(managed_biguint!(1_000), 30u64);
+        })
+        .assert_user_error("User has reached maximum outstanding principal");
+}
+
+// Exposição mantida por contadores do tomador: empréstimos anteriores aos
+// contadores são apurados uma única vez, na primeira alteração
+#[test]
+fn test_borrower_exposure_counters() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let borrower = setup.borrower_address.clone();
+    setup.blockchain_wrapper.set_block_timestamp(10_000);
+    fund_lendable_egld(&mut setup, 10_000);
+    setup.blockchain_wrapper.set_egld_balance(&borrower, &rust_biguint!(2_000));
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.allow_partial_repayments().set(true);
+            sc.set_loan_token(EgldOrEsdtTokenIdentifier::egld(), 1000u64, managed_biguint!(0), OptionalValue::None);
+            sc.loan_counter().set(1u64);
+            sc.loans(0u64).set(Loan {
+                borrower: managed_address!(&borrower),
+                amount: managed_biguint!(1_000),
+                repayment_amount: managed_biguint!(1_100),
+                interest_rate: 1000u64,
+                creation_timestamp: 10_000u64,
+                due_timestamp: 20_000u64,
+                status: LoanStatus::Active,
+            });
+            sc.user_loans(managed_address!(&borrower)).push(&0u64);
+
+            let (active_loans, outstanding_principal) =
+                sc.get_borrower_exposure(managed_address!(&borrower)).into_tuple();
+            assert_eq!(active_loans, 1u64);
+            assert_eq!(outstanding_principal, managed_biguint!(1_000));
+            assert!(!sc.borrower_exposure_synced(&managed_address!(&borrower)).get());
+        })
+        .assert_ok();
+
+    // O pagamento parcial abate o principal devolvido
+    setup.blockchain_wrapper
+        .execute_tx(&borrower, &setup.contract_wrapper, &rust_biguint!(400), |sc| {
+            sc.partial_repay_loan(0u64);
+            let (active_loans, outstanding_principal) = sc.borrower_exposure(&managed_address!(&borrower));
+            assert_eq!(active_loans, 1u64);
+            assert_eq!(outstanding_principal, managed_biguint!(600));
+            assert!(sc.borrower_exposure_synced(&managed_address!(&borrower)).get());
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.credit_decision_callback(
+                ManagedAsyncCallResult::Ok(approved_decision(500u64)),
+                managed_address!(&borrower),
+                managed_biguint!(500),
+                EgldOrEsdtTokenIdentifier::egld(),
+                LoanTerm::Standard,
+                None,
+                0u64,
+            );
+            let (active_loans, outstanding_principal) = sc.borrower_exposure(&managed_address!(&borrower));
+            assert_eq!(active_loans, 2u64);
+            assert_eq!(outstanding_principal, managed_biguint!(1_100));
+        })
+        .assert_ok();
+
+    // A quitação retira o empréstimo da exposição
+    setup.blockchain_wrapper
+        .execute_tx(&borrower, &setup.contract_wrapper, &rust_biguint!(700), |sc| {
+            sc.repay_loan(0u64);
+            let (active_loans, outstanding_principal) = sc.borrower_exposure(&managed_address!(&borrower));
+            assert_eq!(active_loans, 1u64);
+            assert_eq!(outstanding_principal, managed_biguint!(500));
+        })
+        .assert_ok();
+}
+
+#[test]
+fn test_underwriter_approval_queue() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let borrower = setup.borrower_address.clone();
+    let underwriter = setup.blockchain_wrapper.create_user_account(&rust_biguint!(0));
+    let lifetime = 86_400u64;
+    setup.blockchain_wrapper.set_block_timestamp(10_000);
+    fund_lendable_egld(&mut setup, 20_000);
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_loan_token(EgldOrEsdtTokenIdentifier::egld(), 1000u64, managed_biguint!(0), OptionalValue::None);
+            sc.set_underwriting_threshold(managed_biguint!(5_000));
+            sc.set_application_lifetime(lifetime);
+            sc.grant_role(Role::Underwriter, managed_address!(&underwriter));
+        })
+        .assert_ok();
+
+    // Garantia depositada antes do pedido fica retida nele
+    setup.blockchain_wrapper
+        .execute_tx(&borrower, &setup.contract_wrapper, &rust_biguint!(300), |sc| {
+            sc.provide_collateral_for_new_loan();
+        })
+        .assert_ok();
+
+    // Abaixo do limite o desembolso é automático; acima, o pedido vai para a fila
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            for amount in [1_000u64, 6_000u64] {
+                sc.credit_decision_callback(
+                    ManagedAsyncCallResult::Ok(approved_decision(500u64)),
+                    managed_address!(&borrower),
+                    managed_biguint!(amount),
+                    EgldOrEsdtTokenIdentifier::egld(),
+                    LoanTerm::Standard,
+                    None,
+                    0u64,
+                );
+            }
+            assert_eq!(sc.loan_counter().get(), 1u64);
+
+            let application = sc.get_loan_application(1u64).into_option().unwrap();
+            assert_eq!(application.status, ApplicationStatus::Pending);
+            assert_eq!(application.amount, managed_biguint!(6_000));
+            assert_eq!(application.interest_rate, 600u64);
+            assert_eq!(application.collateral, managed_biguint!(300));
+            assert_eq!(application.expires_at, 10_000 + lifetime);
+            assert_eq!(sc.pending_collateral(managed_address!(&borrower)).get(), managed_biguint!(0));
+            assert_eq!(sc.get_pending_applications().to_vec().into_vec(), vec![1u64]);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.check_egld_balance(&borrower, &rust_biguint!(1_700));
+
+    // Apenas underwriters decidem; a aprovação pode reduzir o valor e ajustar a taxa
+    setup.blockchain_wrapper
+        .execute_tx(&borrower, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.approve_loan_application(1u64, managed_biguint!(0), 0u64);
+        })
+        .assert_user_error("Caller does not have the required role");
+    setup.blockchain_wrapper
+        .execute_tx(&underwriter, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.approve_loan_application(1u64, managed_biguint!(7_000), 0u64);
+        })
+        .assert_user_error("Valor aprovado excede o valor pedido");
+    setup.blockchain_wrapper
+        .execute_tx(&underwriter, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.approve_loan_application(1u64, managed_biguint!(4_000), 500u64);
+
+            let loan = sc.loans(2u64).get();
+            assert_eq!(loan.amount, managed_biguint!(4_000));
+            assert_eq!(loan.repayment_amount, managed_biguint!(4_200));
+            assert_eq!(loan.interest_rate, 500u64);
+            assert_eq!(sc.loan_collateral(2u64).get(), managed_biguint!(300));
+
+            let application = sc.get_loan_application(1u64).into_option().unwrap();
+            assert_eq!(application.status, ApplicationStatus::Approved);
+            assert_eq!(application.loan_id, Some(2u64));
+            assert_eq!(sc.get_pending_applications().len(), 0);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.check_egld_balance(&borrower, &rust_biguint!(5_700));
+
+    // Recusa com código de motivo devolve a garantia retida
+    setup.blockchain_wrapper
+        .execute_tx(&borrower, &setup.contract_wrapper, &rust_biguint!(200), |sc| {
+            sc.provide_collateral_for_new_loan();
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            for _ in 0..2 {
+                sc.credit_decision_callback(
+                    ManagedAsyncCallResult::Ok(approved_decision(500u64)),
+                    managed_address!(&borrower),
+                    managed_biguint!(5_000),
+                    EgldOrEsdtTokenIdentifier::egld(),
+                    LoanTerm::Standard,
+                    None,
+                    0u64,
+                );
+            }
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&underwriter, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.reject_loan_application(2u64, 7u32);
+
+            let application = sc.get_loan_application(2u64).into_option().unwrap();
+            assert_eq!(application.status, ApplicationStatus::Rejected);
+            assert_eq!(application.reason_code, 7u32);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.check_egld_balance(&borrower, &rust_biguint!(5_700));
+
+    // Depois do prazo o pedido não pode mais ser aprovado, apenas expirado
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.expire_loan_application(3u64);
+        })
+        .assert_user_error("Pedido ainda não expirou");
+    setup.blockchain_wrapper.set_block_timestamp(10_000 + lifetime + 1);
+    setup.blockchain_wrapper
+        .execute_tx(&underwriter, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.approve_loan_application(3u64, managed_biguint!(0), 0u64);
+        })
+        .assert_user_error("Pedido expirou");
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.expire_loan_application(3u64);
+            assert_eq!(
+                sc.get_loan_application(3u64).into_option().unwrap().status,
+                ApplicationStatus::Expired
+            );
+            assert_eq!(sc.loan_counter().get(), 2u64);
+        })
+        .assert_ok();
+}
+
+// Empréstimos com fiadores acima do limite também passam pelo underwriter;
+// a aprovação usa só o saldo emprestável, não as garantias no contrato
+#[test]
+fn test_guaranteed_loan_underwriting() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let guarantor = setup.blockchain_wrapper.create_user_account(&rust_biguint!(3_000));
+    let underwriter = setup.blockchain_wrapper.create_user_account(&rust_biguint!(0));
+    setup.blockchain_wrapper.set_block_timestamp(10_000);
+    fund_lendable_egld(&mut setup, 5_000);
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_loan_token(EgldOrEsdtTokenIdentifier::egld(), 1000u64, managed_biguint!(0), OptionalValue::None);
+            sc.set_underwriting_threshold(managed_biguint!(5_000));
+            sc.grant_role(Role::Underwriter, managed_address!(&underwriter));
+        })
+        .assert_ok();
+
+    let mut request_id = 0u64;
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            let mut guarantors = MultiValueEncoded::new();
+            guarantors.push(managed_address!(&guarantor));
+            request_id = sc.request_guaranteed_loan(managed_biguint!(6_000), LoanTerm::Standard, guarantors);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&guarantor, &setup.contract_wrapper, &rust_biguint!(3_000), |sc| {
+            sc.pledge_guarantee(request_id);
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.credit_decision_callback(
+                ManagedAsyncCallResult::Ok(approved_decision(500u64)),
+                managed_address!(&setup.borrower_address),
+                managed_biguint!(6_000),
+                EgldOrEsdtTokenIdentifier::egld(),
+                LoanTerm::Standard,
+                None,
+                request_id,
+            );
+            assert_eq!(sc.loan_counter().get(), 0u64);
+            let application = sc.get_loan_application(1u64).into_option().unwrap();
+            assert_eq!(application.status, ApplicationStatus::Pending);
+            assert_eq!(application.guarantee_request_id, request_id);
+        })
+        .assert_ok();
+
+    // Enquanto o pedido aguarda, as garantias ficam travadas
+    setup.blockchain_wrapper
+        .execute_tx(&guarantor, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.withdraw_guarantee(request_id);
+        })
+        .assert_user_error("Empréstimo em abertura");
+
+    // O contrato guarda 8_000 em EGLD, mas só 5_000 podem ser emprestados
+    setup.blockchain_wrapper
+        .execute_tx(&underwriter, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.approve_loan_application(1u64, managed_biguint!(0), 0u64);
+        })
+        .assert_user_error("Saldo insuficiente para o empréstimo");
+    setup.blockchain_wrapper
+        .execute_tx(&underwriter, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.approve_loan_application(1u64, managed_biguint!(5_000), 0u64);
+
+            assert_eq!(sc.get_loan_guarantee_request(1u64).into_option(), Some(request_id));
+            assert!(!sc.guarantee_request_opening(request_id).get());
+            assert_eq!(sc.get_lendable_balance(EgldOrEsdtTokenIdentifier::egld()), managed_biguint!(0));
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.check_egld_balance(&setup.borrower_address, &rust_biguint!(1_000 + 5_000));
+}
+
+#[test]
+fn test_clock_override() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    setup.blockchain_wrapper.set_block_timestamp(10_000);
+
+    // Sem ajuste vale o tempo do bloco; com a feature `testing` os testes avançam o relógio
+    setup.blockchain_wrapper
+        .execute_tx(&setup.borrower_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            assert_eq!(sc.current_timestamp(), 10_000u64);
+            sc.clock_override().set(50_000u64);
+            assert_eq!(sc.current_timestamp(), 50_000u64);
+
+            let loan_id = sc.request_loan_sync(managed_biguint!(1_000), 30u64);
+            let loan = sc.loans(loan_id).get();
+            assert_eq!(loan.creation_timestamp, 50_000u64);
+            assert_eq!(loan.due_timestamp, 50_000u64 + 30 * 86_400);
+
+            sc.clock_override().clear();
+            assert_eq!(sc.current_timestamp(), 10_000u64);
+        })
+        .assert_ok();
+}
+
+#[test]
+fn test_storage_upgrade_migrations() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+
+    // Contrato novo já nasce na versão atual
+    setup.blockchain_wrapper
+        .execute_query(&setup.contract_wrapper, |sc| {
+            assert_eq!(sc.get_storage_version(), 2u32);
+        })
+        .assert_ok();
+
+    // Implantação anterior ao versionamento, com os prazos nas chaves duplicadas
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.storage_version().clear();
+            sc.standard_term_days().clear();
+            sc.short_term_days().clear();
+            sc.standard_loan_term_days().set(45u64);
+            sc.extended_loan_term_days().set(120u64);
+        })
+        .assert_ok();
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.upgrade();
+
+            assert_eq!(sc.get_storage_version(), 2u32);
+            // Chave canônica vazia adota o valor legado; a já preenchida prevalece
+            assert_eq!(sc.get_standard_loan_term_days(), 45u64);
+            assert_eq!(sc.get_extended_loan_term_days(), 90u64);
+            assert!(sc.standard_loan_term_days().is_empty());
+            assert!(sc.extended_loan_term_days().is_empty());
+            // Prazo sem valor em nenhuma chave recebe o padrão do init
+            assert_eq!(sc.short_term_days().get(), 15u64);
+
+            // Um segundo upgrade não repete a migração
+            sc.standard_loan_term_days().set(60u64);
+            sc.upgrade();
+            assert_eq!(sc.get_standard_loan_term_days(), 45u64);
+            assert_eq!(sc.standard_loan_term_days().get(), 60u64);
+        })
+        .assert_ok();
+
+    // Código mais antigo que o armazenamento gravado não pode ser instalado
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.storage_version().set(3u32);
+            sc.upgrade();
+        })
+        .assert_user_error("Versão de armazenamento mais nova que o código");
+
+    // v1 → v2: propostas pendentes sem executable_at ganham o prazo do timelock vigente
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.storage_version().set(1u32);
+            sc.operation_timelock().set(300u64);
+            sc.legacy_pending_parameter_changes(ParamType::MinScore).set((600u64, 1_000u64));
+            sc.pending_parameter_types().insert(ParamType::MinScore);
+
+            sc.upgrade();
+
+            assert_eq!(sc.get_storage_version(), 2u32);
+            let change = sc.pending_parameter_changes(ParamType::MinScore).get();
+            assert_eq!(change.value, 600u64);
+            assert_eq!(change.timestamp, 1_000u64);
+            assert_eq!(change.executable_at, 1_300u64);
+        })
+        .assert_ok();
+}
+
+// Decisão de crédito em uma única resposta do ReputationScore
+#[test]
+fn test_credit_decision_callback() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let borrower = setup.borrower_address.clone();
+    setup.blockchain_wrapper.set_block_timestamp(10_000);
+    fund_lendable_egld(&mut setup, 5_000);
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_loan_token(EgldOrEsdtTokenIdentifier::egld(), 1000u64, managed_biguint!(0), OptionalValue::None);
+        })
+        .assert_ok();
+
+    let decision = |eligible: bool, within_limit: bool| CreditDecision::<DebugApi> {
+        eligible,
+        within_limit,
+        max_amount: managed_biguint!(2_000),
+        score: 500u64,
+    };
+
+    // Pontuação abaixo do mínimo ou valor acima do limite: nada é desembolsado
+    for (eligible, within_limit, message) in [
+        (false, true, "Pontuação do usuário muito baixa para empréstimo"),
+        (true, false, "Valor solicitado excede o limite permitido"),
+    ] {
+        setup.blockchain_wrapper
+            .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+                sc.credit_decision_callback(
+                    ManagedAsyncCallResult::Ok(decision(eligible, within_limit)),
+                    managed_address!(&borrower),
+                    managed_biguint!(1_000),
+                    EgldOrEsdtTokenIdentifier::egld(),
+                    LoanTerm::Standard,
+                    None,
+                    0u64,
+                );
+            })
+            .assert_user_error(message);
+    }
+
+    // Decisão aprovada abre o empréstimo com a pontuação devolvida (500 → 600 bp)
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.credit_decision_callback(
+                ManagedAsyncCallResult::Ok(decision(true, true)),
+                managed_address!(&borrower),
+                managed_biguint!(1_000),
+                EgldOrEsdtTokenIdentifier::egld(),
+                LoanTerm::Standard,
+                None,
+                0u64,
+            );
+            assert_eq!(sc.loan_counter().get(), 1u64);
+            let loan = sc.loans(1u64).get();
+            assert_eq!(loan.amount, managed_biguint!(1_000));
+            assert_eq!(loan.interest_rate, 600u64);
+            assert_eq!(sc.get_lendable_balance(EgldOrEsdtTokenIdentifier::egld()), managed_biguint!(4_000));
+        })
+        .assert_ok();
+    setup.blockchain_wrapper.check_egld_balance(setup.contract_wrapper.address_ref(), &rust_biguint!(4_000));
+
+    // Um pedido acima do saldo emprestável é recusado, mesmo com EGLD de terceiros no contrato
+    setup.blockchain_wrapper.set_egld_balance(setup.contract_wrapper.address_ref(), &rust_biguint!(10_000));
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.credit_decision_callback(
+                ManagedAsyncCallResult::Ok(decision(true, true)),
+                managed_address!(&borrower),
+                managed_biguint!(5_000),
+                EgldOrEsdtTokenIdentifier::egld(),
+                LoanTerm::Standard,
+                None,
+                0u64,
+            );
+        })
+        .assert_user_error("Saldo insuficiente para o empréstimo");
+}
+
+// Prévia de requestLoan com a mesma matemática da originação
+// No shard do ReputationScore a prévia consulta a decisão de crédito atual,
+// a mesma que a originação usaria, e é vinculante
+#[test]
+fn test_quote_loan_binding_in_same_shard() {
+    use reputation_score::ReputationScore;
+
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let borrower = setup.borrower_address.clone();
+    let owner = setup.owner_address.clone();
+    let rs_address = address_in_shard(setup.contract_wrapper.address_ref(), true);
+    let rs_wrapper = setup.blockchain_wrapper.create_sc_account_fixed_address(
+        &rs_address,
+        &rust_biguint!(0),
+        Some(&owner),
+        reputation_score::contract_obj,
+        "../reputation-score/output/reputation-score.wasm",
+    );
+    setup.blockchain_wrapper
+        .execute_tx(&owner, &rs_wrapper, &rust_biguint!(0), |sc| {
+            sc.init(0u64, 1000u64);
+            sc.user_score(managed_address!(&borrower)).set(700u64);
+        })
+        .assert_ok();
+
+    // O cache local ainda tem a pontuação 500
+    setup.blockchain_wrapper
+        .execute_tx(&setup.reputation_score_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.reputation_check_callback(managed_address!(&borrower), 500u64);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&owner, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.reputation_score_address().set(managed_address!(&rs_address));
+        })
+        .assert_ok();
+
+    // Limite atual: 10_000 * 700 * 2 / 1000 = 14_000
+    setup.blockchain_wrapper
+        .execute_query(&setup.contract_wrapper, |sc| {
+            let quote = sc.quote_loan(managed_address!(&borrower), managed_biguint!(12_000), LoanTerm::Standard, OptionalValue::None, OptionalValue::None);
+            assert!(quote.binding);
+            assert_eq!(quote.user_score, 700u64);
+            assert_eq!(quote.max_amount, managed_biguint!(14_000));
+            assert!(quote.eligible);
+
+            let quote = sc.quote_loan(managed_address!(&borrower), managed_biguint!(15_000), LoanTerm::Standard, OptionalValue::None, OptionalValue::None);
+            assert_eq!(quote.reason_code, ELIGIBILITY_AMOUNT_ABOVE_LIMIT);
+        })
+        .assert_ok();
+}
+
+#[test]
+fn test_quote_loan() {
+    let mut setup = setup_contract(loan_controller::contract_obj);
+    let borrower = setup.borrower_address.clone();
+    let owner = setup.owner_address.clone();
+    setup.blockchain_wrapper.set_block_timestamp(10_000);
+    fund_lendable_egld(&mut setup, 20_000);
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.reputation_score_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.reputation_check_callback(managed_address!(&borrower), 500u64);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&owner, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_loan_token(EgldOrEsdtTokenIdentifier::egld(), 1000u64, managed_biguint!(0), OptionalValue::None);
+            sc.set_collateral_ratio(8_000u64);
+            // ReputationScore em outro shard: a prévia usa a pontuação registrada
+            let rs_address = address_in_shard(setup.contract_wrapper.address_ref(), false);
+            sc.reputation_score_address().set(managed_address!(&rs_address));
+        })
+        .assert_ok();
+
+    // Pontuação 500: 1000 * 60% = 600 bp, +20% no prazo estendido = 720 bp;
+    // garantia 5_000 / 80% = 6_250. Sem a decisão de crédito o limite fica indisponível
+    setup.blockchain_wrapper
+        .execute_query(&setup.contract_wrapper, |sc| {
+            let quote = sc.quote_loan(managed_address!(&borrower), managed_biguint!(5_000), LoanTerm::Extended, OptionalValue::None, OptionalValue::None);
+            assert!(!quote.binding);
+            assert!(!quote.eligible);
+            assert_eq!(quote.reason_code, ELIGIBILITY_LIMIT_UNAVAILABLE);
+            assert_eq!(quote.user_score, 500u64);
+            assert_eq!(quote.interest_rate, 720u64);
+            assert_eq!(quote.repayment_amount, managed_biguint!(5_360));
+            assert_eq!(quote.due_timestamp, 10_000 + 90 * 86_400);
+            assert_eq!(quote.max_amount, managed_biguint!(0));
+            assert_eq!(quote.required_collateral, managed_biguint!(6_250));
+            assert!(!quote.requires_underwriting);
+            assert_eq!(quote.installment_count, 1u64);
+
+            // Usuário sem pontuação
+            let quote = sc.quote_loan(managed_address!(&owner), managed_biguint!(1_000), LoanTerm::Standard, OptionalValue::None, OptionalValue::None);
+            assert!(!quote.eligible);
+            assert_eq!(quote.reason_code, ELIGIBILITY_SCORE_TOO_LOW);
+
+            // Token fora da lista de aceitos e parcela maior que o prazo
+            let token = EgldOrEsdtTokenIdentifier::esdt(managed_token_id!(b"ABC-123456"));
+            let quote = sc.quote_loan(managed_address!(&borrower), managed_biguint!(1_000), LoanTerm::Standard, OptionalValue::Some(token), OptionalValue::None);
+            assert_eq!(quote.reason_code, ELIGIBILITY_TOKEN_NOT_ACCEPTED);
+            let quote = sc.quote_loan(
+                managed_address!(&borrower),
+                managed_biguint!(1_000),
+                LoanTerm::Short,
+                OptionalValue::None,
+                OptionalValue::Some(InstallmentPeriod::Monthly),
+            );
+            assert_eq!(quote.reason_code, ELIGIBILITY_INVALID_INSTALLMENT_PERIOD);
+
+            // 30 dias em parcelas semanais: 4 parcelas
+            let quote = sc.quote_loan(
+                managed_address!(&borrower),
+                managed_biguint!(4_000),
+                LoanTerm::Standard,
+                OptionalValue::Some(EgldOrEsdtTokenIdentifier::egld()),
+                OptionalValue::Some(InstallmentPeriod::Weekly),
+            );
+            assert_eq!(quote.reason_code, ELIGIBILITY_LIMIT_UNAVAILABLE);
+            assert_eq!(quote.installment_count, 4u64);
+        })
+        .assert_ok();
+
+    // A originação produz exatamente os termos cotados
+    setup.blockchain_wrapper
+        .execute_tx(&owner, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            let quote = sc.quote_loan(managed_address!(&borrower), managed_biguint!(5_000), LoanTerm::Extended, OptionalValue::None, OptionalValue::None);
+            sc.credit_decision_callback(
+                ManagedAsyncCallResult::Ok(approved_decision(500u64)),
+                managed_address!(&borrower),
+                managed_biguint!(5_000),
+                EgldOrEsdtTokenIdentifier::egld(),
+                LoanTerm::Extended,
+                None,
+                0u64,
+            );
+            let loan = sc.loans(1u64).get();
+            assert_eq!(loan.interest_rate, quote.interest_rate);
+            assert_eq!(loan.repayment_amount, quote.repayment_amount);
+            assert_eq!(loan.due_timestamp, quote.due_timestamp);
+
+            // A decisão de crédito atualiza a pontuação usada nas prévias
+            sc.credit_decision_callback(
+                ManagedAsyncCallResult::Ok(approved_decision(800u64)),
+                managed_address!(&borrower),
+                managed_biguint!(1_000),
+                EgldOrEsdtTokenIdentifier::egld(),
+                LoanTerm::Standard,
+                None,
+                0u64,
+            );
+            let quote = sc.quote_loan(managed_address!(&borrower), managed_biguint!(1_000), LoanTerm::Standard, OptionalValue::None, OptionalValue::None);
+            assert_eq!(quote.user_score, 800u64);
+
+            sc.add_to_blacklist(managed_address!(&borrower));
+            let quote = sc.quote_loan(managed_address!(&borrower), managed_biguint!(1_000), LoanTerm::Standard, OptionalValue::None, OptionalValue::None);
+            assert_eq!(quote.reason_code, ELIGIBILITY_BLACKLISTED);
+        })
+        .assert_ok();
+}
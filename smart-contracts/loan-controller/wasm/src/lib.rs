@@ -5,9 +5,10 @@
 ////////////////////////////////////////////////////
 
 // Init:                                 1
-// Endpoints:                           86
+// Upgrade:                              1
+// Endpoints:                          202
 // Async Callback:                       1
-// Total number of exported functions:  88
+// Total number of exported functions: 205
 
 #![no_std]
 
@@ -18,11 +19,64 @@ multiversx_sc_wasm_adapter::endpoints! {
     loan_controller
     (
         init => init
+        upgrade => upgrade
         withdrawFunds => withdraw_funds
+        getProtocolFees => get_protocol_fees
         setLoanTerms => set_loan_terms
         requestLoan => request_loan
+        requestLoanInToken => request_loan_in_token
+        requestInstallmentLoan => request_installment_loan
         repayLoan => repay_loan
+        setLiquidityPool => set_liquidity_pool
+        setLoanToken => set_loan_token
+        removeLoanToken => remove_loan_token
+        getLoanTokens => get_loan_tokens
+        getLoanTokenConfig => get_loan_token_config
+        getLoanToken => get_loan_token
+        depositLendingFunds => deposit_lending_funds
+        withdrawLendingFunds => withdraw_lending_funds
+        getLendableBalance => get_lendable_balance
+        getLiquidityPoolAddress => get_liquidity_pool_address
+        getLendingTokenId => get_lending_token_id
+        setDebtToken => set_debt_token
+        getDebtTokenAddress => get_debt_token_address
+        getLoanDebtNftNonce => get_loan_debt_nft_nonce
+        getDebtNftLoanId => get_debt_nft_loan_id
         extendLoanDeadline => extend_loan_deadline
+        refinanceLoans => refinance_loans
+        getRefinancedInto => get_refinanced_into
+        getRefinancedFrom => get_refinanced_from
+        openCreditLine => open_credit_line
+        refreshCreditLimit => refresh_credit_limit
+        drawCredit => draw_credit
+        repayCredit => repay_credit
+        closeCreditLine => close_credit_line
+        defaultCreditLine => default_credit_line
+        getCreditLineDueTimestamp => get_credit_line_due_timestamp
+        isCreditLineDefaulted => is_credit_line_defaulted
+        getCreditLine => get_credit_line
+        getAvailableCredit => get_available_credit
+        getCreditUtilization => get_credit_utilization
+        getCreditLineInterest => get_credit_line_interest
+        requestGuaranteedLoan => request_guaranteed_loan
+        pledgeGuarantee => pledge_guarantee
+        withdrawGuarantee => withdraw_guarantee
+        cancelGuaranteedLoan => cancel_guaranteed_loan
+        openGuaranteedLoan => open_guaranteed_loan
+        setGuarantorStakeRatio => set_guarantor_stake_ratio
+        getGuarantorStakeRatio => get_guarantor_stake_ratio
+        getGuaranteedLoanRequest => get_guaranteed_loan_request
+        getGuarantors => get_guarantors
+        getLoanGuaranteeRequest => get_loan_guarantee_request
+        setUnderwritingThreshold => set_underwriting_threshold
+        setApplicationLifetime => set_application_lifetime
+        approveLoanApplication => approve_loan_application
+        rejectLoanApplication => reject_loan_application
+        expireLoanApplication => expire_loan_application
+        getLoanApplication => get_loan_application
+        getPendingApplications => get_pending_applications
+        getUnderwritingThreshold => get_underwriting_threshold
+        getApplicationLifetime => get_application_lifetime
         provideCollateral => provide_collateral
         withdrawCollateral => withdraw_collateral
         markLoanDefaulted => mark_loan_defaulted
@@ -30,28 +84,69 @@ multiversx_sc_wasm_adapter::endpoints! {
         provideCollateralForNewLoan => provide_collateral_for_new_loan
         requestLoanWithCollateral => request_loan_with_collateral
         cancelLoanRequest => cancel_loan_request
+        startCollateralAuction => start_collateral_auction
+        liquidate => liquidate
+        getHealthFactor => get_health_factor
+        getCollateralValue => get_collateral_value
+        getCollateralToken => get_collateral_token
+        setLiquidationThreshold => set_liquidation_threshold
+        getLiquidationThreshold => get_liquidation_threshold
+        setPriceSource => set_price_source
+        setCollateralPrice => set_collateral_price
         liquidateCollateralViaAuction => liquidate_collateral_via_auction
+        settleCollateralAuction => settle_collateral_auction
+        getAuctionPrice => get_auction_price
+        claimAuctionRefund => claim_auction_refund
+        getAuctionRefund => get_auction_refund
+        getCollateralAuction => get_collateral_auction
+        setAuctionDuration => set_auction_duration
+        setLiquidationPenalty => set_liquidation_penalty
+        getTotalLiquidationPenalties => get_total_liquidation_penalties
         partialRepayLoan => partial_repay_loan
+        getAmountDue => get_amount_due
+        quoteRepayment => quote_repayment
+        getLoanApr => get_loan_apr
+        getAccruedInterest => get_accrued_interest
+        setMinInterestFloor => set_min_interest_floor
+        getMinInterestFloor => get_min_interest_floor
+        getLateFeeDue => get_late_fee_due
+        getLateFeesPaid => get_late_fees_paid
+        getTotalLateFeesEarned => get_total_late_fees_earned
+        setProgressiveLateFee => set_progressive_late_fee
+        getNextInstallment => get_next_installment
+        getOverdueAmount => get_overdue_amount
+        getRemainingSchedule => get_remaining_schedule
         add_investor => add_investor
-        distributeProfits => distribute_profits
         removeInvestor => remove_investor
-        emergencyWithdraw => emergency_withdraw
+        claimProfits => claim_profits
+        pendingProfits => pending_profits
+        getInvestors => get_investors
         addToBlacklist => add_to_blacklist
         isBlacklisted => is_blacklisted
         removeFromBlacklist => remove_from_blacklist
         setMaxLoansPerUser => set_max_loans_per_user
+        setExposureTier => set_exposure_tier
+        removeExposureTier => remove_exposure_tier
+        getExposureTiers => get_exposure_tiers
+        getExposureLimit => get_exposure_limit
+        getBorrowerExposure => get_borrower_exposure
         setMinCollateralAmount => set_min_collateral_amount
         set_min_interest_rate => set_min_interest_rate
         set_max_interest_rate => set_max_interest_rate
         getMinInterestRate => get_min_interest_rate
         getMaxInterestRate => get_max_interest_rate
-        set_mock_timestamp => set_mock_timestamp
-        get_block_timestamp => get_block_timestamp
         set_max_active_loans => set_max_active_loans
         getMaxActiveLoans => get_max_active_loans
         getRepaidLoansCount => get_repaid_loans_count
         requestLoanSync => request_loan_sync
         mark_expired_loans => mark_expired_loans
+        processOverdueLoans => process_overdue_loans
+        setKeeperBounty => set_keeper_bounty
+        getKeeperBounty => get_keeper_bounty
+        fundKeeperBounty => fund_keeper_bounty
+        getKeeperBountyReserve => get_keeper_bounty_reserve
+        getOverdueCursorDay => get_overdue_cursor_day
+        getLoansDueOnDay => get_loans_due_on_day
         setExtensionFeePercent => set_extension_fee_percent
         getExtensionFeePercent => get_extension_fee_percent
         setLateFeeDailyRate => set_late_fee_daily_rate
@@ -75,13 +170,9 @@ multiversx_sc_wasm_adapter::endpoints! {
         set_extended_term_rate_multiplier => set_extended_term_rate_multiplier
         set_max_term_rate_multiplier => set_max_term_rate_multiplier
         set_min_required_score => set_min_required_score
-        initiateContractDestruction => initiate_contract_destruction
-        executeContractDestruction => execute_contract_destruction
-        initiateContractDestructionV2 => initiate_contract_destruction_v2
+        performAction => perform_action
         pauseContract => pause_contract
         unpauseContract => unpause_contract
-        confirmContractDestructionV2 => confirm_contract_destruction_v2
-        cancelContractDestructionV2 => cancel_contract_destruction_v2
         requestLoanWithTerm => request_loan_with_term
         getMinRequiredScore => get_min_required_score
         getInterestRateBase => get_interest_rate_base
@@ -92,7 +183,16 @@ multiversx_sc_wasm_adapter::endpoints! {
         getActiveLoansCount => get_active_loans_count
         set_operation_timelock => set_operation_timelock
         getOperationTimelock => get_operation_timelock
+        proposeParameterChange => propose_parameter_change
+        executeParameterChange => execute_parameter_change
+        cancelParameterChange => cancel_parameter_change
+        getPendingParameterChanges => get_pending_parameter_changes
+        proposeConfigChange => propose_config_change
+        executeConfigChange => execute_config_change
+        cancelConfigChange => cancel_config_change
+        getPendingConfigChanges => get_pending_config_changes
         getLoanDetails => get_loan_details
+        quoteLoan => quote_loan
         calculateDueDate => calculate_due_date
         getLoanTermDays => get_loan_term_days
         calculateInterestRateForTerm => get_interest_rate_for_term
@@ -104,6 +204,24 @@ multiversx_sc_wasm_adapter::endpoints! {
         getUserLoanHistory => get_user_loan_history
         getUserActiveLoans => get_user_active_loans
         getUserRepaidLoans => get_user_repaid_loans
+        getLoans => get_loans
+        getUserLoansDetailed => get_user_loans_detailed
+        getUserLoanCount => get_user_loan_count
+        grantRole => grant_role
+        revokeRole => revoke_role
+        hasRole => has_role
+        getRoleMembers => get_role_members
+        setupMultisig => setup_multisig
+        proposeAction => propose_action
+        approveAction => approve_action
+        revokeApproval => revoke_approval
+        discardAction => discard_action
+        getMultisigSigners => get_multisig_signers
+        getMultisigQuorum => get_multisig_quorum
+        getProposal => get_proposal
+        getActionApprovals => get_action_approvals
+        isQuorumReached => is_quorum_reached
+        getStorageVersion => get_storage_version
     )
 }
 
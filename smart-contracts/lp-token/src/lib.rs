@@ -4,6 +4,11 @@
 multiversx_sc::imports!();
 multiversx_sc::derive_imports!();
 
+use common_types::access_control::Role;
+
+// Versão atual do layout de armazenamento (ver StorageVersionModule)
+const STORAGE_VERSION: u32 = 1;
+
 /// Estrutura que armazena as informações básicas do token
 /// - name: Nome do token
 /// - ticker: Símbolo/código do token (ex: BTC, ETH)
@@ -19,7 +24,9 @@ pub struct TokenInfo <M: ManagedTypeApi> {
 /// Contrato principal de token
 /// Implementa funcionalidades similares ao padrão ERC-20 do Ethereum
 #[multiversx_sc::contract]
-pub trait LpToken {
+pub trait LpToken:
+    common_types::access_control::AccessControlModule + common_types::storage_version::StorageVersionModule
+{
     /// Função de inicialização que é chamada uma única vez quando o contrato é deployado
     /// @param initial_supply: Quantidade inicial de tokens a ser criada
     /// @param token_name: Nome completo do token (ex: "Bitcoin")
@@ -48,6 +55,14 @@ pub trait LpToken {
         // Inicializa configurações padrão do contrato
         self.paused().set(false);  // Contrato começa ativo (não pausado)
         self.fee_percentage().set(0u64);  // Sem taxa de transferência inicialmente (0%)
+        self.init_storage_version(STORAGE_VERSION);
+    }
+
+    // Executa as migrações pendentes em ordem; cada uma roda uma única vez
+    #[upgrade]
+    fn upgrade(&self) {
+        // v1: layout inicial, nada a converter
+        self.run_storage_migrations(STORAGE_VERSION, |_| {});
     }
 
     // ======== FUNÇÕES DE VISUALIZAÇÃO (VIEW) ========
@@ -158,21 +173,21 @@ pub trait LpToken {
 
     // ======== FUNÇÕES DE MINT (CRIAR) E BURN (DESTRUIR) TOKENS ========
 
-    /// Cria novos tokens e os atribui a um endereço (somente owner)
+    /// Cria novos tokens e os atribui a um endereço (owner ou Admin)
     /// @param to: Endereço que receberá os novos tokens
     /// @param amount: Quantidade de tokens a criar
-    #[only_owner]
     #[endpoint(mint)]
     fn mint_endpoint(&self, to: &ManagedAddress, amount: &BigUint) -> () {
+        self.require_role(Role::Admin);
         self.mint(to, amount);
     }
 
-    /// Destrói tokens de um endereço específico (somente owner)
+    /// Destrói tokens de um endereço específico (owner ou Admin)
     /// @param address: Endereço de onde os tokens serão destruídos
     /// @param amount: Quantidade de tokens a destruir
-    #[only_owner]
     #[endpoint(burn)]
     fn burn_endpoint(&self, address: &ManagedAddress, amount: &BigUint) -> () {
+        self.require_role(Role::Admin);
         self.burn(address, amount)
     }
 
@@ -186,18 +201,18 @@ pub trait LpToken {
 
     // ======== FUNÇÕES DE CONTROLE DO CONTRATO ========
 
-    /// Pausa todas as operações do contrato (somente owner)
+    /// Pausa todas as operações do contrato (owner ou Pauser)
     /// Útil em caso de emergência ou manutenção
-    #[only_owner]
     #[endpoint(pause)]
     fn pause(&self) -> () {
+        self.require_role(Role::Pauser);
         self.paused().set(true);
     }
 
-    /// Despausa o contrato, permitindo que as operações voltem ao normal (somente owner)
-    #[only_owner]
+    /// Despausa o contrato, permitindo que as operações voltem ao normal (owner ou Pauser)
     #[endpoint(unpause)]
     fn unpause(&self) -> () {
+        self.require_role(Role::Pauser);
         self.paused().set(false);
     }
 
@@ -209,14 +224,12 @@ pub trait LpToken {
 
     // ======== GERENCIAMENTO DE TAXAS ========
 
-    /// Define a porcentagem da taxa cobrada em transferências (somente owner)
+    /// Define a porcentagem da taxa cobrada em transferências (owner ou RiskManager)
     /// @param fee_percentage: Valor da taxa em basis points (1% = 100, 0.5% = 50)
-    #[only_owner]
     #[endpoint(setFeePercentage)]
     fn set_fee_percentage(&self, fee_percentage: u64) {
-        let caller = self.blockchain().get_caller();
-        require!(caller == self.blockchain().get_owner_address(), "unauthorized");
-    
+        self.require_role(Role::RiskManager);
+
         require!(fee_percentage <= 10000, "fee percentage too high");
         self.fee_percentage().set(fee_percentage);
     }
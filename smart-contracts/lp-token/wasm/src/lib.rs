@@ -5,9 +5,10 @@
 ////////////////////////////////////////////////////
 
 // Init:                                 1
-// Endpoints:                           18
+// Upgrade:                              1
+// Endpoints:                           23
 // Async Callback (empty):               1
-// Total number of exported functions:  20
+// Total number of exported functions:  26
 
 #![no_std]
 
@@ -18,6 +19,7 @@ multiversx_sc_wasm_adapter::endpoints! {
     lp_token
     (
         init => init
+        upgrade => upgrade
         getName => get_name
         getTicker => get_ticker
         getDecimals => get_decimals
@@ -36,6 +38,11 @@ multiversx_sc_wasm_adapter::endpoints! {
         getFeePercentage => get_fee_percentage
         approve => approve
         publicMint => public_mint
+        grantRole => grant_role
+        revokeRole => revoke_role
+        hasRole => has_role
+        getRoleMembers => get_role_members
+        getStorageVersion => get_storage_version
     )
 }
 
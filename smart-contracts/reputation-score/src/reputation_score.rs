@@ -7,35 +7,94 @@
 #![no_std]
 multiversx_sc::imports!();
 
+use common_types::access_control::Role;
+use common_types::multisig::MultisigAction;
+use common_types::CreditDecision;
+
+// Resultados de empréstimo reportados pelo LoanController (evento loan_outcome_reported)
+pub const LOAN_OUTCOME_REPAID: u8 = 0;
+pub const LOAN_OUTCOME_LATE: u8 = 1;
+pub const LOAN_OUTCOME_DEFAULTED: u8 = 2;
+pub const LOAN_OUTCOME_GUARANTEED: u8 = 3;
+
+// Versão atual do layout de armazenamento (ver StorageVersionModule)
+const STORAGE_VERSION: u32 = 1;
+
 #[multiversx_sc::contract]
-pub trait ReputationScore {
+pub trait ReputationScore:
+    common_types::access_control::AccessControlModule
+    + common_types::multisig::MultisigModule
+    + common_types::clock::ClockModule
+    + common_types::storage_version::StorageVersionModule
+{
     // Inicializa o contrato com valores mínimos e máximos para a pontuação
     #[init]
     fn init(&self, min_score: u64, max_score: u64) {
         require!(min_score < max_score, "Min score must be less than max score");
         self.min_score().set(min_score);
         self.max_score().set(max_score);
+
+        self.set_default_outcome_weights();
+        self.init_storage_version(STORAGE_VERSION);
+    }
+
+    // Executa as migrações pendentes em ordem; cada uma roda uma única vez
+    #[upgrade]
+    fn upgrade(&self) {
+        self.run_storage_migrations(STORAGE_VERSION, |version| {
+            // v1: contratos implantados antes dos pesos configuráveis recebem os padrões do init
+            if version == 1 {
+                self.set_default_outcome_weights();
+            }
+        });
+    }
+
+    // Pesos padrão dos resultados de empréstimos (valores já configurados são mantidos)
+    fn set_default_outcome_weights(&self) {
+        self.repayment_bonus().set_if_empty(10u64);
+        self.late_payment_penalty_per_day().set_if_empty(2u64);
+        self.default_penalty().set_if_empty(100u64);
+        self.guarantor_bonus().set_if_empty(5u64);
     }
 
     // Define o endereço do oráculo autorizado a atualizar pontuações
-    #[only_owner]
     #[endpoint(setOracleAddress)]
     fn set_oracle_address(&self, oracle_address: ManagedAddress) {
+        self.require_role(Role::Admin);
+        self.require_multisig_not_configured();
+        self.apply_oracle_address(oracle_address);
+    }
+
+    fn apply_oracle_address(&self, oracle_address: ManagedAddress) {
         require!(!oracle_address.is_zero(), "Oracle address cannot be zero");
         self.oracle_address().set(oracle_address);
     }
 
-    // Atualiza a pontuação de reputação de um usuário (somente oráculo)
+    // Executa uma proposta do multisig que atingiu o quórum
+    #[endpoint(performAction)]
+    fn perform_action(&self, action_id: u64) {
+        let action = self.take_approved_action(action_id);
+        if self.perform_signer_action(&action) {
+            return;
+        }
+
+        match action {
+            MultisigAction::SetOracle { address } => self.apply_oracle_address(address),
+            _ => sc_panic!("Ação não suportada por este contrato"),
+        }
+    }
+
+    // Atualiza a pontuação de reputação de um usuário (oráculo configurado ou papel Oracle)
     #[endpoint(updateScore)]
     fn update_score(&self, user_address: ManagedAddress, score: u64) {
-        // Verifica se o oráculo já foi configurado
-        require!(!self.oracle_address().is_empty(), "Oracle not configured");
+        let caller = self.blockchain().get_caller();
+        if !self.role_members(Role::Oracle).contains(&caller) {
+            // Verifica se o oráculo já foi configurado
+            require!(!self.oracle_address().is_empty(), "Oracle not configured");
 
-        // Apenas o oráculo configurado pode chamar
-        require!(
-            self.blockchain().get_caller() == self.oracle_address().get(),
-            "Only oracle can update scores"
-        );
+            // Apenas o oráculo configurado pode chamar
+            require!(caller == self.oracle_address().get(), "Only oracle can update scores");
+        }
 
         // Score deve estar no intervalo permitido
         require!(
@@ -47,6 +106,115 @@ pub trait ReputationScore {
         self.score_updated_event(user_address, score);
     }
 
+    // Define o endereço do LoanController autorizado a reportar resultados de empréstimos
+    #[endpoint(setLoanControllerAddress)]
+    fn set_loan_controller_address(&self, loan_controller_address: ManagedAddress) {
+        self.require_role(Role::Admin);
+        require!(!loan_controller_address.is_zero(), "Loan controller address cannot be zero");
+        self.loan_controller_address().set(loan_controller_address);
+    }
+
+    // Define os pesos aplicados aos resultados reportados pelo LoanController
+    #[endpoint(setScoreWeights)]
+    fn set_score_weights(
+        &self,
+        repayment_bonus: u64,                 // Pontos somados por empréstimo pago em dia
+        late_payment_penalty_per_day: u64,    // Pontos subtraídos por dia de atraso
+        default_penalty: u64,                 // Pontos subtraídos por inadimplência
+    ) {
+        self.require_role(Role::RiskManager);
+        let range = self.max_score().get() - self.min_score().get();
+        require!(
+            repayment_bonus <= range && default_penalty <= range,
+            "Weight exceeds score range"
+        );
+        self.repayment_bonus().set(repayment_bonus);
+        self.late_payment_penalty_per_day().set(late_payment_penalty_per_day);
+        self.default_penalty().set(default_penalty);
+    }
+
+    // Pagamento em dia: soma o bônus de pagamento (somente LoanController)
+    #[endpoint(updateScoreAfterRepayment)]
+    fn update_score_after_repayment(&self, user_address: ManagedAddress, amount: BigUint) {
+        self.require_loan_controller();
+
+        let score = self.get_user_score(user_address.clone()) + self.repayment_bonus().get();
+        self.set_clamped_score(&user_address, score);
+        self.loan_outcome_event(&user_address, LOAN_OUTCOME_REPAID, &amount);
+    }
+
+    // Pagamento em atraso: subtrai a penalidade proporcional aos dias de atraso (somente LoanController)
+    #[endpoint(updateScoreAfterLatePayment)]
+    fn update_score_after_late_payment(&self, user_address: ManagedAddress, amount: BigUint, delay_days: u64) {
+        self.require_loan_controller();
+
+        let penalty = self.late_payment_penalty_per_day().get().saturating_mul(delay_days);
+        let score = self.get_user_score(user_address.clone()).saturating_sub(penalty);
+        self.set_clamped_score(&user_address, score);
+        self.loan_outcome_event(&user_address, LOAN_OUTCOME_LATE, &amount);
+    }
+
+    // Inadimplência: subtrai a penalidade de default (somente LoanController)
+    #[endpoint(updateScoreAfterDefault)]
+    fn update_score_after_default(&self, user_address: ManagedAddress, amount: BigUint) {
+        self.require_loan_controller();
+
+        let score = self.get_user_score(user_address.clone()).saturating_sub(self.default_penalty().get());
+        self.set_clamped_score(&user_address, score);
+        self.loan_outcome_event(&user_address, LOAN_OUTCOME_DEFAULTED, &amount);
+    }
+
+    // Fiador de empréstimo quitado: soma o bônus de fiança (somente LoanController)
+    #[endpoint(updateScoreAfterGuarantee)]
+    fn update_score_after_guarantee(&self, user_address: ManagedAddress, amount: BigUint) {
+        self.require_loan_controller();
+
+        let score = self.get_user_score(user_address.clone()) + self.guarantor_bonus().get();
+        self.set_clamped_score(&user_address, score);
+        self.loan_outcome_event(&user_address, LOAN_OUTCOME_GUARANTEED, &amount);
+    }
+
+    // Define os pontos somados aos fiadores de empréstimos quitados
+    #[endpoint(setGuarantorBonus)]
+    fn set_guarantor_bonus(&self, bonus: u64) {
+        self.require_role(Role::RiskManager);
+        require!(
+            bonus <= self.max_score().get() - self.min_score().get(),
+            "Weight exceeds score range"
+        );
+        self.guarantor_bonus().set(bonus);
+    }
+
+    #[view(getGuarantorBonus)]
+    fn get_guarantor_bonus(&self) -> u64 {
+        self.guarantor_bonus().get()
+    }
+
+    #[view(getScoreWeights)]
+    fn get_score_weights(&self) -> MultiValue3<u64, u64, u64> {
+        (
+            self.repayment_bonus().get(),
+            self.late_payment_penalty_per_day().get(),
+            self.default_penalty().get(),
+        )
+            .into()
+    }
+
+    fn require_loan_controller(&self) {
+        require!(!self.loan_controller_address().is_empty(), "Loan controller not configured");
+        require!(
+            self.blockchain().get_caller() == self.loan_controller_address().get(),
+            "Only loan controller can report loan outcomes"
+        );
+    }
+
+    // Grava a pontuação limitada ao intervalo [min_score, max_score]
+    fn set_clamped_score(&self, user_address: &ManagedAddress, score: u64) {
+        let clamped = score.max(self.min_score().get()).min(self.max_score().get());
+        self.user_score(user_address.clone()).set(clamped);
+        self.score_updated_event(user_address.clone(), clamped);
+    }
+
     // Retorna a pontuação do usuário, ou o valor mínimo se ainda não houver
     #[view(getUserScore)]
     fn get_user_score(&self, user_address: ManagedAddress) -> u64 {
@@ -77,10 +245,40 @@ pub trait ReputationScore {
         base_amount * user_score_big * 2u32 / max_score_big
     }
 
+    // Decisão de crédito em uma só consulta: elegibilidade, valor máximo e
+    // pontuação, para o LoanController não encadear três chamadas
+    #[view(getCreditDecision)]
+    fn get_credit_decision(
+        &self,
+        user_address: ManagedAddress,
+        requested_amount: BigUint,
+        min_score: u64,
+        base_amount: BigUint,
+    ) -> CreditDecision<Self::Api> {
+        let score = self.get_user_score(user_address.clone());
+        let max_amount = self.calculate_max_loan_amount(user_address, base_amount);
+
+        CreditDecision {
+            eligible: score >= min_score,
+            within_limit: requested_amount <= max_amount,
+            max_amount,
+            score,
+        }
+    }
+
     // Evento de atualização de score
     #[event("score_updated")]
     fn score_updated_event(&self, #[indexed] user_address: ManagedAddress, #[indexed] score: u64);
 
+    // Evento de resultado de empréstimo reportado pelo LoanController
+    #[event("loan_outcome_reported")]
+    fn loan_outcome_event(
+        &self,
+        #[indexed] user_address: &ManagedAddress,
+        #[indexed] outcome: u8,
+        amount: &BigUint,
+    );
+
     // --- Storage mappers ---
     #[storage_mapper("oracle_address")]
     fn oracle_address(&self) -> SingleValueMapper<ManagedAddress>;
@@ -93,4 +291,19 @@ pub trait ReputationScore {
 
     #[storage_mapper("user_score")]
     fn user_score(&self, user_address: ManagedAddress) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("loan_controller_address")]
+    fn loan_controller_address(&self) -> SingleValueMapper<ManagedAddress>;
+
+    #[storage_mapper("repayment_bonus")]
+    fn repayment_bonus(&self) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("late_payment_penalty_per_day")]
+    fn late_payment_penalty_per_day(&self) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("default_penalty")]
+    fn default_penalty(&self) -> SingleValueMapper<u64>;
+
+    #[storage_mapper("guarantor_bonus")]
+    fn guarantor_bonus(&self) -> SingleValueMapper<u64>;
 }
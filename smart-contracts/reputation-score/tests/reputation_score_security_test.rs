@@ -71,7 +71,7 @@ fn test_access_control_owner_functions() {
         .execute_tx(&setup.attacker_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
             sc.set_oracle_address(managed_address!(&setup.attacker_address));
         })
-        .assert_error(4, "Endpoint can only be called by owner");
+        .assert_error(4, "Caller does not have the required role");
     
     // Verificar que o oráculo não foi alterado
     setup.blockchain_wrapper
@@ -6,6 +6,7 @@ use multiversx_sc_scenario::{
 };
 
 use reputation_score::*;
+use common_types::storage_version::StorageVersionModule;
 
 const WASM_PATH: &str = "output/reputation-score.wasm";
 
@@ -14,6 +15,7 @@ where
     ContractObjBuilder: 'static + Copy + Fn() -> reputation_score::ContractObj<DebugApi>,
 {
     pub blockchain_wrapper: BlockchainStateWrapper,
+    pub owner_address: Address,
     pub oracle_address: Address,
     pub user_address: Address,
     pub contract_wrapper: ContractObjWrapper<reputation_score::ContractObj<DebugApi>, ContractObjBuilder>,
@@ -55,6 +57,7 @@ where
     
     ContractSetup {
         blockchain_wrapper,
+        owner_address,
         oracle_address,
         user_address,
         contract_wrapper,
@@ -90,7 +93,7 @@ fn test_set_oracle_address() {
         .execute_tx(&setup.user_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
             sc.set_oracle_address(managed_address!(&setup.user_address));
         })
-        .assert_error(4, "Endpoint can only be called by owner");
+        .assert_error(4, "Caller does not have the required role");
 }
 
 #[test]
@@ -218,4 +221,157 @@ fn test_calculate_max_loan_amount() {
             assert_eq!(max_loan, managed_biguint!(2000u64));
         })
         .assert_ok();
-}
\ No newline at end of file
+}
+#[test]
+fn test_get_credit_decision() {
+    let mut setup = setup_contract(reputation_score::contract_obj);
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.oracle_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.update_score(managed_address!(&setup.user_address), 600u64);
+        })
+        .assert_ok();
+
+    // Uma única consulta devolve elegibilidade, valor máximo e pontuação:
+    // 1000 * (600/1000) * 2 = 1200
+    setup.blockchain_wrapper
+        .execute_query(&setup.contract_wrapper, |sc| {
+            let decision = sc.get_credit_decision(
+                managed_address!(&setup.user_address),
+                managed_biguint!(1200u64),
+                500u64,
+                managed_biguint!(1000u64),
+            );
+            assert!(decision.eligible);
+            assert!(decision.within_limit);
+            assert_eq!(decision.max_amount, managed_biguint!(1200u64));
+            assert_eq!(decision.score, 600u64);
+
+            // Valor acima do máximo e pontuação mínima acima da do usuário
+            let decision = sc.get_credit_decision(
+                managed_address!(&setup.user_address),
+                managed_biguint!(1201u64),
+                700u64,
+                managed_biguint!(1000u64),
+            );
+            assert!(!decision.eligible);
+            assert!(!decision.within_limit);
+            assert_eq!(decision.score, 600u64);
+        })
+        .assert_ok();
+}
+
+#[test]
+fn test_loan_outcome_updates() {
+    let mut setup = setup_contract(reputation_score::contract_obj);
+    let controller_address = setup.blockchain_wrapper.create_user_account(&rust_biguint!(0));
+
+    // Sem controlador configurado, nenhum resultado é aceito
+    setup.blockchain_wrapper
+        .execute_tx(&controller_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.update_score_after_repayment(managed_address!(&setup.user_address), managed_biguint!(1000));
+        })
+        .assert_user_error("Loan controller not configured");
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_loan_controller_address(managed_address!(&controller_address));
+            sc.set_score_weights(20u64, 5u64, 300u64);
+        })
+        .assert_ok();
+
+    // Somente o controlador pode reportar
+    setup.blockchain_wrapper
+        .execute_tx(&setup.oracle_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.update_score_after_default(managed_address!(&setup.user_address), managed_biguint!(1000));
+        })
+        .assert_user_error("Only loan controller can report loan outcomes");
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.oracle_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.update_score(managed_address!(&setup.user_address), 500u64);
+        })
+        .assert_ok();
+
+    // Em dia: +20; 3 dias de atraso: -15; inadimplência: -300
+    setup.blockchain_wrapper
+        .execute_tx(&controller_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            let user = managed_address!(&setup.user_address);
+            sc.update_score_after_repayment(user.clone(), managed_biguint!(1000));
+            assert_eq!(sc.get_user_score(user.clone()), 520u64);
+            sc.update_score_after_late_payment(user.clone(), managed_biguint!(1000), 3u64);
+            assert_eq!(sc.get_user_score(user.clone()), 505u64);
+            sc.update_score_after_default(user.clone(), managed_biguint!(1000));
+            assert_eq!(sc.get_user_score(user.clone()), 205u64);
+            // A pontuação nunca fica abaixo do mínimo
+            sc.update_score_after_default(user.clone(), managed_biguint!(1000));
+            assert_eq!(sc.get_user_score(user), 0u64);
+        })
+        .assert_ok();
+
+    // Pesos maiores que o intervalo de pontuação são rejeitados
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_score_weights(20u64, 5u64, 5000u64);
+        })
+        .assert_user_error("Weight exceeds score range");
+}
+
+// Fiadores de empréstimos quitados recebem o bônus de fiança
+#[test]
+fn test_guarantor_bonus() {
+    let mut setup = setup_contract(reputation_score::contract_obj);
+    let controller_address = setup.blockchain_wrapper.create_user_account(&rust_biguint!(0));
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            assert_eq!(sc.get_guarantor_bonus(), 5u64);
+            sc.set_loan_controller_address(managed_address!(&controller_address));
+            sc.set_guarantor_bonus(15u64);
+        })
+        .assert_ok();
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.set_guarantor_bonus(5000u64);
+        })
+        .assert_user_error("Weight exceeds score range");
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.oracle_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.update_score_after_guarantee(managed_address!(&setup.user_address), managed_biguint!(500));
+        })
+        .assert_user_error("Only loan controller can report loan outcomes");
+    setup.blockchain_wrapper
+        .execute_tx(&controller_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            let user = managed_address!(&setup.user_address);
+            let before = sc.get_user_score(user.clone());
+            sc.update_score_after_guarantee(user.clone(), managed_biguint!(500));
+            assert_eq!(sc.get_user_score(user), before + 15u64);
+        })
+        .assert_ok();
+}
+
+// Upgrade de um contrato anterior ao versionamento: pesos ausentes recebem os
+// padrões do init e os já configurados são mantidos
+#[test]
+fn test_storage_upgrade_sets_default_weights() {
+    let mut setup = setup_contract(reputation_score::contract_obj);
+
+    setup.blockchain_wrapper
+        .execute_tx(&setup.owner_address, &setup.contract_wrapper, &rust_biguint!(0), |sc| {
+            sc.storage_version().clear();
+            sc.repayment_bonus().clear();
+            sc.late_payment_penalty_per_day().clear();
+            sc.default_penalty().clear();
+            sc.guarantor_bonus().set(15u64);
+
+            sc.upgrade();
+
+            assert_eq!(sc.get_storage_version(), 1u32);
+            assert_eq!(sc.repayment_bonus().get(), 10u64);
+            assert_eq!(sc.late_payment_penalty_per_day().get(), 2u64);
+            assert_eq!(sc.default_penalty().get(), 100u64);
+            assert_eq!(sc.get_guarantor_bonus(), 15u64);
+        })
+        .assert_ok();
+}
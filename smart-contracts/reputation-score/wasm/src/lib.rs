@@ -5,9 +5,10 @@
 ////////////////////////////////////////////////////
 
 // Init:                                 1
-// Endpoints:                            5
+// Upgrade:                              1
+// Endpoints:                           31
 // Async Callback (empty):               1
-// Total number of exported functions:   7
+// Total number of exported functions:  34
 
 #![no_std]
 
@@ -18,11 +19,38 @@ multiversx_sc_wasm_adapter::endpoints! {
     reputation_score
     (
         init => init
+        upgrade => upgrade
         setOracleAddress => set_oracle_address
+        performAction => perform_action
         updateScore => update_score
+        setLoanControllerAddress => set_loan_controller_address
+        setScoreWeights => set_score_weights
+        updateScoreAfterRepayment => update_score_after_repayment
+        updateScoreAfterLatePayment => update_score_after_late_payment
+        updateScoreAfterDefault => update_score_after_default
+        updateScoreAfterGuarantee => update_score_after_guarantee
+        setGuarantorBonus => set_guarantor_bonus
+        getGuarantorBonus => get_guarantor_bonus
+        getScoreWeights => get_score_weights
         getUserScore => get_user_score
         isEligibleForLoan => is_eligible_for_loan
         calculateMaxLoanAmount => calculate_max_loan_amount
+        getCreditDecision => get_credit_decision
+        grantRole => grant_role
+        revokeRole => revoke_role
+        hasRole => has_role
+        getRoleMembers => get_role_members
+        setupMultisig => setup_multisig
+        proposeAction => propose_action
+        approveAction => approve_action
+        revokeApproval => revoke_approval
+        discardAction => discard_action
+        getMultisigSigners => get_multisig_signers
+        getMultisigQuorum => get_multisig_quorum
+        getProposal => get_proposal
+        getActionApprovals => get_action_approvals
+        isQuorumReached => is_quorum_reached
+        getStorageVersion => get_storage_version
     )
 }
 